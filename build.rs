@@ -7,7 +7,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::fs::create_dir_all(proto_dir)?;
         
         tonic_build::configure()
-            .build_server(false)
+            .build_server(true)
             .build_client(true)
             .out_dir(proto_dir)
             .compile(&["proto/backend.proto"], &["proto/"])?;
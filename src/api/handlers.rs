@@ -2,21 +2,46 @@
 
 use crate::api::models::{
     AddBackendRequest, BackendHealthSummary, BackendInfo, BackendListResponse,
-    GenerateImageRequest, GenerateImageResponse, HealthResponse, ImageData, SuccessResponse,
+    BackendStrategyInfo, CircuitBreakerInfo, GenerateImageRequest, GenerateImageResponse, HealthResponse, ImageData,
+    ImageJobResponse,
+    SetBackendEnabledRequest, SetStrategyRequest, StrategyResponse, SuccessResponse, UpdateBackendRequest,
 };
+use crate::api::multipart::MultipartForm;
+use crate::backend::multi_backend::register_multi_backend;
 use crate::backend::traits::GenerateRequest as BackendGenerateRequest;
 use crate::config::{
-    BackendConfig, BackendType, ProtocolType, BackendAuth, BackendHealthCheck, BackendLoadBalancer,
+    BackendConfig, BackendType, ProtocolType, BackendAuth, BackendHealthCheck, BackendLoadBalancer, Settings,
 };
 use crate::error::AppError;
+use crate::gateway::load_balancer::LoadBalancingStrategy;
+use crate::metrics::{model_label, RequestLabels};
+use crate::response::base64;
+use crate::response::file::detect_image_format;
 use crate::AppState;
 use axum::{
-    extract::{Path, State},
-    Json,
+    extract::{Multipart, Path, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
 };
 use chrono::Utc;
+use futures::stream;
+use std::convert::Infallible;
 use std::sync::Arc;
-use tracing::info;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Response header naming the backend that served a request
+pub const BACKEND_HEADER: &str = "x-gateway-backend";
+
+/// Body size limit for image upload endpoints
+pub const IMAGE_UPLOAD_LIMIT: usize = 16 * 1024 * 1024;
+
+/// How often a job is checked for changes while streaming its events
+const JOB_EVENT_INTERVAL: Duration = Duration::from_millis(500);
 
 /// Generate images from a prompt
 ///
@@ -26,7 +51,8 @@ use tracing::info;
     path = "/v1/images/generations",
     request_body = GenerateImageRequest,
     responses(
-        (status = 200, description = "Images generated successfully", body = GenerateImageResponse),
+        (status = 200, description = "Images generated successfully", body = GenerateImageResponse,
+            headers(("x-gateway-backend" = String, description = "Backend that served the request"))),
         (status = 400, description = "Invalid request"),
         (status = 500, description = "Internal server error"),
     ),
@@ -34,14 +60,263 @@ use tracing::info;
 )]
 pub async fn generate_image(
     State(state): State<Arc<AppState>>,
+    Extension(labels): Extension<RequestLabels>,
     Json(request): Json<GenerateImageRequest>,
-) -> Result<Json<GenerateImageResponse>, AppError> {
+) -> Result<Response, AppError> {
     info!(prompt = %request.prompt, n = request.n, "Received image generation request");
+    request.validate()?;
+
+    let backend_request = backend_request(&request);
+    submit_image_request(&state, &labels, backend_request, request.backend.as_deref()).await
+}
+
+/// Edit an image
+///
+/// Repaints an uploaded image from a prompt, limited to the transparent areas
+/// of the mask when one is given. OpenAI API compatible; routed only to
+/// backends with the `edit` capability.
+#[utoipa::path(
+    post,
+    path = "/v1/images/edits",
+    request_body(content = ImageEditForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Images edited successfully", body = GenerateImageResponse,
+            headers(("x-gateway-backend" = String, description = "Backend that served the request"))),
+        (status = 400, description = "Invalid request or no edit-capable backend"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "Images"
+)]
+pub async fn edit_image(
+    State(state): State<Arc<AppState>>,
+    Extension(labels): Extension<RequestLabels>,
+    multipart: Multipart,
+) -> Result<Response, AppError> {
+    let form = MultipartForm::read(multipart).await?;
+    let image = form
+        .get("image")
+        .ok_or_else(|| AppError::InvalidRequest("Missing 'image' file".to_string()))?;
+    let request = form_request(form_fields(&form)?)?;
+
+    info!(prompt = %request.prompt, n = request.n, masked = form.get("mask").is_some(), "Received image edit request");
+
+    let mut backend_request = backend_request(&request);
+    backend_request.image = Some(base64::encode(&image.data));
+    backend_request.mask = form.get("mask").map(|mask| base64::encode(&mask.data));
+    submit_image_request(&state, &labels, backend_request, request.backend.as_deref()).await
+}
 
+/// Generate variations of an image
+///
+/// Creates new images resembling an uploaded PNG, JPEG, GIF, WebP or BMP
+/// image. OpenAI API compatible; routed only to backends with the
+/// `variations` capability.
+#[utoipa::path(
+    post,
+    path = "/v1/images/variations",
+    request_body(content = ImageVariationForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Variations generated successfully", body = GenerateImageResponse,
+            headers(("x-gateway-backend" = String, description = "Backend that served the request"))),
+        (status = 400, description = "Invalid request, unsupported image, or no variations-capable backend"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "Images"
+)]
+pub async fn create_image_variation(
+    State(state): State<Arc<AppState>>,
+    Extension(labels): Extension<RequestLabels>,
+    multipart: Multipart,
+) -> Result<Response, AppError> {
+    let form = MultipartForm::read(multipart).await?;
+    let image = form
+        .get("image")
+        .ok_or_else(|| AppError::InvalidRequest("Missing 'image' file".to_string()))?;
+    let format = detect_image_format(&image.data)
+        .ok_or_else(|| AppError::InvalidRequest("'image' is not a supported image format".to_string()))?;
+
+    // Variations take no prompt
+    let mut fields = form_fields(&form)?;
+    fields.insert("prompt".to_string(), String::new().into());
+    let request = form_request(fields)?;
+
+    info!(n = request.n, format = format, "Received image variation request");
+
+    let mut backend_request = backend_request(&request);
+    backend_request.image = Some(base64::encode(&image.data));
+    backend_request.variation = true;
+    submit_image_request(&state, &labels, backend_request, request.backend.as_deref()).await
+}
+
+/// Submit an image generation job
+///
+/// Queues the request and returns a job id immediately instead of waiting for
+/// the images. Poll `GET /v1/images/jobs/{id}` for the result.
+#[utoipa::path(
+    post,
+    path = "/v1/images/jobs",
+    request_body = GenerateImageRequest,
+    responses(
+        (status = 202, description = "Job queued", body = ImageJobResponse),
+        (status = 400, description = "Invalid request"),
+        (status = 500, description = "Queue full or internal error"),
+    ),
+    tag = "Images"
+)]
+pub async fn create_image_job(
+    State(state): State<Arc<AppState>>,
+    Extension(labels): Extension<RequestLabels>,
+    Json(request): Json<GenerateImageRequest>,
+) -> Result<(StatusCode, Json<ImageJobResponse>), AppError> {
+    request.validate()?;
+    if let Some(model) = &request.model {
+        labels.set_model(model_label(model, state.load_balancer.is_configured_model(model)));
+    }
+
+    let job = state
+        .request_queue
+        .submit_job(backend_request(&request), request.backend.as_deref())
+        .await?;
+
+    info!(job = %job.id, prompt = %request.prompt, n = request.n, "Image job queued");
+    Ok((StatusCode::ACCEPTED, Json(job.into())))
+}
+
+/// Get an image job
+///
+/// Returns the job status, and the images once it has succeeded. Finished
+/// jobs are kept for the configured retention period.
+#[utoipa::path(
+    get,
+    path = "/v1/images/jobs/{id}",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job status", body = ImageJobResponse),
+        (status = 404, description = "Unknown or expired job"),
+    ),
+    tag = "Images"
+)]
+pub async fn get_image_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ImageJobResponse>, AppError> {
+    Ok(Json(job_snapshot(&state, &id)?))
+}
+
+/// Follow an image job
+///
+/// Streams the job as server-sent events until it finishes. Each event is
+/// named after the job status and carries the job: `queued` events report the
+/// queue position, `running` events the backend progress (step, ETA and a
+/// preview image where the backend provides them), and the final event the
+/// result. Events are only sent when the job changes.
+#[utoipa::path(
+    get,
+    path = "/v1/images/jobs/{id}/events",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job updates", body = ImageJobResponse, content_type = "text/event-stream"),
+        (status = 404, description = "Unknown or expired job"),
+    ),
+    tag = "Images"
+)]
+pub async fn image_job_events(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, AppError> {
+    // Unknown jobs fail before the stream opens
+    job_snapshot(&state, &id)?;
+
+    let events = stream::unfold(Some((state, id, None)), |cursor| async move {
+        let (state, id, last) = cursor?;
+        loop {
+            // The job may expire while followed; end the stream then
+            let job = job_snapshot(&state, &id).ok()?;
+            let data = serde_json::to_string(&job).ok()?;
+            if last.as_ref() != Some(&data) {
+                let event = Event::default().event(job.status.as_str()).data(&data);
+                let next = (!job.status.is_finished()).then_some((state, id, Some(data)));
+                return Some((Ok::<_, Infallible>(event), next));
+            }
+            tokio::time::sleep(JOB_EVENT_INTERVAL).await;
+        }
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()).into_response())
+}
+
+/// Current state of a job, with its place in the queue while queued
+fn job_snapshot(state: &AppState, id: &str) -> Result<ImageJobResponse, AppError> {
+    let job = state
+        .request_queue
+        .job(id)
+        .ok_or_else(|| AppError::JobNotFound(id.to_string()))?;
+    Ok(ImageJobResponse {
+        queue_position: state.request_queue.queue_position(id),
+        ..job.into()
+    })
+}
+
+/// Cancel an image job
+///
+/// Stops a queued or running job; finished jobs are returned unchanged.
+#[utoipa::path(
+    delete,
+    path = "/v1/images/jobs/{id}",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job after cancellation", body = ImageJobResponse),
+        (status = 404, description = "Unknown or expired job"),
+    ),
+    tag = "Images"
+)]
+pub async fn cancel_image_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ImageJobResponse>, AppError> {
+    let job = state
+        .request_queue
+        .cancel_job(&id)
+        .ok_or(AppError::JobNotFound(id))?;
+    info!(job = %job.id, status = ?job.status, "Image job cancelled");
+    Ok(Json(job.into()))
+}
+
+/// Read the text fields of a multipart image form
+///
+/// Numeric fields and `extra_params` are parsed as JSON so the form accepts
+/// the same fields as the JSON endpoints.
+fn form_fields(form: &MultipartForm) -> Result<serde_json::Map<String, serde_json::Value>, AppError> {
+    const JSON_FIELDS: &[&str] = &[
+        "n", "seed", "guidance_scale", "num_inference_steps", "denoising_strength", "controls", "extra_params",
+    ];
+
+    let mut fields = serde_json::Map::new();
+    for (name, value) in form.text_fields()? {
+        let value = if JSON_FIELDS.contains(&name.as_str()) {
+            serde_json::from_str(&value)
+                .map_err(|_| AppError::InvalidRequest(format!("Invalid value for '{}'", name)))?
+        } else {
+            serde_json::Value::String(value)
+        };
+        fields.insert(name, value);
+    }
+    Ok(fields)
+}
+
+/// Build a request from multipart form fields
+fn form_request(fields: serde_json::Map<String, serde_json::Value>) -> Result<GenerateImageRequest, AppError> {
+    let request: GenerateImageRequest =
+        serde_json::from_value(fields.into()).map_err(|e| AppError::InvalidRequest(e.to_string()))?;
+    request.validate()?;
+    Ok(request)
+}
+
+/// Convert an API request into a backend request
+fn backend_request(request: &GenerateImageRequest) -> BackendGenerateRequest {
     let (width, height) = request.parse_size();
 
-    // Create backend request
-    let backend_request = BackendGenerateRequest {
+    BackendGenerateRequest {
         prompt: request.prompt.clone(),
         negative_prompt: request.negative_prompt.clone(),
         n: request.n,
@@ -51,29 +326,43 @@ pub async fn generate_image(
         seed: request.seed,
         guidance_scale: request.guidance_scale,
         num_inference_steps: request.num_inference_steps,
+        quality: request.quality.clone(),
+        style: request.style.clone(),
         response_format: request.response_format.clone(),
-    };
+        extra_params: request.extra_params.clone(),
+        image: None,
+        mask: None,
+        variation: false,
+        init_image: request.init_image.clone(),
+        denoising_strength: request.denoising_strength,
+        controls: request.controls.clone(),
+    }
+}
+
+/// Run a backend request through the queue and build the API response
+async fn submit_image_request(
+    state: &AppState,
+    labels: &RequestLabels,
+    backend_request: BackendGenerateRequest,
+    backend: Option<&str>,
+) -> Result<Response, AppError> {
+    if let Some(model) = &backend_request.model {
+        labels.set_model(model_label(model, state.load_balancer.is_configured_model(model)));
+    }
 
     // Submit request to the queue for processing
     let response = state
         .request_queue
-        .submit(backend_request, request.backend.as_deref())
+        .submit(backend_request, backend)
         .await?;
 
-    // Convert backend response to API response
-    let image_data: Vec<ImageData> = response
-        .images
-        .into_iter()
-        .map(|img| ImageData {
-            b64_json: img.b64_json,
-            url: img.url,
-            revised_prompt: img.revised_prompt,
-        })
-        .collect();
+    let backend = response.backend.clone().unwrap_or_default();
+    labels.set_backend(backend.as_str());
 
+    // Convert backend response to API response
     let api_response = GenerateImageResponse {
         created: Utc::now().timestamp(),
-        data: image_data,
+        data: response.images.into_iter().map(ImageData::from).collect(),
     };
 
     info!(
@@ -81,7 +370,7 @@ pub async fn generate_image(
         "Image generation completed"
     );
 
-    Ok(Json(api_response))
+    Ok(([(BACKEND_HEADER, backend)], Json(api_response)).into_response())
 }
 
 /// List all registered backends
@@ -109,6 +398,7 @@ pub async fn list_backends(
             healthy: b.healthy,
             weight: b.weight,
             enabled: b.enabled,
+            circuit_breakers: b.circuits.into_iter().map(CircuitBreakerInfo::from).collect(),
         })
         .collect();
 
@@ -119,7 +409,9 @@ pub async fn list_backends(
 
 /// Add a new backend dynamically
 ///
-/// Dynamically add a new image or text generation backend.
+/// Dynamically add a new image or text generation backend. Text backends go
+/// to the text registry and multi backends to both. The backend is written to
+/// backends.yaml when `management.persist_changes` is set.
 #[utoipa::path(
     post,
     path = "/v1/backends",
@@ -135,85 +427,395 @@ pub async fn add_backend(
     State(state): State<Arc<AppState>>,
     Json(request): Json<AddBackendRequest>,
 ) -> Result<Json<SuccessResponse>, AppError> {
-    info!(name = %request.name, protocol = %request.protocol, "Adding new backend");
+    info!(
+        name = %request.name,
+        backend_type = %request.backend.backend_type,
+        protocol = %request.backend.protocol,
+        "Adding new backend"
+    );
+
+    // Holding the settings lock keeps persisted changes and reloads in order
+    let mut settings = state.settings.write().await;
+    if state.backend_registry.contains(&request.name) || state.text_registry.contains(&request.name) {
+        return Err(AppError::InvalidRequest(format!(
+            "Backend '{}' already exists",
+            request.name
+        )));
+    }
+    let backend_config = backend_config(request.name.clone(), request.backend)?;
+
+    let registered = register_backend(&state, &with_defaults(&backend_config, &settings)).await;
+    if let Err(e) = registered.and_then(|_| settings.persist_backend(&backend_config)) {
+        // Keep the registries in line with the file
+        let _ = unregister_backend(&state, &backend_config.name).await;
+        return Err(e);
+    }
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        message: format!("Backend '{}' added successfully", request.name),
+    }))
+}
+
+/// Update a backend
+///
+/// Replace the configuration of a registered backend, keeping whether it is
+/// enabled. Changing its type moves it between the image and text
+/// registries. Requests already running on the old configuration complete.
+#[utoipa::path(
+    put,
+    path = "/v1/backends/{name}",
+    params(
+        ("name" = String, Path, description = "Backend name to update")
+    ),
+    request_body = UpdateBackendRequest,
+    responses(
+        (status = 200, description = "Backend updated successfully", body = SuccessResponse),
+        (status = 400, description = "Invalid request"),
+        (status = 404, description = "Backend not found"),
+    ),
+    tag = "Backends"
+)]
+pub async fn update_backend(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(request): Json<UpdateBackendRequest>,
+) -> Result<Json<SuccessResponse>, AppError> {
+    info!(name = %name, backend_type = %request.backend_type, protocol = %request.protocol, "Updating backend");
+
+    let mut settings = state.settings.write().await;
+    let enabled = backend_enabled(&state, &name)
+        .await
+        .ok_or_else(|| AppError::BackendNotFound(name.clone()))?;
+    let mut backend_config = backend_config(name.clone(), request)?;
+    backend_config.enabled = enabled;
+    let previous = running_config(&settings, &name, enabled);
 
-    // Parse protocol
+    register_backend(&state, &with_defaults(&backend_config, &settings)).await?;
+    if let Err(e) = settings.persist_backend(&backend_config) {
+        // Keep the registries in line with the file
+        match previous {
+            Some(previous) => {
+                let _ = register_backend(&state, &previous).await;
+            }
+            None => warn!(backend = %name, "Previous configuration unknown, update not rolled back"),
+        }
+        return Err(e);
+    }
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        message: format!("Backend '{}' updated successfully", name),
+    }))
+}
+
+/// Enable or disable a backend
+///
+/// A disabled backend stays registered and keeps being health checked, but
+/// receives no traffic.
+#[utoipa::path(
+    patch,
+    path = "/v1/backends/{name}",
+    params(
+        ("name" = String, Path, description = "Backend name to enable or disable")
+    ),
+    request_body = SetBackendEnabledRequest,
+    responses(
+        (status = 200, description = "Backend updated successfully", body = SuccessResponse),
+        (status = 404, description = "Backend not found"),
+    ),
+    tag = "Backends"
+)]
+pub async fn set_backend_enabled(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(request): Json<SetBackendEnabledRequest>,
+) -> Result<Json<SuccessResponse>, AppError> {
+    let mut settings = state.settings.write().await;
+    let image = state.backend_registry.get(&name);
+    let text = state.text_registry.get_backend(&name).await;
+    if image.is_none() && text.is_none() {
+        return Err(AppError::BackendNotFound(name));
+    }
+
+    if settings.persists_backends() {
+        match settings.backends.iter().find(|b| b.name == name).cloned() {
+            Some(mut config) => {
+                config.enabled = request.enabled;
+                settings.persist_backend(&config)?;
+            }
+            None => warn!(backend = %name, "Backend is not in the backends file, change not persisted"),
+        }
+    }
+    if let Some(backend) = image {
+        backend.set_enabled(request.enabled);
+    }
+    if let Some(backend) = text {
+        backend.set_enabled(request.enabled);
+    }
+    info!(backend = %name, enabled = request.enabled, "Changed backend state");
+
+    let action = if request.enabled { "enabled" } else { "disabled" };
+    Ok(Json(SuccessResponse {
+        success: true,
+        message: format!("Backend '{}' {}", name, action),
+    }))
+}
+
+/// Remove a backend
+///
+/// Remove a registered image, text or multi backend by name.
+#[utoipa::path(
+    delete,
+    path = "/v1/backends/{name}",
+    params(
+        ("name" = String, Path, description = "Backend name to remove")
+    ),
+    responses(
+        (status = 200, description = "Backend removed successfully", body = SuccessResponse),
+        (status = 404, description = "Backend not found"),
+    ),
+    tag = "Backends"
+)]
+pub async fn remove_backend(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<SuccessResponse>, AppError> {
+    info!(name = %name, "Removing backend");
+
+    let mut settings = state.settings.write().await;
+    let enabled = backend_enabled(&state, &name).await.unwrap_or(true);
+    let previous = running_config(&settings, &name, enabled);
+
+    unregister_backend(&state, &name).await?;
+    if let Err(e) = settings.persist_backend_removal(&name) {
+        // Keep the registries in line with the file
+        match previous {
+            Some(previous) => {
+                let _ = register_backend(&state, &previous).await;
+            }
+            None => warn!(backend = %name, "Previous configuration unknown, removal not rolled back"),
+        }
+        return Err(e);
+    }
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        message: format!("Backend '{}' removed successfully", name),
+    }))
+}
+
+/// Configuration a registered backend is running with, for rolling back a
+/// change that could not be persisted
+fn running_config(settings: &Settings, name: &str, enabled: bool) -> Option<BackendConfig> {
+    let mut config = settings.backends.iter().find(|b| b.name == name)?.clone();
+    config.enabled = enabled;
+    Some(config)
+}
+
+/// Fill in settings left to the configured defaults, as loading the
+/// backends file would
+///
+/// The persisted configuration keeps them unset so later default changes
+/// still apply.
+fn with_defaults(config: &BackendConfig, settings: &Settings) -> BackendConfig {
+    let mut config = config.clone();
+    config.retry_count.get_or_insert(settings.connection.retry_count);
+    config
+}
+
+/// Build and validate a backend configuration from a management API request
+fn backend_config(name: String, request: UpdateBackendRequest) -> Result<BackendConfig, AppError> {
     let protocol = match request.protocol.to_lowercase().as_str() {
         "http" => ProtocolType::Http,
         "grpc" => ProtocolType::Grpc,
         "openai" => ProtocolType::OpenAI,
         "anthropic" => ProtocolType::Anthropic,
         "tgi" => ProtocolType::Tgi,
-        _ => ProtocolType::Http,
+        "sdwebui" | "a1111" => ProtocolType::SdWebUi,
+        "comfyui" => ProtocolType::ComfyUi,
+        other => return Err(AppError::InvalidRequest(format!("Unknown protocol '{}'", other))),
     };
     
-    // Parse backend type
     let backend_type = match request.backend_type.to_lowercase().as_str() {
         "text" => BackendType::Text,
         "image" => BackendType::Image,
         "multi" => BackendType::Multi,
-        _ => BackendType::Image,
+        other => return Err(AppError::InvalidRequest(format!("Unknown backend type '{}'", other))),
     };
 
-    let backend_config = BackendConfig {
-        name: request.name.clone(),
+    let config = BackendConfig {
+        name,
         backend_type,
         protocol,
         endpoints: request.endpoints,
         enabled: true,
-        auth: BackendAuth::default(),
+        auth: request.auth.map(BackendAuth::from).unwrap_or_default(),
         health_check: BackendHealthCheck {
             path: request.health_check_path.clone(),
-            interval_secs: request.health_check_interval_secs,
+            interval_secs: Some(request.health_check_interval_secs),
             ..Default::default()
         },
         load_balancer: BackendLoadBalancer {
             weight: request.weight,
             ..Default::default()
         },
-        models: vec![],
-        capabilities: vec![],
+        circuit_breaker: Default::default(),
+        models: request.models,
+        capabilities: request.capabilities,
+        retry_count: request.retry_count,
+        chat_template: None,
+        workflow: request.workflow,
+        api_style: request.api_style,
         health_check_path: request.health_check_path,
         health_check_interval_secs: request.health_check_interval_secs,
         timeout_ms: request.timeout_ms,
         weight: request.weight,
     };
+    config.validate().map_err(|e| AppError::InvalidRequest(e.to_string()))?;
 
-    state.backend_registry.add_backend(backend_config).await?;
+    Ok(config)
+}
 
-    Ok(Json(SuccessResponse {
-        success: true,
-        message: format!("Backend '{}' added successfully", request.name),
-    }))
+/// Register a backend in the registries matching its type, replacing any
+/// backend with the same name and dropping it from registries it left
+async fn register_backend(state: &AppState, config: &BackendConfig) -> Result<(), AppError> {
+    match config.backend_type {
+        BackendType::Image => {
+            state.backend_registry.replace_backend(config.clone()).await.map_err(invalid_config)?;
+            if state.text_registry.contains(&config.name) {
+                state.text_registry.remove_backend(&config.name).await?;
+            }
+        }
+        BackendType::Text => {
+            state.text_registry.replace_backend(config.clone()).await.map_err(invalid_config)?;
+            if state.backend_registry.contains(&config.name) {
+                state.backend_registry.remove_backend(&config.name).await?;
+            }
+        }
+        BackendType::Multi => {
+            register_multi_backend(config, &state.backend_registry, &state.text_registry)
+                .map_err(invalid_config)?;
+        }
+    }
+
+    Ok(())
 }
 
-/// Remove a backend
+/// Remove a backend from every registry holding it
+async fn unregister_backend(state: &AppState, name: &str) -> Result<(), AppError> {
+    let image = state.backend_registry.contains(name);
+    let text = state.text_registry.contains(name);
+    if !image && !text {
+        return Err(AppError::BackendNotFound(name.to_string()));
+    }
+
+    if image {
+        state.backend_registry.remove_backend(name).await?;
+    }
+    if text {
+        state.text_registry.remove_backend(name).await?;
+    }
+    state.health_manager.forget(name);
+
+    Ok(())
+}
+
+/// Whether a registered backend is enabled, or `None` if no registry holds it
+async fn backend_enabled(state: &AppState, name: &str) -> Option<bool> {
+    if let Some(backend) = state.backend_registry.get(name) {
+        return Some(backend.is_enabled());
+    }
+    state.text_registry.get_backend(name).await.map(|backend| backend.is_enabled())
+}
+
+/// Backends that cannot be created from their configuration are client errors
+fn invalid_config(error: AppError) -> AppError {
+    match error {
+        AppError::Config(e) => AppError::InvalidRequest(e.to_string()),
+        other => other,
+    }
+}
+
+/// Get image load-balancing strategies
 ///
-/// Remove a registered backend by name.
+/// Returns the strategy used to pick between image backends and each image
+/// backend's endpoint selection strategy. Text backends are chosen by model
+/// and routing rules only; their endpoint strategies come from configuration
+/// and are not listed here.
 #[utoipa::path(
-    delete,
-    path = "/v1/backends/{name}",
-    params(
-        ("name" = String, Path, description = "Backend name to remove")
+    get,
+    path = "/v1/images/routing/strategy",
+    responses(
+        (status = 200, description = "Current strategies", body = StrategyResponse),
     ),
+    tag = "Backends"
+)]
+pub async fn get_image_strategy(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<StrategyResponse>, AppError> {
+    Ok(Json(image_strategy_response(&state)))
+}
+
+/// Change an image load-balancing strategy
+///
+/// Changes the strategy used to pick between image backends, or the endpoint
+/// strategy of a single image backend when `backend` is given. Text routing
+/// is not affected. Changes are not written back to the configuration file.
+#[utoipa::path(
+    put,
+    path = "/v1/images/routing/strategy",
+    request_body = SetStrategyRequest,
     responses(
-        (status = 200, description = "Backend removed successfully", body = SuccessResponse),
-        (status = 404, description = "Backend not found"),
+        (status = 200, description = "Strategy changed", body = StrategyResponse),
+        (status = 400, description = "Unknown strategy"),
+        (status = 404, description = "Image backend not found"),
     ),
     tag = "Backends"
 )]
-pub async fn remove_backend(
+pub async fn set_image_strategy(
     State(state): State<Arc<AppState>>,
-    Path(name): Path<String>,
-) -> Result<Json<SuccessResponse>, AppError> {
-    info!(name = %name, "Removing backend");
+    Json(request): Json<SetStrategyRequest>,
+) -> Result<Json<StrategyResponse>, AppError> {
+    let strategy: LoadBalancingStrategy = request
+        .strategy
+        .parse()
+        .map_err(AppError::InvalidRequest)?;
 
-    state.backend_registry.remove_backend(&name).await?;
+    match &request.backend {
+        Some(name) => {
+            let backend = state
+                .backend_registry
+                .get(name)
+                .ok_or_else(|| AppError::BackendNotFound(name.clone()))?;
+            backend.set_endpoint_strategy(strategy);
+            info!(backend = %name, strategy = %strategy, "Changed endpoint load-balancing strategy");
+        }
+        None => {
+            state.load_balancer.set_strategy(strategy);
+            info!(strategy = %strategy, "Changed load-balancing strategy");
+        }
+    }
 
-    Ok(Json(SuccessResponse {
-        success: true,
-        message: format!("Backend '{}' removed successfully", name),
-    }))
+    Ok(Json(image_strategy_response(&state)))
+}
+
+fn image_strategy_response(state: &AppState) -> StrategyResponse {
+    let mut backends: Vec<BackendStrategyInfo> = state
+        .backend_registry
+        .get_all()
+        .into_iter()
+        .map(|b| BackendStrategyInfo {
+            name: b.name().to_string(),
+            strategy: b.endpoint_strategy().to_string(),
+        })
+        .collect();
+    backends.sort_by(|a, b| a.name.cmp(&b.name));
+
+    StrategyResponse {
+        strategy: state.load_balancer.strategy().to_string(),
+        backends,
+    }
 }
 
 /// Health check endpoint
@@ -231,24 +833,278 @@ pub async fn health_check(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<HealthResponse>, AppError> {
     let (total, healthy, unhealthy) = state.health_manager.get_health_summary().await;
+    let (text_total, text_healthy, text_unhealthy) = state.health_manager.get_text_health_summary();
 
     Ok(Json(HealthResponse {
-        status: if healthy > 0 { "healthy" } else { "degraded" }.to_string(),
+        status: if healthy + text_healthy > 0 { "healthy" } else { "degraded" }.to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         backends: BackendHealthSummary {
             total,
             healthy,
             unhealthy,
         },
+        text_backends: BackendHealthSummary {
+            total: text_total,
+            healthy: text_healthy,
+            unhealthy: text_unhealthy,
+        },
     }))
 }
 
-/// Metrics endpoint (Prometheus format placeholder)
-pub async fn metrics(State(_state): State<Arc<AppState>>) -> String {
-    // TODO: Implement proper Prometheus metrics
-    "# HELP img_serving_requests_total Total number of image generation requests\n\
-     # TYPE img_serving_requests_total counter\n\
-     img_serving_requests_total 0\n"
-        .to_string()
+/// Metrics endpoint (Prometheus text exposition format)
+pub async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    // Gauges are sampled at scrape time; counters are recorded on the request path
+    state.metrics.set_queue_stats(&state.request_queue.stats());
+    state.metrics.set_backend_health(&state.health_manager.backend_health());
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        state.metrics.render(),
+    )
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::UpdateBackendRequest;
+    use crate::backend::{registry::BackendRegistry, TextBackendRegistry};
+    use crate::config::RoutingConfig;
+    use crate::gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer, router::RoutingRules};
+    use crate::metrics::Metrics;
+    use crate::middleware::{auth::AuthLayer, rate_limit::RateLimitLayer};
+    use crate::queue::request_queue::RequestQueue;
+
+    fn test_state() -> Arc<AppState> {
+        let backend_registry = Arc::new(BackendRegistry::new());
+        let load_balancer = Arc::new(LoadBalancer::new(backend_registry.clone()));
+        Arc::new(AppState {
+            settings: Arc::new(tokio::sync::RwLock::new(crate::config::Settings::default())),
+            backend_registry: backend_registry.clone(),
+            text_registry: Arc::new(TextBackendRegistry::new()),
+            load_balancer: load_balancer.clone(),
+            health_manager: Arc::new(HealthCheckManager::new(backend_registry)),
+            request_queue: Arc::new(RequestQueue::new(load_balancer)),
+            metrics: Arc::new(Metrics::new()),
+            auth: AuthLayer::new(vec![]),
+            rate_limit: RateLimitLayer::new(100, 200),
+        })
+    }
+
+    fn add_request(name: &str, backend_type: &str, protocol: &str) -> AddBackendRequest {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "backend_type": backend_type,
+            "protocol": protocol,
+            "endpoints": ["http://localhost:8000/v1"],
+            "models": ["llama3"],
+            "auth": {"type": "bearer", "api_key": "secret"},
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backends_dispatch_by_type() {
+        let state = test_state();
+
+        assert!(add_backend(State(state.clone()), Json(add_request("sd", "image", "http"))).await.unwrap().success);
+        assert!(add_backend(State(state.clone()), Json(add_request("ollama", "text", "openai"))).await.unwrap().success);
+        assert!(add_backend(State(state.clone()), Json(add_request("both", "multi", "openai"))).await.unwrap().success);
+
+        assert!(state.backend_registry.contains("sd") && !state.text_registry.contains("sd"));
+        assert!(!state.backend_registry.contains("ollama") && state.text_registry.contains("ollama"));
+        assert!(state.backend_registry.contains("both") && state.text_registry.contains("both"));
+        let ollama = state.text_registry.get_backend("ollama").await.unwrap();
+        assert_eq!(ollama.models(), vec!["llama3".to_string()]);
+
+        let duplicate = add_backend(State(state.clone()), Json(add_request("ollama", "image", "http"))).await;
+        assert!(matches!(duplicate, Err(AppError::InvalidRequest(_))));
+        let unknown = add_backend(State(state.clone()), Json(add_request("x", "audio", "http"))).await;
+        assert!(matches!(unknown, Err(AppError::InvalidRequest(_))));
+
+        // Changing the type moves the backend between registries
+        let update: UpdateBackendRequest = add_request("sd", "text", "openai").backend;
+        assert!(update_backend(State(state.clone()), Path("sd".to_string()), Json(update)).await.unwrap().success);
+        assert!(!state.backend_registry.contains("sd") && state.text_registry.contains("sd"));
+
+        assert!(remove_backend(State(state.clone()), Path("ollama".to_string())).await.unwrap().success);
+        assert!(remove_backend(State(state.clone()), Path("both".to_string())).await.unwrap().success);
+        assert!(!state.text_registry.contains("ollama"));
+        assert!(!state.backend_registry.contains("both") && !state.text_registry.contains("both"));
+        let missing = remove_backend(State(state), Path("ollama".to_string())).await;
+        assert!(matches!(missing, Err(AppError::BackendNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_backend_gets_no_traffic() {
+        let state = test_state();
+        assert!(add_backend(State(state.clone()), Json(add_request("sd-1", "image", "http"))).await.unwrap().success);
+        assert!(add_backend(State(state.clone()), Json(add_request("sd-2", "image", "http"))).await.unwrap().success);
+        let set_enabled = |enabled| {
+            set_backend_enabled(
+                State(state.clone()),
+                Path("sd-1".to_string()),
+                Json(SetBackendEnabledRequest { enabled }),
+            )
+        };
+
+        assert!(set_enabled(false).await.unwrap().success);
+        for _ in 0..10 {
+            assert_eq!(state.load_balancer.select_backend(None).await.unwrap().name(), "sd-2");
+        }
+        let result = state.load_balancer.select_backend(Some("sd-1")).await;
+        assert!(matches!(result, Err(AppError::NoHealthyBackends(_))));
+
+        assert!(set_enabled(true).await.unwrap().success);
+        assert_eq!(state.load_balancer.select_backend(Some("sd-1")).await.unwrap().name(), "sd-1");
+
+        let missing = set_backend_enabled(
+            State(state),
+            Path("missing".to_string()),
+            Json(SetBackendEnabledRequest { enabled: false }),
+        )
+        .await;
+        assert!(matches!(missing, Err(AppError::BackendNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_edits_route_to_capable_backends() {
+        use crate::backend::traits::ControlInput;
+        use axum::extract::FromRequest;
+
+        let state = test_state();
+        for (name, capabilities) in [("sd-1", vec![]), ("sd-2", vec![]), ("inpaint", vec!["edit"])] {
+            let mut request = add_request(name, "image", "http");
+            request.backend.capabilities = capabilities.into_iter().map(String::from).collect();
+            assert!(add_backend(State(state.clone()), Json(request)).await.unwrap().success);
+        }
+        let health = Arc::new(HealthCheckManager::new(state.backend_registry.clone()));
+        let lb = LoadBalancer::new(state.backend_registry.clone()).with_health_manager(health.clone());
+
+        for _ in 0..5 {
+            assert_eq!(lb.select_backend_for(None, &["edit"]).await.unwrap().name(), "inpaint");
+        }
+        assert!(lb.supports("inpaint", &["edit"]));
+        assert!(!lb.supports("sd-1", &["edit"]));
+
+        // A named backend without the capability is a client error
+        let result = lb.select_backend_for(Some("sd-1"), &["edit"]).await;
+        assert!(matches!(result, Err(AppError::InvalidRequest(_))));
+        let result = lb.select_backend_for(None, &["variations"]).await;
+        assert!(matches!(result, Err(AppError::InvalidRequest(_))));
+
+        // The edit endpoint routes the same way
+        let body = "--XyZ\r\n\
+            Content-Disposition: form-data; name=\"prompt\"\r\n\r\n\
+            a red hat\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"backend\"\r\n\r\n\
+            sd-1\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"image\"; filename=\"cat.png\"\r\n\
+            Content-Type: image/png\r\n\r\n\
+            PNG\r\n\
+            --XyZ--\r\n";
+        let request = axum::http::Request::builder()
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=XyZ")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let multipart = Multipart::from_request(request, &()).await.unwrap();
+        let result = edit_image(State(state), Extension(RequestLabels::default()), multipart).await;
+        assert!(matches!(result, Err(AppError::InvalidRequest(message)) if message.contains("'edit'")));
+
+        for _ in 0..3 {
+            health.record_outcome("inpaint", false);
+        }
+        let result = lb.select_backend_for(None, &["edit"]).await;
+        assert!(matches!(result, Err(AppError::NoHealthyBackends(_))));
+
+        let request = BackendGenerateRequest {
+            image: Some("aW1hZ2U=".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(request.required_capabilities(), vec!["edit"]);
+        let request = BackendGenerateRequest { variation: true, ..request };
+        assert_eq!(request.required_capabilities(), vec!["variations"]);
+
+        let request = BackendGenerateRequest {
+            init_image: Some("aW1hZ2U=".to_string()),
+            controls: vec![ControlInput {
+                control_type: "depth".to_string(),
+                image: "ZGVwdGg=".to_string(),
+                model: None,
+                weight: 1.0,
+                start: 0.0,
+                end: 1.0,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(request.required_capabilities(), vec!["img2img", "controlnet"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_persistence_rolls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let backends_path = dir.path().join("backends.yaml");
+        let state = test_state();
+        {
+            let mut settings = state.settings.write().await;
+            settings.management.persist_changes = true;
+            settings.backends_path = Some(backends_path.clone());
+        }
+
+        assert!(add_backend(State(state.clone()), Json(add_request("sd", "image", "http"))).await.unwrap().success);
+        // Retries are left to the file defaults
+        let persisted = Settings::load_backends_config(&backends_path).unwrap();
+        assert_eq!(persisted.backends.image[0].retry_count, None);
+
+        // Writes now fail: the directory holding the file is gone
+        state.settings.write().await.backends_path = Some(dir.path().join("missing").join("backends.yaml"));
+
+        let mut update: UpdateBackendRequest = add_request("sd", "image", "http").backend;
+        update.endpoints = vec!["http://localhost:9000".to_string()];
+        assert!(update_backend(State(state.clone()), Path("sd".to_string()), Json(update)).await.is_err());
+        let endpoints = state.backend_registry.get("sd").unwrap().endpoints();
+        assert_eq!(endpoints, vec!["http://localhost:8000/v1".to_string()]);
+
+        assert!(remove_backend(State(state.clone()), Path("sd".to_string())).await.is_err());
+        assert!(state.backend_registry.contains("sd"));
+    }
+
+    #[tokio::test]
+    async fn test_metric_labels_ignore_unknown_names() {
+        let state = test_state();
+        state.load_balancer.set_routing(RoutingRules::from_config(&RoutingConfig {
+            model_mappings: [("sdxl".to_string(), "sd".to_string())].into(),
+            ..Default::default()
+        }));
+        let image_request = |model: &str| -> GenerateImageRequest {
+            serde_json::from_value(serde_json::json!({
+                "prompt": "a cat",
+                "model": model,
+                "backend": "made-up-backend",
+            }))
+            .unwrap()
+        };
+
+        // Unknown models share one label, and an unresolved backend is not recorded
+        let labels = RequestLabels::default();
+        let result = generate_image(State(state.clone()), Extension(labels.clone()), Json(image_request("x-1234"))).await;
+        assert!(result.is_err());
+        assert_eq!(labels.get(), (String::new(), "other".to_string()));
+
+        let labels = RequestLabels::default();
+        let _ = generate_image(State(state.clone()), Extension(labels.clone()), Json(image_request("sdxl"))).await;
+        assert_eq!(labels.get().1, "sdxl");
+
+        let labels = RequestLabels::default();
+        let chat: crate::api::text_handlers::ApiChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "x-5678",
+            "messages": [{"role": "user", "content": "Hi"}],
+        }))
+        .unwrap();
+        let result = crate::api::text_handlers::chat_completion(State(state), Extension(labels.clone()), Json(chat)).await;
+        assert!(result.is_err());
+        assert_eq!(labels.get().1, "other");
+    }
+}
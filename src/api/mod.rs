@@ -2,6 +2,7 @@
 
 pub mod handlers;
 pub mod models;
+pub mod multipart;
 pub mod routes;
 pub mod text_handlers;
 
@@ -3,6 +3,11 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::backend::traits::{ControlInput, EndpointCircuit, GeneratedImage, GenerationProgress};
+use crate::config::BackendAuth;
+use crate::error::{AppError, Result};
+use crate::queue::jobs::{Job, JobStatus};
+
 /// Image generation request (OpenAI compatible)
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct GenerateImageRequest {
@@ -25,6 +30,14 @@ pub struct GenerateImageRequest {
     #[serde(default = "default_response_format")]
     pub response_format: String,
     
+    /// Image quality, "standard" or "hd" (OpenAI backends)
+    #[serde(default)]
+    pub quality: Option<String>,
+    
+    /// Image style, "vivid" or "natural" (OpenAI backends)
+    #[serde(default)]
+    pub style: Option<String>,
+    
     /// Negative prompt (extension, not in OpenAI API)
     #[serde(default)]
     pub negative_prompt: Option<String>,
@@ -41,9 +54,26 @@ pub struct GenerateImageRequest {
     #[serde(default)]
     pub num_inference_steps: Option<u32>,
     
+    /// Image to start from, base64 encoded (img2img, extension)
+    #[serde(default)]
+    pub init_image: Option<String>,
+    
+    /// How far generation departs from the init image, 0.0 to 1.0 (extension)
+    #[serde(default)]
+    pub denoising_strength: Option<f32>,
+    
+    /// Control inputs conditioning the generation (ControlNet, extension)
+    #[serde(default)]
+    pub controls: Vec<ControlInput>,
+    
     /// Specific backend to use (extension)
     #[serde(default)]
     pub backend: Option<String>,
+    
+    /// Additional backend-specific parameters, passed through as-is (extension)
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub extra_params: Option<serde_json::Value>,
 }
 
 fn default_n() -> u32 {
@@ -59,6 +89,26 @@ fn default_response_format() -> String {
 }
 
 impl GenerateImageRequest {
+    /// Check the img2img and control fields
+    pub fn validate(&self) -> Result<()> {
+        if let Some(strength) = self.denoising_strength {
+            if !(0.0..=1.0).contains(&strength) {
+                return Err(AppError::InvalidRequest(
+                    "denoising_strength must be between 0.0 and 1.0".to_string(),
+                ));
+            }
+        }
+        for control in &self.controls {
+            if !(0.0 <= control.start && control.start <= control.end && control.end <= 1.0) {
+                return Err(AppError::InvalidRequest(format!(
+                    "Control '{}' needs 0.0 <= start <= end <= 1.0",
+                    control.control_type
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Parse size string into width and height
     pub fn parse_size(&self) -> (u32, u32) {
         let parts: Vec<&str> = self.size.split('x').collect();
@@ -72,6 +122,56 @@ impl GenerateImageRequest {
     }
 }
 
+/// Image edit form (`multipart/form-data`, OpenAI compatible)
+///
+/// Documents the upload fields; the handler reads them into a
+/// [`GenerateImageRequest`], so its extension fields are accepted as well.
+#[derive(Debug, ToSchema)]
+pub struct ImageEditForm {
+    /// The image to edit
+    #[schema(value_type = String, format = Binary)]
+    pub image: Vec<u8>,
+    
+    /// Mask whose transparent areas mark where the image is edited
+    #[schema(value_type = Option<String>, format = Binary)]
+    pub mask: Option<Vec<u8>>,
+    
+    /// Description of the edited image
+    pub prompt: String,
+    
+    /// Number of images to generate (1-10)
+    pub n: Option<u32>,
+    
+    /// The size of the generated images (e.g., "1024x1024")
+    pub size: Option<String>,
+    
+    /// The format of the response: "url", "b64_json", or "file"
+    pub response_format: Option<String>,
+    
+    /// The model to use for editing
+    pub model: Option<String>,
+}
+
+/// Image variation form (`multipart/form-data`, OpenAI compatible)
+#[derive(Debug, ToSchema)]
+pub struct ImageVariationForm {
+    /// The image to make variations of
+    #[schema(value_type = String, format = Binary)]
+    pub image: Vec<u8>,
+    
+    /// Number of images to generate (1-10)
+    pub n: Option<u32>,
+    
+    /// The size of the generated images (e.g., "1024x1024")
+    pub size: Option<String>,
+    
+    /// The format of the response: "url", "b64_json", or "file"
+    pub response_format: Option<String>,
+    
+    /// The model to use for variations
+    pub model: Option<String>,
+}
+
 /// Image data in the response
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct ImageData {
@@ -88,6 +188,16 @@ pub struct ImageData {
     pub revised_prompt: Option<String>,
 }
 
+impl From<GeneratedImage> for ImageData {
+    fn from(image: GeneratedImage) -> Self {
+        Self {
+            b64_json: image.b64_json,
+            url: image.url,
+            revised_prompt: image.revised_prompt,
+        }
+    }
+}
+
 /// Image generation response (OpenAI compatible)
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct GenerateImageResponse {
@@ -98,6 +208,75 @@ pub struct GenerateImageResponse {
     pub data: Vec<ImageData>,
 }
 
+/// Asynchronous image job (extension)
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct ImageJobResponse {
+    /// Job id for polling and cancellation
+    pub id: String,
+    
+    /// Always "image.job"
+    pub object: String,
+    
+    /// `queued`, `running`, `succeeded`, `failed`, or `cancelled`
+    pub status: JobStatus,
+    
+    /// Unix timestamp of submission
+    pub created: i64,
+    
+    /// Unix timestamp of when a backend started the job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started: Option<i64>,
+    
+    /// Unix timestamp of when the job finished
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished: Option<i64>,
+    
+    /// Requests ahead of the job, while queued
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
+    
+    /// Latest backend progress, while running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<GenerationProgress>,
+    
+    /// Backend that served the job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    
+    /// Generated images, once succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<ImageData>>,
+    
+    /// Failure message, once failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<Job> for ImageJobResponse {
+    fn from(job: Job) -> Self {
+        let (backend, data) = match job.response {
+            Some(response) => (
+                response.backend,
+                Some(response.images.into_iter().map(ImageData::from).collect()),
+            ),
+            None => (None, None),
+        };
+        Self {
+            id: job.id,
+            object: "image.job".to_string(),
+            status: job.status,
+            created: job.created_at,
+            started: job.started_at,
+            finished: job.finished_at,
+            queue_position: None,
+            progress: job.progress,
+            backend,
+            data,
+            error: job.error,
+        }
+    }
+}
+
 /// Backend information for management API
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct BackendInfo {
@@ -107,6 +286,31 @@ pub struct BackendInfo {
     pub healthy: bool,
     pub weight: u32,
     pub enabled: bool,
+    /// Circuit breaker state of each endpoint
+    pub circuit_breakers: Vec<CircuitBreakerInfo>,
+}
+
+/// Circuit breaker state of one backend endpoint
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct CircuitBreakerInfo {
+    pub endpoint: String,
+    /// `closed`, `open`, or `half_open`
+    pub state: String,
+    /// Failure rate over the rolling window, from 0.0 to 1.0
+    pub failure_rate: f64,
+    /// Requests recorded in the rolling window
+    pub requests: usize,
+}
+
+impl From<EndpointCircuit> for CircuitBreakerInfo {
+    fn from(endpoint: EndpointCircuit) -> Self {
+        Self {
+            endpoint: endpoint.url,
+            state: endpoint.circuit.state.to_string(),
+            failure_rate: endpoint.circuit.failure_rate,
+            requests: endpoint.circuit.requests,
+        }
+    }
 }
 
 /// Backend list response
@@ -119,6 +323,13 @@ pub struct BackendListResponse {
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct AddBackendRequest {
     pub name: String,
+    #[serde(flatten)]
+    pub backend: UpdateBackendRequest,
+}
+
+/// Update backend request, replacing a backend's configuration
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct UpdateBackendRequest {
     #[serde(default = "default_protocol")]
     pub protocol: String,
     pub endpoints: Vec<String>,
@@ -130,9 +341,65 @@ pub struct AddBackendRequest {
     pub timeout_ms: u64,
     #[serde(default = "default_weight")]
     pub weight: u32,
-    /// Backend type: "image" or "text"
+    /// Backend type: "image", "text" or "multi" (both)
     #[serde(default = "default_backend_type")]
     pub backend_type: String,
+    /// Models served by a text backend
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Capabilities such as "chat", "completion" or "streaming"
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Credentials sent to the backend
+    #[serde(default)]
+    pub auth: Option<BackendAuthRequest>,
+    /// Workflow template path for ComfyUI backends
+    #[serde(default)]
+    pub workflow: Option<String>,
+    /// Request API of a generic HTTP backend, probed when unset
+    #[serde(default)]
+    pub api_style: Option<String>,
+    /// Retries for transient failures; follows `defaults.connection.retry_count` when unset
+    #[serde(default)]
+    pub retry_count: Option<u32>,
+}
+
+/// Backend credentials
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct BackendAuthRequest {
+    /// Auth type: "none", "bearer" or "api_key"
+    #[serde(rename = "type", default = "default_auth_type")]
+    pub auth_type: String,
+    /// Environment variable holding the token
+    #[serde(default)]
+    pub token_env: Option<String>,
+    /// Header carrying the token instead of `Authorization`
+    #[serde(default)]
+    pub header_name: Option<String>,
+    /// Token, when not read from the environment
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+impl From<BackendAuthRequest> for BackendAuth {
+    fn from(auth: BackendAuthRequest) -> Self {
+        Self {
+            auth_type: auth.auth_type,
+            token_env: auth.token_env,
+            header_name: auth.header_name,
+            api_key: auth.api_key,
+        }
+    }
+}
+
+/// Enable or disable a backend
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct SetBackendEnabledRequest {
+    pub enabled: bool,
+}
+
+fn default_auth_type() -> String {
+    "none".to_string()
 }
 
 fn default_protocol() -> String {
@@ -159,12 +426,43 @@ fn default_weight() -> u32 {
     1
 }
 
+/// Image load-balancing strategies currently in effect
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct StrategyResponse {
+    /// Strategy used to pick between image backends
+    pub strategy: String,
+    /// Per-image-backend strategies used to pick between endpoints
+    pub backends: Vec<BackendStrategyInfo>,
+}
+
+/// Endpoint selection strategy of a single backend
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct BackendStrategyInfo {
+    pub name: String,
+    pub strategy: String,
+}
+
+/// Change an image load-balancing strategy at runtime
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct SetStrategyRequest {
+    /// Strategy name, e.g. "round_robin", "least_connections", "least_latency"
+    /// or "power_of_two_choices"
+    pub strategy: String,
+    /// Image backend whose endpoint strategy to change; omit to change the
+    /// strategy used to pick between image backends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+}
+
 /// Health check response
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
+    /// Image backends
     pub backends: BackendHealthSummary,
+    /// Text backends
+    pub text_backends: BackendHealthSummary,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
@@ -0,0 +1,149 @@
+//! `multipart/form-data` handling for image upload endpoints
+//!
+//! Uploads are small (a source image and an optional mask), so every field of
+//! the `Multipart` extractor is read into memory before the request is built.
+
+use axum::extract::multipart::{Multipart, MultipartError};
+
+use crate::error::{AppError, Result};
+
+/// One field of a multipart form
+#[derive(Debug, Clone)]
+pub struct Part {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Buffered multipart form
+#[derive(Debug, Clone, Default)]
+pub struct MultipartForm {
+    parts: Vec<Part>,
+}
+
+impl MultipartForm {
+    /// Read every field of a multipart request
+    pub async fn read(mut multipart: Multipart) -> Result<Self> {
+        let mut parts = Vec::new();
+        while let Some(field) = multipart.next_field().await.map_err(invalid)? {
+            let name = field
+                .name()
+                .map(str::to_string)
+                .ok_or_else(|| AppError::InvalidRequest("Invalid multipart body: part without a name".to_string()))?;
+            let filename = field.file_name().map(str::to_string);
+            let content_type = field.content_type().map(str::to_string);
+            let data = field.bytes().await.map_err(invalid)?.to_vec();
+            parts.push(Part {
+                name,
+                filename,
+                content_type,
+                data,
+            });
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// First field with the given name
+    pub fn get(&self, name: &str) -> Option<&Part> {
+        self.parts.iter().find(|part| part.name == name)
+    }
+
+    /// Text value of a field
+    pub fn text(&self, name: &str) -> Result<Option<String>> {
+        self.get(name).map(part_text).transpose()
+    }
+
+    /// All fields that are not file uploads, as name and text
+    ///
+    /// A part counts as a file when it has a filename or a content type, so
+    /// uploads named only through `filename*` are still left out.
+    pub fn text_fields(&self) -> Result<Vec<(String, String)>> {
+        self.parts
+            .iter()
+            .filter(|part| part.filename.is_none() && part.content_type.is_none())
+            .map(|part| Ok((part.name.clone(), part_text(part)?)))
+            .collect()
+    }
+}
+
+fn part_text(part: &Part) -> Result<String> {
+    String::from_utf8(part.data.clone())
+        .map_err(|_| AppError::InvalidRequest(format!("Field '{}' must be UTF-8 text", part.name)))
+}
+
+fn invalid(error: MultipartError) -> AppError {
+    AppError::InvalidRequest(format!("Invalid multipart body: {}", error.body_text()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::FromRequest;
+    use axum::http::{header, Request};
+
+    async fn form(body: &'static [u8]) -> Result<MultipartForm> {
+        let request = Request::builder()
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=XyZ")
+            .body(Body::from(body))
+            .unwrap();
+        let multipart = Multipart::from_request(request, &()).await.unwrap();
+        MultipartForm::read(multipart).await
+    }
+
+    #[tokio::test]
+    async fn test_read_form() {
+        let form = form(
+            b"--XyZ\r\n\
+            Content-Disposition: form-data; name=\"prompt\"\r\n\r\n\
+            a red hat\r\n\
+            --XyZ\r\n\
+            content-disposition: form-data; name=\"image\"; filename=\"cat.png\"\r\n\
+            Content-Type: image/png\r\n\r\n\
+            \x89PNG\r\n\x1a\n\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"mask\"; filename*=UTF-8''mask%20one.png\r\n\
+            Content-Type: image/png\r\n\r\n\
+            \xff\xfe\r\n\
+            --XyZ--\r\n",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(form.text("prompt").unwrap().as_deref(), Some("a red hat"));
+        let image = form.get("image").unwrap();
+        assert_eq!(image.filename.as_deref(), Some("cat.png"));
+        assert_eq!(image.content_type.as_deref(), Some("image/png"));
+        // Line breaks inside binary data are kept
+        assert_eq!(image.data, b"\x89PNG\r\n\x1a\n");
+        assert_eq!(form.get("mask").unwrap().data, b"\xff\xfe");
+        assert_eq!(form.text_fields().unwrap(), vec![("prompt".to_string(), "a red hat".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_text_fields_keep_their_values() {
+        let form = form(
+            b"--XyZ\r\n\
+            Content-Disposition: form-data; name=\"tag\"\r\n\r\n\
+            first\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"tag\"\r\n\r\n\
+            second\r\n\
+            --XyZ--\r\n",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            form.text_fields().unwrap(),
+            vec![("tag".to_string(), "first".to_string()), ("tag".to_string(), "second".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unterminated_body() {
+        assert!(form(b"--XyZ\r\nContent-Disposition: form-data; name=\"prompt\"\r\n\r\nhat").await.is_err());
+    }
+}
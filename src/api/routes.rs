@@ -5,12 +5,16 @@ use crate::api::models::*;
 use crate::api::text_handlers::{self, *};
 use crate::backend::{
     ChatMessage, ChatCompletionResponse, ChatChoice,
+    ChatCompletionChunk, ChatChunkChoice, ChatDelta,
     TextCompletionResponse, TextChoice, Usage,
     ModelsResponse, ModelInfo,
 };
-use crate::middleware::{auth::AuthLayer, rate_limit::RateLimitLayer};
+use crate::backend::traits::{ControlInput, GenerationProgress};
+use crate::queue::jobs::JobStatus;
 use axum::{
-    routing::{delete, get, post},
+    extract::DefaultBodyLimit,
+    middleware::from_fn_with_state,
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use std::sync::Arc;
@@ -32,9 +36,19 @@ use utoipa_swagger_ui::SwaggerUi;
     ),
     paths(
         handlers::generate_image,
+        handlers::edit_image,
+        handlers::create_image_variation,
+        handlers::create_image_job,
+        handlers::get_image_job,
+        handlers::image_job_events,
+        handlers::cancel_image_job,
         handlers::list_backends,
         handlers::add_backend,
+        handlers::update_backend,
+        handlers::set_backend_enabled,
         handlers::remove_backend,
+        handlers::get_image_strategy,
+        handlers::set_image_strategy,
         handlers::health_check,
         text_handlers::chat_completion,
         text_handlers::text_completion,
@@ -43,11 +57,23 @@ use utoipa_swagger_ui::SwaggerUi;
     ),
     components(schemas(
         GenerateImageRequest,
+        ControlInput,
         GenerateImageResponse,
+        ImageEditForm,
+        ImageVariationForm,
+        ImageJobResponse,
+        JobStatus,
+        GenerationProgress,
         ImageData,
         BackendInfo,
+        CircuitBreakerInfo,
         BackendListResponse,
         AddBackendRequest,
+        UpdateBackendRequest,
+        SetBackendEnabledRequest,
+        StrategyResponse,
+        BackendStrategyInfo,
+        SetStrategyRequest,
         HealthResponse,
         BackendHealthSummary,
         SuccessResponse,
@@ -58,6 +84,9 @@ use utoipa_swagger_ui::SwaggerUi;
         ChatMessage,
         ChatCompletionResponse,
         ChatChoice,
+        ChatCompletionChunk,
+        ChatChunkChoice,
+        ChatDelta,
         TextCompletionResponse,
         TextChoice,
         Usage,
@@ -77,22 +106,23 @@ pub struct ApiDoc;
 
 /// Create the main application router
 pub async fn create_router(state: Arc<crate::AppState>) -> Router {
-    // Get configuration for middleware
-    let (auth_enabled, api_keys, rate_limit_enabled, rps, burst) = {
-        let config = state.settings.read().await;
-        (
-            config.auth.enabled,
-            config.auth.api_keys.clone(),
-            config.rate_limit.enabled,
-            config.rate_limit.requests_per_second,
-            config.rate_limit.burst_size,
-        )
-    };
-
     // Build the API routes that require authentication and rate limiting
     let api_routes = Router::new()
         // Image generation endpoint (OpenAI compatible)
         .route("/images/generations", post(handlers::generate_image))
+        .route(
+            "/images/edits",
+            post(handlers::edit_image).layer(DefaultBodyLimit::max(handlers::IMAGE_UPLOAD_LIMIT)),
+        )
+        .route(
+            "/images/variations",
+            post(handlers::create_image_variation).layer(DefaultBodyLimit::max(handlers::IMAGE_UPLOAD_LIMIT)),
+        )
+        // Asynchronous image jobs
+        .route("/images/jobs", post(handlers::create_image_job))
+        .route("/images/jobs/:id", get(handlers::get_image_job))
+        .route("/images/jobs/:id", delete(handlers::cancel_image_job))
+        .route("/images/jobs/:id/events", get(handlers::image_job_events))
         // Text/Chat completion endpoints (OpenAI compatible)
         .route("/chat/completions", post(text_handlers::chat_completion))
         .route("/completions", post(text_handlers::text_completion))
@@ -101,21 +131,19 @@ pub async fn create_router(state: Arc<crate::AppState>) -> Router {
         // Backend management endpoints
         .route("/backends", get(handlers::list_backends))
         .route("/backends", post(handlers::add_backend))
+        .route("/backends/:name", put(handlers::update_backend))
+        .route("/backends/:name", patch(handlers::set_backend_enabled))
         .route("/backends/:name", delete(handlers::remove_backend))
-        .route("/backends/text", get(text_handlers::list_text_backends));
-
-    // Apply middleware conditionally
-    let api_routes = if rate_limit_enabled {
-        api_routes.layer(RateLimitLayer::new(rps, burst))
-    } else {
-        api_routes
-    };
+        .route("/backends/text", get(text_handlers::list_text_backends))
+        // Image load-balancing strategy management
+        .route("/images/routing/strategy", get(handlers::get_image_strategy))
+        .route("/images/routing/strategy", put(handlers::set_image_strategy));
 
-    let api_routes = if auth_enabled {
-        api_routes.layer(AuthLayer::new(api_keys))
-    } else {
-        api_routes
-    };
+    // Auth and rate limits read their shared state per request, so config
+    // reloads apply without rebuilding the router
+    let api_routes = api_routes
+        .layer(state.rate_limit.clone())
+        .layer(state.auth.clone());
 
     // Build the full router
     Router::new()
@@ -131,6 +159,8 @@ pub async fn create_router(state: Arc<crate::AppState>) -> Router {
         .nest_service("/files", tower_http::services::ServeDir::new("generated"))
         // API routes under /v1 prefix
         .nest("/v1", api_routes)
+        // Request metrics for matched routes
+        .route_layer(from_fn_with_state(state.clone(), crate::metrics::track_requests))
         // Add shared state
         .with_state(state)
         // Add tracing layer
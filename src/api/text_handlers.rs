@@ -1,20 +1,31 @@
 //! Text generation API handlers (OpenAI compatible)
 
+use crate::api::handlers::BACKEND_HEADER;
+use crate::api::models::CircuitBreakerInfo;
 use crate::backend::{
-    ChatCompletionRequest, ChatCompletionResponse, ChatMessage,
-    TextCompletionRequest, TextCompletionResponse,
+    ChatCompletionRequest, ChatMessage,
+    TextBackend, TextCompletionRequest,
     ModelsResponse, ModelInfo,
 };
 use crate::error::AppError;
+use crate::gateway::router::should_fail_over;
+use crate::metrics::{model_label, RequestLabels};
 use crate::AppState;
 use axum::{
     extract::State,
-    Json,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
 };
 use chrono::Utc;
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::future::Future;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{debug, info, warn};
 use utoipa::ToSchema;
 
 /// API chat completion request
@@ -63,15 +74,89 @@ pub struct ApiTextCompletionRequest {
     pub backend: Option<String>,
 }
 
+/// Relay a backend chunk stream to the client as server-sent events
+///
+/// Each chunk is sent as a `data:` event and the stream is terminated with
+/// `data: [DONE]`. A backend error mid-stream is sent as an OpenAI-style error
+/// event before the terminator. When the client disconnects, axum drops the
+/// body, which drops the backend stream and closes the upstream request.
+fn sse_response<T>(chunks: BoxStream<'static, crate::Result<T>>) -> Response
+where
+    T: Serialize + Send + 'static,
+{
+    let events = chunks
+        .map(|chunk| {
+            let data = chunk
+                .and_then(|chunk| serde_json::to_string(&chunk).map_err(AppError::from))
+                .unwrap_or_else(|e| {
+                    warn!(error = %e, "Backend stream failed");
+                    serde_json::to_string(&e.to_error_response().1).unwrap_or_default()
+                });
+            Ok::<_, Infallible>(Event::default().data(data))
+        })
+        .chain(stream::once(async {
+            debug!("Stream completed");
+            Ok(Event::default().data("[DONE]"))
+        }));
+
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// Call each candidate backend in turn until one succeeds
+///
+/// Moves on to the next backend only for errors another backend might not
+/// hit; client errors are returned immediately. For streaming calls this
+/// covers opening the stream, not failures after the first chunk.
+async fn with_fallbacks<T, F, Fut>(
+    backends: Vec<Arc<dyn TextBackend>>,
+    mut call: F,
+) -> Result<(Arc<dyn TextBackend>, T), AppError>
+where
+    F: FnMut(Arc<dyn TextBackend>) -> Fut,
+    Fut: Future<Output = crate::Result<T>>,
+{
+    let mut backends = backends.into_iter();
+    let mut backend = backends
+        .next()
+        .ok_or_else(|| AppError::NoHealthyBackends("No available text backend".to_string()))?;
+
+    loop {
+        match call(backend.clone()).await {
+            Ok(value) => return Ok((backend, value)),
+            Err(e) => match backends.next() {
+                Some(next) if should_fail_over(&e) => {
+                    warn!(
+                        backend = %backend.name(),
+                        fallback = %next.name(),
+                        error = %e,
+                        "Text backend unavailable, trying fallback"
+                    );
+                    backend = next;
+                }
+                _ => return Err(e),
+            },
+        }
+    }
+}
+
+/// Attach the name of the backend that served a request
+fn with_backend_header(backend: &str, response: impl IntoResponse) -> Response {
+    ([(BACKEND_HEADER, backend.to_string())], response).into_response()
+}
+
 /// Chat completion handler (OpenAI /v1/chat/completions compatible)
 ///
 /// Creates a chat completion for the provided messages. OpenAI API compatible.
+/// With `stream: true` the response is a `text/event-stream` of
+/// `chat.completion.chunk` objects terminated by `data: [DONE]`.
 #[utoipa::path(
     post,
     path = "/v1/chat/completions",
     request_body = ApiChatCompletionRequest,
     responses(
-        (status = 200, description = "Chat completion successful", body = crate::backend::ChatCompletionResponse),
+        (status = 200, description = "Chat completion successful", body = crate::backend::ChatCompletionResponse,
+            headers(("x-gateway-backend" = String, description = "Backend that served the request"))),
+        (status = 200, description = "Chat completion chunks when `stream` is true", body = crate::backend::ChatCompletionChunk, content_type = "text/event-stream"),
         (status = 400, description = "Invalid request"),
         (status = 500, description = "Internal server error"),
     ),
@@ -79,16 +164,23 @@ pub struct ApiTextCompletionRequest {
 )]
 pub async fn chat_completion(
     State(state): State<Arc<AppState>>,
+    Extension(labels): Extension<RequestLabels>,
     Json(request): Json<ApiChatCompletionRequest>,
-) -> Result<Json<ChatCompletionResponse>, AppError> {
+) -> Result<Response, AppError> {
+    let stream = request.stream.unwrap_or(false);
+
     info!(
         model = %request.model,
         messages = request.messages.len(),
+        stream = stream,
         "Received chat completion request"
     );
 
-    // Find appropriate backend
-    let backend = state.text_registry.get_backend_for_model(&request.model, request.backend.as_deref()).await?;
+    let model = model_label(&request.model, state.text_registry.is_configured_model(&request.model)).to_string();
+    labels.set_model(model.as_str());
+
+    // Find the backend for the model and its fallbacks
+    let backends = state.text_registry.get_backends_for_model(&request.model, request.backend.as_deref()).await?;
     
     // Create backend request
     let backend_request = ChatCompletionRequest {
@@ -97,15 +189,44 @@ pub async fn chat_completion(
         temperature: request.temperature,
         top_p: request.top_p,
         max_tokens: request.max_tokens,
-        stream: request.stream,
+        stream: Some(stream),
         stop: request.stop,
         presence_penalty: request.presence_penalty,
         frequency_penalty: request.frequency_penalty,
         user: request.user,
     };
 
+    if stream {
+        let (backend, chunks) = with_fallbacks(backends, |backend| {
+            let backend_request = backend_request.clone();
+            async move { backend.chat_completion_stream(backend_request).await }
+        })
+        .await?;
+        labels.set_backend(backend.name());
+
+        let metrics = state.metrics.clone();
+        let (backend_name, model) = (backend.name().to_string(), model.clone());
+        let chunks = chunks
+            .inspect(move |chunk| {
+                if let Some(usage) = chunk.as_ref().ok().and_then(|c| c.usage.as_ref()) {
+                    metrics.record_usage(&backend_name, &model, usage);
+                }
+            })
+            .boxed();
+        return Ok(with_backend_header(backend.name(), sse_response(chunks)));
+    }
+
     // Forward to backend
-    let response = backend.chat_completion(backend_request).await?;
+    let (backend, response) = with_fallbacks(backends, |backend| {
+        let backend_request = backend_request.clone();
+        async move { backend.chat_completion(backend_request).await }
+    })
+    .await?;
+    labels.set_backend(backend.name());
+
+    if let Some(usage) = &response.usage {
+        state.metrics.record_usage(backend.name(), &model, usage);
+    }
 
     info!(
         model = %response.model,
@@ -113,18 +234,22 @@ pub async fn chat_completion(
         "Chat completion completed"
     );
 
-    Ok(Json(response))
+    Ok(with_backend_header(backend.name(), Json(response)))
 }
 
 /// Text completion handler (OpenAI /v1/completions compatible)
 ///
 /// Creates a text completion for the provided prompt. OpenAI API compatible.
+/// With `stream: true` the response is a `text/event-stream` of completion
+/// chunks terminated by `data: [DONE]`.
 #[utoipa::path(
     post,
     path = "/v1/completions",
     request_body = ApiTextCompletionRequest,
     responses(
-        (status = 200, description = "Text completion successful", body = crate::backend::TextCompletionResponse),
+        (status = 200, description = "Text completion successful", body = crate::backend::TextCompletionResponse,
+            headers(("x-gateway-backend" = String, description = "Backend that served the request"))),
+        (status = 200, description = "Text completion chunks when `stream` is true", body = crate::backend::TextCompletionResponse, content_type = "text/event-stream"),
         (status = 400, description = "Invalid request"),
         (status = 500, description = "Internal server error"),
     ),
@@ -132,16 +257,23 @@ pub async fn chat_completion(
 )]
 pub async fn text_completion(
     State(state): State<Arc<AppState>>,
+    Extension(labels): Extension<RequestLabels>,
     Json(request): Json<ApiTextCompletionRequest>,
-) -> Result<Json<TextCompletionResponse>, AppError> {
+) -> Result<Response, AppError> {
+    let stream = request.stream.unwrap_or(false);
+
     info!(
         model = %request.model,
         prompt_len = request.prompt.len(),
+        stream = stream,
         "Received text completion request"
     );
 
-    // Find appropriate backend
-    let backend = state.text_registry.get_backend_for_model(&request.model, request.backend.as_deref()).await?;
+    let model = model_label(&request.model, state.text_registry.is_configured_model(&request.model)).to_string();
+    labels.set_model(model.as_str());
+
+    // Find the backend for the model and its fallbacks
+    let backends = state.text_registry.get_backends_for_model(&request.model, request.backend.as_deref()).await?;
     
     // Create backend request
     let backend_request = TextCompletionRequest {
@@ -151,11 +283,40 @@ pub async fn text_completion(
         temperature: request.temperature,
         top_p: request.top_p,
         stop: request.stop,
-        stream: request.stream,
+        stream: Some(stream),
     };
 
+    if stream {
+        let (backend, chunks) = with_fallbacks(backends, |backend| {
+            let backend_request = backend_request.clone();
+            async move { backend.text_completion_stream(backend_request).await }
+        })
+        .await?;
+        labels.set_backend(backend.name());
+
+        let metrics = state.metrics.clone();
+        let (backend_name, model) = (backend.name().to_string(), model.clone());
+        let chunks = chunks
+            .inspect(move |chunk| {
+                if let Some(usage) = chunk.as_ref().ok().and_then(|c| c.usage.as_ref()) {
+                    metrics.record_usage(&backend_name, &model, usage);
+                }
+            })
+            .boxed();
+        return Ok(with_backend_header(backend.name(), sse_response(chunks)));
+    }
+
     // Forward to backend
-    let response = backend.text_completion(backend_request).await?;
+    let (backend, response) = with_fallbacks(backends, |backend| {
+        let backend_request = backend_request.clone();
+        async move { backend.text_completion(backend_request).await }
+    })
+    .await?;
+    labels.set_backend(backend.name());
+
+    if let Some(usage) = &response.usage {
+        state.metrics.record_usage(backend.name(), &model, usage);
+    }
 
     info!(
         model = %response.model,
@@ -163,7 +324,7 @@ pub async fn text_completion(
         "Text completion completed"
     );
 
-    Ok(Json(response))
+    Ok(with_backend_header(backend.name(), Json(response)))
 }
 
 /// List models handler (OpenAI /v1/models compatible)
@@ -224,6 +385,8 @@ pub struct TextBackendInfo {
     pub models: Vec<String>,
     pub capabilities: Vec<String>,
     pub enabled: bool,
+    /// Circuit breaker state of each endpoint
+    pub circuit_breakers: Vec<CircuitBreakerInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -257,6 +420,7 @@ pub async fn list_text_backends(
             models: b.models,
             capabilities: b.capabilities,
             enabled: b.enabled,
+            circuit_breakers: b.circuits.into_iter().map(CircuitBreakerInfo::from).collect(),
         })
         .collect();
 
@@ -0,0 +1,734 @@
+//! ComfyUI workflow backend
+//!
+//! [`ComfyUiBackend`] fills the configured workflow template (exported from
+//! ComfyUI in API format) with the request, queues it with `POST /prompt`,
+//! polls `/history/{prompt_id}` until the job finishes and downloads the
+//! saved images from `/view`. A whole job runs on one endpoint. A job that is
+//! abandoned before it finishes (deadline, failed polling, or the caller
+//! dropping the request) is removed from the ComfyUI queue and interrupted.
+//!
+//! Placeholders are written as string values in the template:
+//! `{{prompt}}`, `{{negative_prompt}}`, `{{seed}}`, `{{steps}}`, `{{cfg}}`,
+//! `{{width}}`, `{{height}}`, `{{batch_size}}` and `{{model}}`. A value that is
+//! exactly one placeholder is replaced with a typed JSON value; placeholders
+//! inside longer strings are replaced as text.
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use crate::backend::traits::{
+    BackendEndpoint, EndpointCircuit, GenerateRequest, GenerateResponse, GeneratedImage,
+    ImageBackend,
+};
+use crate::config::BackendConfig;
+use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckSchedule;
+use crate::gateway::load_balancer::LoadBalancingStrategy;
+use crate::gateway::load_tracker::{select_endpoint, LoadStats};
+use crate::gateway::retry::{is_retryable_status, retry_after, Attempt, RetryPolicy};
+use crate::response::base64;
+
+/// How often the job history is polled
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Sampling steps when the request does not set them
+const DEFAULT_STEPS: u32 = 20;
+
+/// CFG scale when the request does not set it
+const DEFAULT_CFG: f32 = 7.0;
+
+/// ComfyUI image generation backend
+pub struct ComfyUiBackend {
+    name: String,
+    client: Client,
+    endpoints: Arc<RwLock<Vec<BackendEndpoint>>>,
+    health_check_path: String,
+    health_check_schedule: HealthCheckSchedule,
+    weight: u32,
+    enabled: AtomicBool,
+    timeout: Duration,
+    retry: RetryPolicy,
+    strategy: RwLock<LoadBalancingStrategy>,
+    current_endpoint_index: Arc<RwLock<usize>>,
+    capabilities: Vec<String>,
+    workflow: Value,
+    /// Model used for `{{model}}` when the request names none
+    default_model: Option<String>,
+    client_id: String,
+}
+
+/// `POST /prompt` response
+#[derive(Debug, Deserialize)]
+struct QueuedPrompt {
+    prompt_id: String,
+}
+
+/// Finished job in `/history/{prompt_id}`
+#[derive(Debug, Deserialize)]
+struct HistoryEntry {
+    #[serde(default)]
+    outputs: BTreeMap<String, NodeOutput>,
+    #[serde(default)]
+    status: Option<HistoryStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryStatus {
+    #[serde(default)]
+    status_str: Option<String>,
+    #[serde(default)]
+    messages: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeOutput {
+    #[serde(default)]
+    images: Vec<OutputImage>,
+}
+
+/// Image written by an output node
+#[derive(Debug, Deserialize)]
+struct OutputImage {
+    filename: String,
+    #[serde(default)]
+    subfolder: String,
+    /// `output` for saved images, `temp` for previews
+    #[serde(rename = "type", default)]
+    image_type: String,
+}
+
+impl ComfyUiBackend {
+    /// Create a new ComfyUI backend, loading its workflow template
+    pub fn new(config: &BackendConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
+
+        let endpoints: Vec<BackendEndpoint> = config
+            .endpoints
+            .iter()
+            .map(|url| BackendEndpoint::with_circuit_breaker(url.trim_end_matches('/').to_string(), &config.circuit_breaker))
+            .collect();
+
+        Ok(Self {
+            name: config.name.clone(),
+            client,
+            endpoints: Arc::new(RwLock::new(endpoints)),
+            health_check_path: config.health_check.path.clone(),
+            health_check_schedule: HealthCheckSchedule::from_config(config),
+            weight: config.weight,
+            enabled: AtomicBool::new(config.enabled),
+            timeout: Duration::from_millis(config.timeout_ms),
+            retry: RetryPolicy::from_config(config),
+            strategy: RwLock::new(config.endpoint_strategy()?),
+            current_endpoint_index: Arc::new(RwLock::new(0)),
+            capabilities: config.capabilities.clone(),
+            workflow: load_workflow(config)?,
+            default_model: config.models.first().cloned(),
+            client_id: uuid::Uuid::new_v4().to_string(),
+        })
+    }
+
+    /// Get the next endpoint whose circuit lets requests through, using the
+    /// backend's endpoint strategy
+    fn get_next_endpoint(&self) -> Option<(String, Arc<LoadStats>)> {
+        let endpoints = self.endpoints.read();
+        let available: Vec<_> = endpoints.iter().filter(|e| e.is_available()).collect();
+
+        if available.is_empty() {
+            return None;
+        }
+
+        let mut index = self.current_endpoint_index.write();
+        *index = (*index + 1) % available.len();
+        let selected = available[select_endpoint(*self.strategy.read(), &available, *index, |e| &e.load)?];
+        // A half-open circuit may have handed its last probe slot to a concurrent request
+        if !selected.breaker.try_acquire() {
+            return None;
+        }
+        Some((selected.url.clone(), selected.load.clone()))
+    }
+
+    /// Queue the workflow on the next healthy endpoint, wait for it and
+    /// download its images as base64
+    ///
+    /// Only queueing is retried; once a job is accepted its failures are final.
+    async fn generate_once(&self, workflow: &Value, deadline: Instant) -> Attempt<Vec<String>> {
+        let Some((endpoint, load)) = self.get_next_endpoint() else {
+            return Attempt::Done(Err(AppError::NoHealthyBackends(self.name.clone())));
+        };
+        let in_flight = load.start();
+        let started = Instant::now();
+
+        debug!(backend = %self.name, endpoint = %endpoint, "Queueing workflow");
+
+        let body = serde_json::json!({ "prompt": workflow, "client_id": self.client_id });
+        let response = match self.client.post(format!("{}/prompt", endpoint)).json(&body).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                self.mark_endpoint_unhealthy(&endpoint);
+                let retryable = e.is_connect();
+                let error = AppError::BackendError(format!("Request to {} failed: {}", endpoint, e));
+                // Nothing reached the backend on a connect error, so it is safe to retry
+                return if retryable {
+                    Attempt::Retry(Err(error), None)
+                } else {
+                    Attempt::Done(Err(error))
+                };
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let delay = retry_after(response.headers());
+            let body = response.text().await.unwrap_or_default();
+            if is_retryable_status(status) {
+                self.mark_endpoint_unhealthy(&endpoint);
+                return Attempt::Retry(
+                    Err(AppError::BackendError(format!("Backend returned {}: {}", status, body))),
+                    delay,
+                );
+            }
+            if status.is_server_error() {
+                self.mark_endpoint_unhealthy(&endpoint);
+                return Attempt::Done(Err(AppError::BackendError(format!("Backend returned {}: {}", status, body))));
+            }
+            // Validation errors (e.g. an unknown checkpoint) come back as 400 with `node_errors`
+            self.mark_endpoint_healthy(&endpoint, None);
+            return Attempt::Done(Err(AppError::InvalidRequest(format!("Workflow rejected: {}", body))));
+        }
+
+        let result = async {
+            let queued: QueuedPrompt = response
+                .json()
+                .await
+                .map_err(|e| AppError::BackendError(format!("Failed to parse response: {}", e)))?;
+            let cancel = PromptCancel::new(&self.client, &endpoint, &queued.prompt_id);
+            let entry = self.wait_for(&endpoint, &queued.prompt_id, deadline).await?;
+            cancel.disarm();
+            self.download_images(&endpoint, entry).await
+        }
+        .await;
+
+        match result {
+            Ok(images) => {
+                self.mark_endpoint_healthy(&endpoint, Some(started.elapsed()));
+                in_flight.finish();
+                Attempt::Done(Ok(images))
+            }
+            Err(e) => {
+                if !matches!(e, AppError::InvalidRequest(_)) {
+                    self.mark_endpoint_unhealthy(&endpoint);
+                }
+                Attempt::Done(Err(e))
+            }
+        }
+    }
+
+    /// Poll the job history until the prompt has finished
+    async fn wait_for(&self, endpoint: &str, prompt_id: &str, deadline: Instant) -> Result<HistoryEntry> {
+        let url = format!("{}/history/{}", endpoint, prompt_id);
+        let mut poll = tokio::time::interval(POLL_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = poll.tick() => {}
+                _ = tokio::time::sleep_until(deadline) => {
+                    return Err(AppError::Timeout(format!("ComfyUI job {} did not finish in time", prompt_id)));
+                }
+            }
+
+            let response = self.client.get(&url).send().await?;
+            if !response.status().is_success() {
+                return Err(AppError::BackendError(format!(
+                    "Backend returned {} for job history",
+                    response.status()
+                )));
+            }
+            // The history only lists a prompt once it has finished
+            let mut history: HashMap<String, HistoryEntry> = response
+                .json()
+                .await
+                .map_err(|e| AppError::BackendError(format!("Failed to parse job history: {}", e)))?;
+            let Some(entry) = history.remove(prompt_id) else {
+                continue;
+            };
+
+            if let Some(status) = &entry.status {
+                if status.status_str.as_deref() == Some("error") {
+                    return Err(AppError::BackendError(format!(
+                        "ComfyUI job {} failed: {}",
+                        prompt_id,
+                        Value::Array(status.messages.clone())
+                    )));
+                }
+            }
+            return Ok(entry);
+        }
+    }
+
+    /// Download the images saved by a finished job, falling back to previews
+    /// when the workflow has no save node
+    async fn download_images(&self, endpoint: &str, entry: HistoryEntry) -> Result<Vec<String>> {
+        let outputs: Vec<OutputImage> = entry.outputs.into_values().flat_map(|o| o.images).collect();
+        if outputs.is_empty() {
+            return Err(AppError::BackendError("workflow produced no images".to_string()));
+        }
+        let saved = outputs.iter().any(|image| image.image_type == "output");
+
+        let mut images = Vec::new();
+        for image in outputs.iter().filter(|image| !saved || image.image_type == "output") {
+            let response = self
+                .client
+                .get(format!("{}/view", endpoint))
+                .query(&[
+                    ("filename", image.filename.as_str()),
+                    ("subfolder", image.subfolder.as_str()),
+                    ("type", image.image_type.as_str()),
+                ])
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(AppError::BackendError(format!(
+                    "Backend returned {} for image {}",
+                    response.status(),
+                    image.filename
+                )));
+            }
+            images.push(base64::encode(&response.bytes().await?));
+        }
+
+        Ok(images)
+    }
+
+    /// Record a failure against an endpoint's circuit breaker
+    fn mark_endpoint_unhealthy(&self, url: &str) {
+        let mut endpoints = self.endpoints.write();
+        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
+            endpoint.mark_unhealthy();
+            warn!(backend = %self.name, url = %url, circuit = %endpoint.breaker.state(), "Endpoint request failed");
+        }
+    }
+
+    /// Record a success against an endpoint's circuit breaker
+    fn mark_endpoint_healthy(&self, url: &str, latency: Option<Duration>) {
+        let mut endpoints = self.endpoints.write();
+        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
+            endpoint.mark_healthy(latency);
+            debug!(backend = %self.name, url = %url, circuit = %endpoint.breaker.state(), "Endpoint request succeeded");
+        }
+    }
+}
+
+/// Cancels a queued ComfyUI prompt when dropped, unless disarmed once the
+/// prompt has finished, so abandoned jobs do not keep the GPU busy
+struct PromptCancel {
+    client: Client,
+    endpoint: String,
+    prompt_id: String,
+    armed: bool,
+}
+
+impl PromptCancel {
+    fn new(client: &Client, endpoint: &str, prompt_id: &str) -> Self {
+        Self {
+            client: client.clone(),
+            endpoint: endpoint.to_string(),
+            prompt_id: prompt_id.to_string(),
+            armed: true,
+        }
+    }
+
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for PromptCancel {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let client = self.client.clone();
+        let endpoint = std::mem::take(&mut self.endpoint);
+        let prompt_id = std::mem::take(&mut self.prompt_id);
+        runtime.spawn(async move {
+            debug!(endpoint = %endpoint, prompt_id = %prompt_id, "Cancelling abandoned ComfyUI job");
+            // Drop the prompt if it is still queued, and stop it if it is running
+            let delete = serde_json::json!({ "delete": [&prompt_id] });
+            if let Err(e) = client.post(format!("{}/queue", endpoint)).json(&delete).send().await {
+                warn!(endpoint = %endpoint, prompt_id = %prompt_id, error = %e, "Failed to remove ComfyUI job from the queue");
+            }
+            let interrupt = serde_json::json!({ "prompt_id": &prompt_id });
+            if let Err(e) = client.post(format!("{}/interrupt", endpoint)).json(&interrupt).send().await {
+                warn!(endpoint = %endpoint, prompt_id = %prompt_id, error = %e, "Failed to interrupt ComfyUI job");
+            }
+        });
+    }
+}
+
+/// Read and parse the workflow template named in the configuration
+fn load_workflow(config: &BackendConfig) -> Result<Value> {
+    let config_error = |message: String| AppError::Config(config::ConfigError::Message(message));
+
+    let path = config.workflow.as_ref().ok_or_else(|| {
+        config_error(format!("Backend '{}': comfyui backends need a workflow template", config.name))
+    })?;
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| config_error(format!("Backend '{}': failed to read workflow {}: {}", config.name, path, e)))?;
+    let workflow: Value = serde_json::from_str(&contents)
+        .map_err(|e| config_error(format!("Backend '{}': invalid workflow {}: {}", config.name, path, e)))?;
+    if !workflow.is_object() {
+        return Err(config_error(format!(
+            "Backend '{}': workflow {} must be exported in API format",
+            config.name, path
+        )));
+    }
+
+    Ok(workflow)
+}
+
+/// Replace placeholders in a workflow template
+fn fill_template(template: &Value, values: &HashMap<&str, Value>) -> Value {
+    match template {
+        Value::String(s) => {
+            if let Some(value) = s
+                .strip_prefix("{{")
+                .and_then(|s| s.strip_suffix("}}"))
+                .and_then(|key| values.get(key))
+            {
+                return value.clone();
+            }
+            let mut filled = s.clone();
+            for (key, value) in values {
+                let text = match value {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                filled = filled.replace(&format!("{{{{{}}}}}", key), &text);
+            }
+            Value::String(filled)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| fill_template(item, values)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), fill_template(value, values)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[async_trait]
+impl ImageBackend for ComfyUiBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        "comfyui"
+    }
+
+    fn endpoints(&self) -> Vec<String> {
+        self.endpoints.read().iter().map(|e| e.url.clone()).collect()
+    }
+
+    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
+        // ComfyUI needs an explicit seed; report the one used
+        let seed = request.seed.unwrap_or_else(|| rand::random::<u32>() as i64);
+        let model = request.model.clone().or_else(|| self.default_model.clone());
+
+        let mut values: HashMap<&str, Value> = HashMap::from([
+            ("prompt", Value::from(request.prompt)),
+            ("negative_prompt", Value::from(request.negative_prompt.unwrap_or_default())),
+            ("seed", Value::from(seed)),
+            ("steps", Value::from(request.num_inference_steps.unwrap_or(DEFAULT_STEPS))),
+            ("cfg", Value::from(request.guidance_scale.unwrap_or(DEFAULT_CFG))),
+            ("width", Value::from(request.width)),
+            ("height", Value::from(request.height)),
+            ("batch_size", Value::from(request.n)),
+        ]);
+        if let Some(model) = &model {
+            values.insert("model", Value::from(model.clone()));
+        }
+        let workflow = fill_template(&self.workflow, &values);
+
+        let deadline = Instant::now() + self.timeout;
+        let images = self
+            .retry
+            .run(deadline, |_| self.generate_once(&workflow, deadline))
+            .await?;
+
+        Ok(GenerateResponse {
+            images: images
+                .into_iter()
+                .map(|image| GeneratedImage {
+                    b64_json: Some(image),
+                    url: None,
+                    revised_prompt: None,
+                    seed: Some(seed),
+                })
+                .collect(),
+            model,
+            backend: None,
+        })
+    }
+
+    async fn health_check(&self) -> bool {
+        let endpoints = self.endpoints.read().clone();
+        let mut any_healthy = false;
+
+        for endpoint in &endpoints {
+            let url = format!("{}{}", endpoint.url, self.health_check_path);
+
+            match self.client.get(&url).timeout(self.health_check_schedule.timeout).send().await {
+                Ok(response) if response.status().is_success() => {
+                    self.mark_endpoint_healthy(&endpoint.url, None);
+                    any_healthy = true;
+                    debug!(backend = %self.name, endpoint = %endpoint.url, "Health check passed");
+                }
+                Ok(response) => {
+                    self.mark_endpoint_unhealthy(&endpoint.url);
+                    debug!(
+                        backend = %self.name,
+                        endpoint = %endpoint.url,
+                        status = %response.status(),
+                        "Health check failed"
+                    );
+                }
+                Err(e) => {
+                    self.mark_endpoint_unhealthy(&endpoint.url);
+                    debug!(backend = %self.name, endpoint = %endpoint.url, error = %e, "Health check failed");
+                }
+            }
+        }
+
+        any_healthy
+    }
+
+    fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        self.health_check_schedule
+    }
+
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        *self.strategy.read()
+    }
+
+    fn set_endpoint_strategy(&self, strategy: LoadBalancingStrategy) {
+        *self.strategy.write() = strategy;
+    }
+
+    fn circuits(&self) -> Vec<EndpointCircuit> {
+        self.endpoints.read().iter().map(BackendEndpoint::circuit).collect()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.capabilities.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProtocolType;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const WORKFLOW: &str = r#"{
+        "3": {"class_type": "KSampler", "inputs": {"seed": "{{seed}}", "steps": "{{steps}}", "cfg": "{{cfg}}"}},
+        "4": {"class_type": "CheckpointLoaderSimple", "inputs": {"ckpt_name": "{{model}}"}},
+        "5": {"class_type": "EmptyLatentImage", "inputs": {"width": "{{width}}", "height": "{{height}}", "batch_size": "{{batch_size}}"}},
+        "6": {"class_type": "CLIPTextEncode", "inputs": {"text": "masterpiece, {{prompt}}"}},
+        "9": {"class_type": "SaveImage", "inputs": {"filename_prefix": "gateway"}}
+    }"#;
+
+    fn test_backend(server: &MockServer, dir: &tempfile::TempDir, timeout_ms: u64) -> ComfyUiBackend {
+        let workflow = dir.path().join("workflow.json");
+        std::fs::write(&workflow, WORKFLOW).unwrap();
+        ComfyUiBackend::new(&BackendConfig {
+            name: "comfyui".to_string(),
+            protocol: ProtocolType::ComfyUi,
+            endpoints: vec![server.uri()],
+            models: vec!["sdxl.safetensors".to_string()],
+            workflow: Some(workflow.to_string_lossy().into_owned()),
+            timeout_ms,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    async fn mount_prompt(server: &MockServer) {
+        Mock::given(method("POST"))
+            .and(path("/prompt"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "prompt_id": "job-1", "number": 0, "node_errors": {}
+            })))
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn test_fill_template() {
+        let template: Value = serde_json::from_str(WORKFLOW).unwrap();
+        let values = HashMap::from([
+            ("prompt", Value::from("a fox")),
+            ("seed", Value::from(42)),
+            ("steps", Value::from(30)),
+        ]);
+
+        let filled = fill_template(&template, &values);
+        assert_eq!(filled["3"]["inputs"]["seed"], 42);
+        assert_eq!(filled["3"]["inputs"]["steps"], 30);
+        assert_eq!(filled["6"]["inputs"]["text"], "masterpiece, a fox");
+        // Unknown placeholders are left for ComfyUI to reject
+        assert_eq!(filled["4"]["inputs"]["ckpt_name"], "{{model}}");
+    }
+
+    #[tokio::test]
+    async fn test_generate_runs_workflow() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/prompt"))
+            .and(body_partial_json(serde_json::json!({
+                "prompt": {
+                    "3": {"inputs": {"seed": 7, "steps": 20, "cfg": 7.0}},
+                    "4": {"inputs": {"ckpt_name": "sdxl.safetensors"}},
+                    "5": {"inputs": {"width": 512, "height": 768, "batch_size": 1}}
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "prompt_id": "job-1", "number": 0, "node_errors": {}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/history/job-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "job-1": {
+                    "outputs": {
+                        "9": {"images": [{"filename": "gateway_00001_.png", "subfolder": "", "type": "output"}]}
+                    },
+                    "status": {"status_str": "success", "completed": true, "messages": []}
+                }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/view"))
+            .and(query_param("filename", "gateway_00001_.png"))
+            .and(query_param("type", "output"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"hello".to_vec()))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let backend = test_backend(&server, &dir, 60000);
+
+        let response = backend
+            .generate(GenerateRequest {
+                prompt: "a fox".to_string(),
+                negative_prompt: None,
+                n: 1,
+                width: 512,
+                height: 768,
+                model: None,
+                seed: Some(7),
+                guidance_scale: None,
+                num_inference_steps: None,
+                quality: None,
+                style: None,
+                response_format: "b64_json".to_string(),
+                extra_params: None,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.images.len(), 1);
+        assert_eq!(response.images[0].b64_json.as_deref(), Some("aGVsbG8="));
+        assert_eq!(response.images[0].seed, Some(7));
+        assert_eq!(response.model.as_deref(), Some("sdxl.safetensors"));
+    }
+
+    #[tokio::test]
+    async fn test_workflow_without_images_fails() {
+        let server = MockServer::start().await;
+        mount_prompt(&server).await;
+        Mock::given(method("GET"))
+            .and(path("/history/job-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "job-1": {
+                    "outputs": {},
+                    "status": {"status_str": "success", "completed": true, "messages": []}
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let backend = test_backend(&server, &dir, 60000);
+        let result = backend.generate(GenerateRequest::default()).await;
+
+        assert!(matches!(result, Err(AppError::BackendError(message)) if message.contains("no images")));
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_job_is_cancelled() {
+        let server = MockServer::start().await;
+        mount_prompt(&server).await;
+        // The prompt never shows up in the history
+        Mock::given(method("GET"))
+            .and(path("/history/job-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/queue"))
+            .and(body_partial_json(serde_json::json!({"delete": ["job-1"]})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/interrupt"))
+            .and(body_partial_json(serde_json::json!({"prompt_id": "job-1"})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let backend = test_backend(&server, &dir, 300);
+        let result = backend.generate(GenerateRequest::default()).await;
+        assert!(matches!(result, Err(AppError::Timeout(_))));
+
+        // Cancellation runs in the background
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        server.verify().await;
+    }
+}
@@ -2,16 +2,30 @@
 
 use async_trait::async_trait;
 use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::time::Instant;
 use tonic::transport::{Channel, Endpoint};
+use tonic::Code;
 use tracing::{debug, warn};
 
+use crate::backend::proto::imagebackend::{
+    image_backend_service_client::ImageBackendServiceClient,
+    ControlInput as ProtoControlInput, GenerateRequest as ProtoGenerateRequest,
+    GenerateResponse as ProtoGenerateResponse,
+};
 use crate::backend::traits::{
-    BackendEndpoint, GenerateRequest, GenerateResponse, ImageBackend,
+    BackendEndpoint, EndpointCircuit, GenerateRequest, GenerateResponse, GeneratedImage,
+    ImageBackend,
 };
 use crate::config::BackendConfig;
 use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckSchedule;
+use crate::gateway::load_balancer::LoadBalancingStrategy;
+use crate::gateway::load_tracker::{select_endpoint, LoadStats};
+use crate::gateway::retry::{Attempt, RetryPolicy};
+use crate::response::base64;
 
 /// gRPC-based image generation backend
 pub struct GrpcBackend {
@@ -19,9 +33,13 @@ pub struct GrpcBackend {
     endpoints: Arc<RwLock<Vec<BackendEndpoint>>>,
     channels: Arc<RwLock<Vec<Option<Channel>>>>,
     timeout_ms: u64,
+    health_check_schedule: HealthCheckSchedule,
     weight: u32,
-    enabled: bool,
+    enabled: AtomicBool,
+    retry: RetryPolicy,
+    strategy: RwLock<LoadBalancingStrategy>,
     current_endpoint_index: Arc<RwLock<usize>>,
+    capabilities: Vec<String>,
 }
 
 impl GrpcBackend {
@@ -30,7 +48,7 @@ impl GrpcBackend {
         let endpoints: Vec<BackendEndpoint> = config
             .endpoints
             .iter()
-            .map(|url| BackendEndpoint::new(url.clone()))
+            .map(|url| BackendEndpoint::with_circuit_breaker(url.clone(), &config.circuit_breaker))
             .collect();
 
         let channels: Vec<Option<Channel>> = vec![None; endpoints.len()];
@@ -40,9 +58,13 @@ impl GrpcBackend {
             endpoints: Arc::new(RwLock::new(endpoints)),
             channels: Arc::new(RwLock::new(channels)),
             timeout_ms: config.timeout_ms,
+            health_check_schedule: HealthCheckSchedule::from_config(config),
             weight: config.weight,
-            enabled: config.enabled,
+            enabled: AtomicBool::new(config.enabled),
+            retry: RetryPolicy::from_config(config),
+            strategy: RwLock::new(config.endpoint_strategy()?),
             current_endpoint_index: Arc::new(RwLock::new(0)),
+            capabilities: config.capabilities.clone(),
         })
     }
 
@@ -73,7 +95,7 @@ impl GrpcBackend {
         let channel = endpoint
             .connect()
             .await
-            .map_err(|e| AppError::Grpc(tonic::Status::unavailable(format!("Connection failed: {}", e))))?;
+            .map_err(|e| AppError::BackendError(format!("Connection failed to {}: {}", endpoint_url, e)))?;
 
         // Store the channel
         {
@@ -86,32 +108,89 @@ impl GrpcBackend {
         Ok(channel)
     }
 
-    /// Get the next healthy endpoint index
-    fn get_next_healthy_index(&self) -> Option<usize> {
+    /// Get the index of the next endpoint whose circuit lets requests
+    /// through, using the backend's endpoint strategy
+    fn get_next_healthy_index(&self) -> Option<(usize, Arc<LoadStats>)> {
         let endpoints = self.endpoints.read();
-        let healthy_indices: Vec<usize> = endpoints
+        let available: Vec<(usize, &BackendEndpoint)> = endpoints
             .iter()
             .enumerate()
-            .filter(|(_, e)| e.healthy)
-            .map(|(i, _)| i)
+            .filter(|(_, e)| e.is_available())
             .collect();
 
-        if healthy_indices.is_empty() {
+        if available.is_empty() {
             return None;
         }
 
         let mut index = self.current_endpoint_index.write();
-        *index = (*index + 1) % healthy_indices.len();
-        Some(healthy_indices[*index])
+        *index = (*index + 1) % available.len();
+        let strategy = *self.strategy.read();
+        let (selected, endpoint) = available[select_endpoint(strategy, &available, *index, |(_, e)| &e.load)?];
+        // A half-open circuit may have handed its last probe slot to a concurrent request
+        if !endpoint.breaker.try_acquire() {
+            return None;
+        }
+        Some((selected, endpoint.load.clone()))
     }
 
-    /// Mark an endpoint as unhealthy
-    fn mark_endpoint_unhealthy(&self, index: usize) {
-        let mut endpoints = self.endpoints.write();
-        if let Some(endpoint) = endpoints.get_mut(index) {
-            endpoint.mark_unhealthy();
-            warn!(backend = %self.name, url = %endpoint.url, "Marked gRPC endpoint as unhealthy");
+    /// Send one generate request to the next healthy endpoint
+    async fn generate_once(&self, request: ProtoGenerateRequest) -> Attempt<ProtoGenerateResponse> {
+        let Some((index, load)) = self.get_next_healthy_index() else {
+            return Attempt::Done(Err(AppError::NoHealthyBackends(self.name.clone())));
+        };
+        let in_flight = load.start();
+        let started = Instant::now();
+
+        let channel = match self.get_channel(index).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                // Nothing was sent, so another endpoint can safely be tried
+                self.mark_endpoint_unhealthy(index);
+                return Attempt::Retry(Err(e), None);
+            }
+        };
+
+        debug!(backend = %self.name, "Sending gRPC generate request");
+
+        let mut grpc_request = tonic::Request::new(request);
+        grpc_request.set_timeout(Duration::from_millis(self.timeout_ms));
+
+        let mut client = ImageBackendServiceClient::new(channel);
+
+        match client.generate(grpc_request).await {
+            Ok(response) => {
+                self.mark_endpoint_healthy(index, Some(started.elapsed()));
+                in_flight.finish();
+                Attempt::Done(Ok(response.into_inner()))
+            }
+            Err(status) => {
+                // Transport-level failures and overload count against the endpoint's
+                // circuit; other errors mean it answered normally
+                match status.code() {
+                    Code::Unavailable | Code::Unknown => self.mark_endpoint_unhealthy(index),
+                    Code::ResourceExhausted => self.record_endpoint_failure(index),
+                    _ => self.mark_endpoint_healthy(index, None),
+                }
+                warn!(
+                    backend = %self.name,
+                    code = ?status.code(),
+                    message = %status.message(),
+                    "gRPC generate request failed"
+                );
+                let retryable = matches!(status.code(), Code::Unavailable | Code::ResourceExhausted);
+                let outcome = Err(AppError::from(status));
+                if retryable {
+                    Attempt::Retry(outcome, None)
+                } else {
+                    Attempt::Done(outcome)
+                }
+            }
         }
+    }
+
+    /// Record a failure against an endpoint's circuit breaker and drop its channel
+    fn mark_endpoint_unhealthy(&self, index: usize) {
+        self.record_endpoint_failure(index);
 
         // Clear the channel so it will be recreated
         let mut channels = self.channels.write();
@@ -120,12 +199,21 @@ impl GrpcBackend {
         }
     }
 
-    /// Mark an endpoint as healthy
-    fn mark_endpoint_healthy(&self, index: usize) {
+    /// Record a failure against an endpoint's circuit breaker, keeping its channel
+    fn record_endpoint_failure(&self, index: usize) {
         let mut endpoints = self.endpoints.write();
         if let Some(endpoint) = endpoints.get_mut(index) {
-            endpoint.mark_healthy();
-            debug!(backend = %self.name, url = %endpoint.url, "Marked gRPC endpoint as healthy");
+            endpoint.mark_unhealthy();
+            warn!(backend = %self.name, url = %endpoint.url, circuit = %endpoint.breaker.state(), "gRPC endpoint request failed");
+        }
+    }
+
+    /// Record a success against an endpoint's circuit breaker
+    fn mark_endpoint_healthy(&self, index: usize, latency: Option<Duration>) {
+        let mut endpoints = self.endpoints.write();
+        if let Some(endpoint) = endpoints.get_mut(index) {
+            endpoint.mark_healthy(latency);
+            debug!(backend = %self.name, url = %endpoint.url, circuit = %endpoint.breaker.state(), "gRPC endpoint request succeeded");
         }
     }
 }
@@ -144,51 +232,16 @@ impl ImageBackend for GrpcBackend {
         self.endpoints.read().iter().map(|e| e.url.clone()).collect()
     }
 
-    async fn generate(&self, _request: GenerateRequest) -> Result<GenerateResponse> {
-        let index = self
-            .get_next_healthy_index()
-            .ok_or_else(|| AppError::NoHealthyBackends(self.name.clone()))?;
-
-        let _channel = self.get_channel(index).await?;
-
-        debug!(backend = %self.name, "Sending gRPC generate request");
-
-        // Create gRPC client and make request
-        // For now, we'll simulate the gRPC call since we don't have a real gRPC server
-        // In production, this would use the generated protobuf client
-        
-        // Simulated response for demonstration
-        // TODO: Replace with actual gRPC client call when backend is available
-        /*
-        use crate::backend::proto::imagebackend::image_backend_service_client::ImageBackendServiceClient;
-        use crate::backend::proto::imagebackend::GenerateRequest as ProtoRequest;
-        
-        let mut client = ImageBackendServiceClient::new(channel);
-        
-        let proto_request = ProtoRequest {
-            prompt: request.prompt,
-            negative_prompt: request.negative_prompt.unwrap_or_default(),
-            n: request.n as i32,
-            width: request.width as i32,
-            height: request.height as i32,
-            model: request.model.unwrap_or_default(),
-            seed: request.seed.unwrap_or(-1),
-            guidance_scale: request.guidance_scale.unwrap_or(7.5),
-            num_inference_steps: request.num_inference_steps.unwrap_or(50) as i32,
-            response_format: request.response_format,
-            extra_params: String::new(),
-        };
-        
-        let response = client.generate(proto_request).await?;
-        */
+    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
+        let requested_model = request.model.clone();
+        let proto_request = to_proto_request(request)?;
 
-        // For now, return an error indicating gRPC backend needs actual server
-        self.mark_endpoint_healthy(index);
-        
-        // Placeholder response - in real implementation, this would come from gRPC server
-        Err(AppError::BackendError(
-            "gRPC backend requires a running gRPC server. Please configure an HTTP backend or start the gRPC server.".to_string()
-        ))
+        let deadline = Instant::now() + Duration::from_millis(self.timeout_ms);
+        let response = self
+            .retry
+            .run(deadline, |_| self.generate_once(proto_request.clone()))
+            .await?;
+        Ok(from_proto_response(response, requested_model))
     }
 
     async fn health_check(&self) -> bool {
@@ -200,7 +253,7 @@ impl ImageBackend for GrpcBackend {
                 Ok(_channel) => {
                     // TODO: Make actual gRPC health check call
                     // For now, just check if we can connect
-                    self.mark_endpoint_healthy(index);
+                    self.mark_endpoint_healthy(index, None);
                     any_healthy = true;
                     debug!(
                         backend = %self.name,
@@ -228,7 +281,292 @@ impl ImageBackend for GrpcBackend {
     }
 
     fn is_enabled(&self) -> bool {
-        self.enabled
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        self.health_check_schedule
+    }
+
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        *self.strategy.read()
+    }
+
+    fn set_endpoint_strategy(&self, strategy: LoadBalancingStrategy) {
+        *self.strategy.write() = strategy;
+    }
+
+    fn circuits(&self) -> Vec<EndpointCircuit> {
+        self.endpoints.read().iter().map(BackendEndpoint::circuit).collect()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.capabilities.clone()
+    }
+}
+
+
+/// Convert a gateway request into the `imagebackend.GenerateRequest` proto
+///
+/// Unset optional fields fall back to the proto's sentinels (empty string,
+/// `-1` for a random seed) or common diffusion defaults.
+fn to_proto_request(request: GenerateRequest) -> Result<ProtoGenerateRequest> {
+    let extra_params = match request.extra_params {
+        Some(params) => serde_json::to_string(&params)?,
+        None => String::new(),
+    };
+    // Images travel as raw bytes; the gateway API carries them as base64
+    let decode = |data: Option<String>| match data {
+        Some(data) => base64::decode(&data),
+        None => Ok(Vec::new()),
+    };
+
+    Ok(ProtoGenerateRequest {
+        prompt: request.prompt,
+        negative_prompt: request.negative_prompt.unwrap_or_default(),
+        n: request.n as i32,
+        width: request.width as i32,
+        height: request.height as i32,
+        model: request.model.unwrap_or_default(),
+        seed: request.seed.unwrap_or(-1),
+        guidance_scale: request.guidance_scale.unwrap_or(7.5),
+        num_inference_steps: request.num_inference_steps.unwrap_or(50) as i32,
+        response_format: request.response_format,
+        extra_params,
+        image: decode(request.image)?,
+        mask: decode(request.mask)?,
+        variation: request.variation,
+        init_image: decode(request.init_image)?,
+        denoising_strength: request.denoising_strength,
+        controls: request
+            .controls
+            .into_iter()
+            .map(|control| {
+                Ok(ProtoControlInput {
+                    r#type: control.control_type,
+                    image: base64::decode(&control.image)?,
+                    model: control.model.unwrap_or_default(),
+                    weight: control.weight,
+                    start: control.start,
+                    end: control.end,
+                })
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+/// Convert an `imagebackend.GenerateResponse` proto into a gateway response
+fn from_proto_response(
+    response: ProtoGenerateResponse,
+    requested_model: Option<String>,
+) -> GenerateResponse {
+    let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+
+    let images = response
+        .data
+        .into_iter()
+        .map(|img| GeneratedImage {
+            b64_json: non_empty(img.b64_json),
+            url: non_empty(img.url),
+            revised_prompt: non_empty(img.revised_prompt),
+            seed: if img.seed >= 0 { Some(img.seed) } else { None },
+        })
+        .collect();
+
+    GenerateResponse {
+        images,
+        model: non_empty(response.model).or(requested_model),
+        backend: None,
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::proto::imagebackend::{
+        image_backend_service_server::{ImageBackendService, ImageBackendServiceServer},
+        GetInfoRequest, GetInfoResponse, HealthCheckRequest, HealthCheckResponse, ImageData,
+    };
+    use tonic::transport::server::TcpIncoming;
+    use tonic::{Request, Response, Status};
+
+    /// In-process mock of a gRPC diffusion worker
+    struct MockImageService;
+
+    #[tonic::async_trait]
+    impl ImageBackendService for MockImageService {
+        async fn generate(
+            &self,
+            request: Request<ProtoGenerateRequest>,
+        ) -> std::result::Result<Response<ProtoGenerateResponse>, Status> {
+            let request = request.into_inner();
+            if request.prompt.is_empty() {
+                return Err(Status::invalid_argument("prompt must not be empty"));
+            }
+            if request.prompt == "overloaded" {
+                return Err(Status::resource_exhausted("GPU queue full"));
+            }
+
+            let extra: serde_json::Value =
+                serde_json::from_str(&request.extra_params).unwrap_or_default();
+
+            let data = (0..request.n)
+                .map(|i| ImageData {
+                    b64_json: "aW1hZ2U=".to_string(),
+                    url: String::new(),
+                    revised_prompt: extra["sampler"].as_str().unwrap_or_default().to_string(),
+                    seed: request.seed + i as i64,
+                })
+                .collect();
+
+            Ok(Response::new(ProtoGenerateResponse {
+                created: 0,
+                data,
+                model: format!("{}x{}", request.width, request.height),
+            }))
+        }
+
+        async fn health_check(
+            &self,
+            _request: Request<HealthCheckRequest>,
+        ) -> std::result::Result<Response<HealthCheckResponse>, Status> {
+            Ok(Response::new(HealthCheckResponse {
+                healthy: true,
+                message: String::new(),
+                available_models: vec![],
+            }))
+        }
+
+        async fn get_info(
+            &self,
+            _request: Request<GetInfoRequest>,
+        ) -> std::result::Result<Response<GetInfoResponse>, Status> {
+            Err(Status::unimplemented("not needed"))
+        }
+    }
+
+    async fn start_mock_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(ImageBackendServiceServer::new(MockImageService))
+                .serve_with_incoming(incoming),
+        );
+
+        format!("http://{}", addr)
+    }
+
+    fn test_request(prompt: &str) -> GenerateRequest {
+        GenerateRequest {
+            prompt: prompt.to_string(),
+            negative_prompt: None,
+            n: 2,
+            width: 512,
+            height: 768,
+            model: None,
+            seed: Some(42),
+            guidance_scale: None,
+            num_inference_steps: None,
+            quality: None,
+            style: None,
+            response_format: "b64_json".to_string(),
+            extra_params: Some(serde_json::json!({ "sampler": "euler_a" })),
+            ..Default::default()
+        }
+    }
+
+    async fn test_backend() -> GrpcBackend {
+        let config = BackendConfig {
+            name: "grpc-test".to_string(),
+            protocol: crate::config::ProtocolType::Grpc,
+            endpoints: vec![start_mock_server().await],
+            timeout_ms: 5000,
+            ..Default::default()
+        };
+        GrpcBackend::new(&config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_generate_round_trip() {
+        let backend = test_backend().await;
+
+        let response = backend.generate(test_request("a red fox")).await.unwrap();
+
+        assert_eq!(response.images.len(), 2);
+        assert_eq!(response.model.as_deref(), Some("512x768"));
+        assert_eq!(response.images[0].b64_json.as_deref(), Some("aW1hZ2U="));
+        assert_eq!(response.images[0].url, None);
+        assert_eq!(response.images[0].revised_prompt.as_deref(), Some("euler_a"));
+        assert_eq!(response.images[1].seed, Some(43));
+    }
+
+    #[tokio::test]
+    async fn test_generate_maps_status_codes() {
+        let backend = test_backend().await;
+
+        let err = backend.generate(test_request("")).await.unwrap_err();
+        assert!(matches!(err, AppError::InvalidRequest(_)));
+
+        let err = backend.generate(test_request("overloaded")).await.unwrap_err();
+        assert!(matches!(err, AppError::BackendError(message) if message == "Backend overloaded: GPU queue full"));
+    }
+
+    #[test]
+    fn test_images_sent_as_bytes() {
+        let request = GenerateRequest {
+            image: Some("data:image/png;base64,aW1hZ2U=".to_string()),
+            mask: Some("bWFzaw==".to_string()),
+            ..test_request("a red fox")
+        };
+        let proto = to_proto_request(request).unwrap();
+        assert_eq!(proto.image, b"image");
+        assert_eq!(proto.mask, b"mask");
+        assert!(to_proto_request(test_request("a red fox")).unwrap().image.is_empty());
+
+        let request = GenerateRequest {
+            init_image: Some("aW5pdA==".to_string()),
+            denoising_strength: Some(0.5),
+            controls: vec![crate::backend::traits::ControlInput {
+                control_type: "canny".to_string(),
+                image: "Y2Fubnk=".to_string(),
+                model: None,
+                weight: 0.7,
+                start: 0.1,
+                end: 0.9,
+            }],
+            ..test_request("a red fox")
+        };
+        let proto = to_proto_request(request).unwrap();
+        assert_eq!(proto.init_image, b"init");
+        assert_eq!(proto.denoising_strength, Some(0.5));
+        assert_eq!(proto.controls[0].r#type, "canny");
+        assert_eq!(proto.controls[0].image, b"canny");
+
+        let request = GenerateRequest {
+            image: Some("not base64!".to_string()),
+            ..test_request("a red fox")
+        };
+        assert!(matches!(to_proto_request(request), Err(AppError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_generate_unreachable_endpoint() {
+        let config = BackendConfig {
+            name: "grpc-down".to_string(),
+            protocol: crate::config::ProtocolType::Grpc,
+            endpoints: vec!["http://127.0.0.1:1".to_string()],
+            ..Default::default()
+        };
+        let backend = GrpcBackend::new(&config).await.unwrap();
+
+        let err = backend.generate(test_request("a red fox")).await.unwrap_err();
+        assert!(matches!(err, AppError::BackendError(_)));
+    }
+}
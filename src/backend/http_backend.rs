@@ -1,18 +1,33 @@
 //! HTTP backend client implementation
+//!
+//! Generic HTTP image servers differ in where they accept requests. Each
+//! endpoint's [`ApiStyle`] is learned from its first successful response, or
+//! pinned with `api_style` in the backend configuration, so later requests go
+//! straight to the right URL. The other styles are only tried again when the
+//! learned one stops answering (404/405 or an unparseable response).
 
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{debug, warn};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
 
 use crate::backend::traits::{
-    BackendEndpoint, GenerateRequest, GenerateResponse, GeneratedImage, ImageBackend,
+    BackendEndpoint, ControlInput, EndpointCircuit, GenerateRequest, GenerateResponse, GeneratedImage,
+    ImageBackend,
 };
 use crate::config::BackendConfig;
 use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckSchedule;
+use crate::gateway::load_balancer::LoadBalancingStrategy;
+use crate::gateway::load_tracker::{select_endpoint, LoadStats};
+use crate::gateway::retry::{is_retryable_status, retry_after, Attempt, RetryPolicy};
 
 /// HTTP-based image generation backend
 pub struct HttpBackend {
@@ -20,9 +35,81 @@ pub struct HttpBackend {
     client: Client,
     endpoints: Arc<RwLock<Vec<BackendEndpoint>>>,
     health_check_path: String,
+    health_check_schedule: HealthCheckSchedule,
     weight: u32,
-    enabled: bool,
+    enabled: AtomicBool,
+    timeout: Duration,
+    retry: RetryPolicy,
+    strategy: RwLock<LoadBalancingStrategy>,
     current_endpoint_index: Arc<RwLock<usize>>,
+    capabilities: Vec<String>,
+    /// API style from the configuration; never re-probed
+    pinned_style: Option<ApiStyle>,
+    /// API style learned per endpoint URL
+    learned_styles: RwLock<HashMap<String, ApiStyle>>,
+}
+
+/// Request API spoken by a generic HTTP image server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiStyle {
+    /// `POST /v1/images/generations`
+    OpenAI,
+    /// `POST /generate`
+    Generate,
+    /// `POST /api/generate`
+    ApiGenerate,
+    /// `POST /sdapi/v1/txt2img` (Automatic1111)
+    Automatic1111,
+}
+
+impl ApiStyle {
+    /// All styles, in probing order
+    pub const ALL: [ApiStyle; 4] = [
+        ApiStyle::OpenAI,
+        ApiStyle::Generate,
+        ApiStyle::ApiGenerate,
+        ApiStyle::Automatic1111,
+    ];
+
+    /// Path of the generate request, relative to the endpoint
+    pub fn path(&self) -> &'static str {
+        match self {
+            ApiStyle::OpenAI => "/v1/images/generations",
+            ApiStyle::Generate => "/generate",
+            ApiStyle::ApiGenerate => "/api/generate",
+            ApiStyle::Automatic1111 => "/sdapi/v1/txt2img",
+        }
+    }
+
+    /// Name used in configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiStyle::OpenAI => "openai",
+            ApiStyle::Generate => "generate",
+            ApiStyle::ApiGenerate => "api_generate",
+            ApiStyle::Automatic1111 => "a1111",
+        }
+    }
+}
+
+impl FromStr for ApiStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "openai" => Ok(ApiStyle::OpenAI),
+            "generate" => Ok(ApiStyle::Generate),
+            "api_generate" => Ok(ApiStyle::ApiGenerate),
+            "a1111" | "sdapi" => Ok(ApiStyle::Automatic1111),
+            other => Err(format!("Unknown API style '{}'", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Generic API request for HTTP backends
@@ -47,19 +134,62 @@ struct ApiGenerateRequest {
     num_inference_steps: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     response_format: Option<String>,
+    /// Base64 source image for edits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    /// Base64 mask for edits; transparent areas are repainted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mask: Option<String>,
+    /// Generate variations of `image` rather than editing it
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    variation: bool,
+    /// Base64 image to start from (img2img)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    init_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    denoising_strength: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    controls: Vec<ControlInput>,
 }
 
 /// Generic API response from HTTP backends
 #[derive(Debug, Deserialize)]
 struct ApiGenerateResponse {
     #[serde(default)]
-    images: Vec<ApiImageData>,
+    images: Vec<ApiImage>,
     #[serde(default)]
-    data: Vec<ApiImageData>,
+    data: Vec<ApiImage>,
     #[serde(default)]
     model: Option<String>,
 }
 
+/// Image entry of a response: a bare base64 string (Automatic1111) or an object
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ApiImage {
+    Base64(String),
+    Data(ApiImageData),
+}
+
+impl From<ApiImage> for GeneratedImage {
+    fn from(image: ApiImage) -> Self {
+        match image {
+            ApiImage::Base64(b64_json) => GeneratedImage {
+                b64_json: Some(b64_json),
+                url: None,
+                revised_prompt: None,
+                seed: None,
+            },
+            ApiImage::Data(image) => GeneratedImage {
+                b64_json: image.b64_json.or(image.base64),
+                url: image.url,
+                revised_prompt: image.revised_prompt,
+                seed: image.seed,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ApiImageData {
     #[serde(default)]
@@ -85,7 +215,7 @@ impl HttpBackend {
         let endpoints: Vec<BackendEndpoint> = config
             .endpoints
             .iter()
-            .map(|url| BackendEndpoint::new(url.clone()))
+            .map(|url| BackendEndpoint::with_circuit_breaker(url.clone(), &config.circuit_breaker))
             .collect();
 
         Ok(Self {
@@ -93,97 +223,77 @@ impl HttpBackend {
             client,
             endpoints: Arc::new(RwLock::new(endpoints)),
             health_check_path: config.health_check_path.clone(),
+            health_check_schedule: HealthCheckSchedule::from_config(config),
             weight: config.weight,
-            enabled: config.enabled,
+            enabled: AtomicBool::new(config.enabled),
+            timeout: Duration::from_millis(config.timeout_ms),
+            retry: RetryPolicy::from_config(config),
+            strategy: RwLock::new(config.endpoint_strategy()?),
             current_endpoint_index: Arc::new(RwLock::new(0)),
+            capabilities: config.capabilities.clone(),
+            pinned_style: config.api_style()?,
+            learned_styles: RwLock::new(HashMap::new()),
         })
     }
 
-    /// Get the next healthy endpoint using round-robin
-    fn get_next_endpoint(&self) -> Option<String> {
+    /// API style used for an endpoint: pinned, learned, or `None` if it
+    /// still has to be probed
+    pub fn api_style(&self, endpoint: &str) -> Option<ApiStyle> {
+        self.pinned_style
+            .or_else(|| self.learned_styles.read().get(endpoint).copied())
+    }
+
+    /// Get the next endpoint whose circuit lets requests through, using the
+    /// backend's endpoint strategy
+    fn get_next_endpoint(&self) -> Option<(String, Arc<LoadStats>)> {
         let endpoints = self.endpoints.read();
-        let healthy_endpoints: Vec<_> = endpoints
+        let available: Vec<_> = endpoints
             .iter()
-            .filter(|e| e.healthy)
+            .filter(|e| e.is_available())
             .collect();
 
-        if healthy_endpoints.is_empty() {
+        if available.is_empty() {
             return None;
         }
 
         let mut index = self.current_endpoint_index.write();
-        *index = (*index + 1) % healthy_endpoints.len();
-        Some(healthy_endpoints[*index].url.clone())
-    }
-
-    /// Mark an endpoint as unhealthy
-    fn mark_endpoint_unhealthy(&self, url: &str) {
-        let mut endpoints = self.endpoints.write();
-        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
-            endpoint.mark_unhealthy();
-            warn!(backend = %self.name, url = %url, "Marked endpoint as unhealthy");
-        }
-    }
-
-    /// Mark an endpoint as healthy
-    fn mark_endpoint_healthy(&self, url: &str) {
-        let mut endpoints = self.endpoints.write();
-        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
-            endpoint.mark_healthy();
-            debug!(backend = %self.name, url = %url, "Marked endpoint as healthy");
+        *index = (*index + 1) % available.len();
+        let selected = available[select_endpoint(*self.strategy.read(), &available, *index, |e| &e.load)?];
+        // A half-open circuit may have handed its last probe slot to a concurrent request
+        if !selected.breaker.try_acquire() {
+            return None;
         }
-    }
-}
-
-#[async_trait]
-impl ImageBackend for HttpBackend {
-    fn name(&self) -> &str {
-        &self.name
-    }
-
-    fn protocol(&self) -> &str {
-        "http"
-    }
-
-    fn endpoints(&self) -> Vec<String> {
-        self.endpoints.read().iter().map(|e| e.url.clone()).collect()
+        Some((selected.url.clone(), selected.load.clone()))
     }
 
-    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
-        let endpoint = self
-            .get_next_endpoint()
-            .ok_or_else(|| AppError::NoHealthyBackends(self.name.clone()))?;
+    /// Send one generate request to the next healthy endpoint
+    async fn generate_once(&self, api_request: &ApiGenerateRequest) -> Attempt<GenerateResponse> {
+        let Some((endpoint, load)) = self.get_next_endpoint() else {
+            return Attempt::Done(Err(AppError::NoHealthyBackends(self.name.clone())));
+        };
+        let in_flight = load.start();
+        let started = Instant::now();
 
         debug!(backend = %self.name, endpoint = %endpoint, "Sending generate request");
 
-        let api_request = ApiGenerateRequest {
-            prompt: request.prompt,
-            negative_prompt: request.negative_prompt,
-            n: Some(request.n),
-            width: Some(request.width),
-            height: Some(request.height),
-            model: request.model,
-            seed: request.seed,
-            guidance_scale: request.guidance_scale,
-            num_inference_steps: request.num_inference_steps,
-            response_format: Some(request.response_format),
+        // A known style is tried first; the others only if it no longer matches
+        let known = self.api_style(&endpoint);
+        let styles: Vec<ApiStyle> = match (self.pinned_style, known) {
+            (Some(pinned), _) => vec![pinned],
+            (None, Some(learned)) => std::iter::once(learned)
+                .chain(ApiStyle::ALL.into_iter().filter(|s| *s != learned))
+                .collect(),
+            (None, None) => ApiStyle::ALL.to_vec(),
         };
 
-        // Try different endpoint patterns that common image generation APIs use
-        let urls_to_try = vec![
-            format!("{}/v1/images/generations", endpoint),
-            format!("{}/generate", endpoint),
-            format!("{}/api/generate", endpoint),
-            format!("{}/sdapi/v1/txt2img", endpoint), // Automatic1111 style
-        ];
-
         let mut last_error = None;
 
-        for url in &urls_to_try {
+        for style in styles {
+            let url = format!("{}{}", endpoint, style.path());
             match self
                 .client
-                .post(url)
-                .json(&api_request)
+                .post(&url)
+                .json(api_request)
                 .send()
                 .await
             {
@@ -191,26 +301,22 @@ impl ImageBackend for HttpBackend {
                     if response.status().is_success() {
                         match response.json::<ApiGenerateResponse>().await {
                             Ok(api_response) => {
-                                self.mark_endpoint_healthy(&endpoint);
+                                self.mark_endpoint_healthy(&endpoint, Some(started.elapsed()));
+                                in_flight.finish();
+                                self.learn_style(&endpoint, style);
                                 
                                 // Combine images from both possible response formats
                                 let mut all_images = api_response.images;
                                 all_images.extend(api_response.data);
                                 
-                                let images: Vec<GeneratedImage> = all_images
-                                    .into_iter()
-                                    .map(|img| GeneratedImage {
-                                        b64_json: img.b64_json.or(img.base64),
-                                        url: img.url,
-                                        revised_prompt: img.revised_prompt,
-                                        seed: img.seed,
-                                    })
-                                    .collect();
-
-                                return Ok(GenerateResponse {
+                                let images: Vec<GeneratedImage> =
+                                    all_images.into_iter().map(GeneratedImage::from).collect();
+
+                                return Attempt::Done(Ok(GenerateResponse {
                                     images,
                                     model: api_response.model,
-                                });
+                                    backend: None,
+                                }));
                             }
                             Err(e) => {
                                 last_error = Some(AppError::BackendError(format!(
@@ -219,32 +325,138 @@ impl ImageBackend for HttpBackend {
                                 )));
                             }
                         }
+                    } else if is_retryable_status(response.status()) {
+                        // The endpoint is up but overloaded; other URL patterns would fail the same way.
+                        // Sustained overload counts towards opening its circuit.
+                        self.mark_endpoint_unhealthy(&endpoint);
+                        let status = response.status();
+                        let delay = retry_after(response.headers());
+                        let body = response.text().await.unwrap_or_default();
+                        return Attempt::Retry(
+                            Err(AppError::BackendError(format!(
+                                "Backend returned {}: {}",
+                                status, body
+                            ))),
+                            delay,
+                        );
                     } else {
                         let status = response.status();
                         let body = response.text().await.unwrap_or_default();
-                        last_error = Some(AppError::BackendError(format!(
+                        let error = AppError::BackendError(format!(
                             "Backend returned {}: {}",
                             status, body
-                        )));
+                        ));
+                        // The known API answered, so the request itself was rejected
+                        if known == Some(style) && !is_style_mismatch(status) {
+                            self.mark_endpoint_healthy(&endpoint, None);
+                            return Attempt::Done(Err(error));
+                        }
+                        last_error = Some(error);
                     }
                 }
                 Err(e) if e.is_connect() || e.is_timeout() => {
                     // Connection or timeout error - don't try other URL patterns
                     self.mark_endpoint_unhealthy(&endpoint);
-                    return Err(AppError::BackendError(format!(
+                    let error = AppError::BackendError(format!(
                         "Connection failed to {}: {}",
                         endpoint, e
-                    )));
+                    ));
+                    // Nothing reached the backend on a connect error, so it is safe to retry
+                    return if e.is_connect() {
+                        Attempt::Retry(Err(error), None)
+                    } else {
+                        Attempt::Done(Err(error))
+                    };
                 }
                 Err(e) => {
                     last_error = Some(AppError::HttpClient(e));
                 }
             }
+
+            if known == Some(style) && self.pinned_style.is_none() {
+                self.learned_styles.write().remove(&endpoint);
+                info!(backend = %self.name, endpoint = %endpoint, api_style = %style, "API style no longer matches, probing again");
+            }
         }
 
         // If we get here, none of the URL patterns worked
         self.mark_endpoint_unhealthy(&endpoint);
-        Err(last_error.unwrap_or_else(|| AppError::BackendError("Unknown error".to_string())))
+        Attempt::Done(Err(last_error.unwrap_or_else(|| AppError::BackendError("Unknown error".to_string()))))
+    }
+
+    /// Remember the API style an endpoint answered on
+    fn learn_style(&self, endpoint: &str, style: ApiStyle) {
+        if self.pinned_style.is_some() {
+            return;
+        }
+        let previous = self.learned_styles.write().insert(endpoint.to_string(), style);
+        if previous != Some(style) {
+            info!(backend = %self.name, endpoint = %endpoint, api_style = %style, "Learned endpoint API style");
+        }
+    }
+
+    /// Record a failure against an endpoint's circuit breaker
+    fn mark_endpoint_unhealthy(&self, url: &str) {
+        let mut endpoints = self.endpoints.write();
+        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
+            endpoint.mark_unhealthy();
+            warn!(backend = %self.name, url = %url, circuit = %endpoint.breaker.state(), "Endpoint request failed");
+        }
+    }
+
+    /// Record a success against an endpoint's circuit breaker
+    fn mark_endpoint_healthy(&self, url: &str, latency: Option<Duration>) {
+        let mut endpoints = self.endpoints.write();
+        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
+            endpoint.mark_healthy(latency);
+            debug!(backend = %self.name, url = %url, circuit = %endpoint.breaker.state(), "Endpoint request succeeded");
+        }
+    }
+}
+
+/// Whether a status means the endpoint does not serve this API style
+fn is_style_mismatch(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 404 | 405)
+}
+
+#[async_trait]
+impl ImageBackend for HttpBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        "http"
+    }
+
+    fn endpoints(&self) -> Vec<String> {
+        self.endpoints.read().iter().map(|e| e.url.clone()).collect()
+    }
+
+    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
+        let api_request = ApiGenerateRequest {
+            prompt: request.prompt,
+            negative_prompt: request.negative_prompt,
+            n: Some(request.n),
+            width: Some(request.width),
+            height: Some(request.height),
+            model: request.model,
+            seed: request.seed,
+            guidance_scale: request.guidance_scale,
+            num_inference_steps: request.num_inference_steps,
+            response_format: Some(request.response_format),
+            image: request.image,
+            mask: request.mask,
+            variation: request.variation,
+            init_image: request.init_image,
+            denoising_strength: request.denoising_strength,
+            controls: request.controls,
+        };
+
+        let deadline = Instant::now() + self.timeout;
+        self.retry
+            .run(deadline, |_| self.generate_once(&api_request))
+            .await
     }
 
     async fn health_check(&self) -> bool {
@@ -254,9 +466,9 @@ impl ImageBackend for HttpBackend {
         for endpoint in &endpoints {
             let url = format!("{}{}", endpoint.url, self.health_check_path);
             
-            match self.client.get(&url).send().await {
+            match self.client.get(&url).timeout(self.health_check_schedule.timeout).send().await {
                 Ok(response) if response.status().is_success() => {
-                    self.mark_endpoint_healthy(&endpoint.url);
+                    self.mark_endpoint_healthy(&endpoint.url, None);
                     any_healthy = true;
                     debug!(
                         backend = %self.name,
@@ -293,7 +505,136 @@ impl ImageBackend for HttpBackend {
     }
 
     fn is_enabled(&self) -> bool {
-        self.enabled
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        self.health_check_schedule
+    }
+
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        *self.strategy.read()
+    }
+
+    fn set_endpoint_strategy(&self, strategy: LoadBalancingStrategy) {
+        *self.strategy.write() = strategy;
+    }
+
+    fn circuits(&self) -> Vec<EndpointCircuit> {
+        self.endpoints.read().iter().map(BackendEndpoint::circuit).collect()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.capabilities.clone()
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request() -> GenerateRequest {
+        GenerateRequest {
+            prompt: "a cat".to_string(),
+            negative_prompt: None,
+            n: 1,
+            width: 512,
+            height: 512,
+            model: None,
+            seed: None,
+            guidance_scale: None,
+            num_inference_steps: None,
+            quality: None,
+            style: None,
+            response_format: "b64_json".to_string(),
+            extra_params: None,
+            ..Default::default()
+        }
+    }
+
+    async fn mock(server: &MockServer, route: &str, status: u16, calls: u64) {
+        Mock::given(method("POST"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(status).set_body_json(serde_json::json!({
+                "images": [{"b64_json": "aGVsbG8="}]
+            })))
+            .expect(calls)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_learns_api_style() {
+        let server = MockServer::start().await;
+        mock(&server, "/v1/images/generations", 404, 1).await;
+        mock(&server, "/generate", 404, 1).await;
+        mock(&server, "/api/generate", 200, 2).await;
+
+        let backend = HttpBackend::new(&BackendConfig {
+            endpoints: vec![server.uri()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(backend.api_style(&server.uri()), None);
+
+        backend.generate(request()).await.unwrap();
+        assert_eq!(backend.api_style(&server.uri()), Some(ApiStyle::ApiGenerate));
+        // The second request goes straight to the learned URL
+        backend.generate(request()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pinned_api_style() {
+        let server = MockServer::start().await;
+        mock(&server, "/v1/images/generations", 200, 0).await;
+        mock(&server, "/generate", 200, 1).await;
+
+        let backend = HttpBackend::new(&BackendConfig {
+            endpoints: vec![server.uri()],
+            api_style: Some("generate".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        backend.generate(request()).await.unwrap();
+        assert_eq!(backend.api_style(&server.uri()), Some(ApiStyle::Generate));
+        assert!(HttpBackend::new(&BackendConfig {
+            api_style: Some("soap".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_automatic1111_response() {
+        let server = MockServer::start().await;
+        // As returned by /sdapi/v1/txt2img: bare base64 strings and a JSON-encoded info
+        Mock::given(method("POST"))
+            .and(path("/sdapi/v1/txt2img"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "images": ["iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="],
+                "parameters": {"prompt": "a cat", "steps": 20, "batch_size": 1},
+                "info": "{\"prompt\": \"a cat\", \"seed\": 1234, \"all_seeds\": [1234]}"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let backend = HttpBackend::new(&BackendConfig {
+            endpoints: vec![server.uri()],
+            api_style: Some("a1111".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let response = backend.generate(request()).await.unwrap();
+        assert_eq!(response.images.len(), 1);
+        assert!(response.images[0].b64_json.as_deref().unwrap().starts_with("iVBORw0KGgo"));
+    }
+}
@@ -1,9 +1,13 @@
 //! Backend module - Traits, HTTP/gRPC clients, and registry
 
+pub mod comfyui_backend;
 pub mod grpc_backend;
 pub mod http_backend;
+pub mod multi_backend;
+pub mod openai_image_backend;
 pub mod proto;
 pub mod registry;
+pub mod sdwebui_backend;
 pub mod text_backend;
 pub mod text_registry;
 pub mod traits;
@@ -12,7 +16,8 @@ pub mod traits;
 pub use text_backend::{
     TextBackend, TextBackendStatus,
     ChatMessage, ChatCompletionRequest, ChatCompletionResponse, ChatChoice,
-    TextCompletionRequest, TextCompletionResponse, TextChoice,
+    ChatCompletionChunk, ChatChunkChoice, ChatDelta, ChatCompletionStream,
+    TextCompletionRequest, TextCompletionResponse, TextChoice, TextCompletionStream,
     Usage, ModelInfo, ModelsResponse,
     create_text_backend,
 };
@@ -0,0 +1,329 @@
+//! Multi-modal backend serving image and text generation from one server
+//!
+//! [`MultiBackend`] wraps an OpenAI-compatible text backend and serves the
+//! OpenAI Images API over the same endpoints with [`openai_image_backend`]'s
+//! request building and error mapping, so both halves share circuit breakers,
+//! health checks and credentials. A single instance is registered in both the
+//! image and the text registry.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::backend::openai_image_backend;
+use crate::backend::registry::BackendRegistry;
+use crate::backend::text_backend::{
+    ChatCompletionRequest, ChatCompletionResponse, ChatCompletionStream, ModelsResponse,
+    OpenAICompatibleBackend, TextBackend, TextBackendStatus, TextCompletionRequest,
+    TextCompletionResponse, TextCompletionStream,
+};
+use crate::backend::text_registry::TextBackendRegistry;
+use crate::backend::traits::{
+    BackendStatus, EndpointCircuit, GenerateRequest, GenerateResponse, ImageBackend,
+};
+use crate::config::{BackendConfig, ProtocolType};
+use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckSchedule;
+
+/// Backend serving both image and text generation over shared endpoints
+pub struct MultiBackend {
+    inner: OpenAICompatibleBackend,
+    weight: u32,
+}
+
+impl MultiBackend {
+    /// Create a multi backend; only OpenAI-compatible servers serve both APIs
+    pub fn new(config: &BackendConfig) -> Result<Self> {
+        if !matches!(config.protocol, ProtocolType::OpenAI | ProtocolType::Http) {
+            return Err(AppError::Config(config::ConfigError::Message(format!(
+                "Multi backend '{}' must use the openai or http protocol, not {}",
+                config.name, config.protocol
+            ))));
+        }
+
+        Ok(Self {
+            inner: OpenAICompatibleBackend::new(config)?,
+            weight: config.weight,
+        })
+    }
+}
+
+/// Create a multi backend and register the same instance in both registries,
+/// replacing any backends with the same name
+pub fn register_multi_backend(
+    config: &BackendConfig,
+    image_registry: &BackendRegistry,
+    text_registry: &TextBackendRegistry,
+) -> Result<()> {
+    let backend = Arc::new(MultiBackend::new(config)?);
+    image_registry.insert(backend.clone());
+    text_registry.insert(backend);
+    info!(name = %config.name, "Registered multi backend");
+    Ok(())
+}
+
+#[async_trait]
+impl ImageBackend for MultiBackend {
+    fn name(&self) -> &str {
+        TextBackend::name(&self.inner)
+    }
+
+    fn protocol(&self) -> &str {
+        TextBackend::protocol(&self.inner)
+    }
+
+    fn endpoints(&self) -> Vec<String> {
+        self.inner.status().endpoints
+    }
+
+    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
+        openai_image_backend::generate(&self.inner, request).await
+    }
+
+    async fn health_check(&self) -> bool {
+        TextBackend::health_check(&self.inner).await
+    }
+
+    fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    fn is_enabled(&self) -> bool {
+        TextBackend::is_enabled(&self.inner)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        TextBackend::set_enabled(&self.inner, enabled);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        TextBackend::health_check_schedule(&self.inner)
+    }
+
+    fn circuits(&self) -> Vec<EndpointCircuit> {
+        self.inner.status().circuits
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        openai_image_backend::capabilities(&self.inner)
+    }
+
+    fn status(&self) -> BackendStatus {
+        let status = self.inner.status();
+        BackendStatus {
+            name: status.name,
+            protocol: status.protocol,
+            endpoints: status.endpoints,
+            healthy: status.healthy,
+            weight: self.weight,
+            enabled: status.enabled,
+            circuits: status.circuits,
+        }
+    }
+}
+
+#[async_trait]
+impl TextBackend for MultiBackend {
+    fn name(&self) -> &str {
+        TextBackend::name(&self.inner)
+    }
+
+    fn protocol(&self) -> &str {
+        TextBackend::protocol(&self.inner)
+    }
+
+    fn models(&self) -> Vec<String> {
+        self.inner.models()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.inner.capabilities()
+    }
+
+    async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
+        self.inner.chat_completion(request).await
+    }
+
+    async fn text_completion(&self, request: TextCompletionRequest) -> Result<TextCompletionResponse> {
+        self.inner.text_completion(request).await
+    }
+
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> Result<ChatCompletionStream> {
+        self.inner.chat_completion_stream(request).await
+    }
+
+    async fn text_completion_stream(&self, request: TextCompletionRequest) -> Result<TextCompletionStream> {
+        self.inner.text_completion_stream(request).await
+    }
+
+    async fn list_models(&self) -> Result<ModelsResponse> {
+        self.inner.list_models().await
+    }
+
+    async fn health_check(&self) -> bool {
+        TextBackend::health_check(&self.inner).await
+    }
+
+    fn is_enabled(&self) -> bool {
+        TextBackend::is_enabled(&self.inner)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        TextBackend::set_enabled(&self.inner, enabled);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        TextBackend::health_check_schedule(&self.inner)
+    }
+
+    fn status(&self) -> TextBackendStatus {
+        self.inner.status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BackendType;
+    use wiremock::matchers::{body_partial_json, body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_shared_instance_serves_both_apis() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/images/generations"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(serde_json::json!({"size": "512x768", "n": 1})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"b64_json": "aGVsbG8="}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chat-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "llama3",
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "hi"}, "finish_reason": "stop"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let config = BackendConfig {
+            name: "local-ai".to_string(),
+            backend_type: BackendType::Multi,
+            protocol: ProtocolType::OpenAI,
+            endpoints: vec![format!("{}/v1", server.uri())],
+            models: vec!["llama3".to_string()],
+            auth: crate::config::BackendAuth {
+                api_key: Some("secret".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let image_registry = BackendRegistry::new();
+        let text_registry = TextBackendRegistry::new();
+        register_multi_backend(&config, &image_registry, &text_registry).unwrap();
+
+        let image = image_registry.get("local-ai").unwrap();
+        let response = image
+            .generate(GenerateRequest {
+                prompt: "a cat".to_string(),
+                negative_prompt: None,
+                n: 1,
+                width: 512,
+                height: 768,
+                model: None,
+                seed: None,
+                guidance_scale: None,
+                num_inference_steps: None,
+                quality: None,
+                style: None,
+                response_format: "b64_json".to_string(),
+                extra_params: None,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.images[0].b64_json.as_deref(), Some("aGVsbG8="));
+
+        let text = text_registry.get_backend_for_model("llama3", None).await.unwrap();
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "hello"}]
+        }))
+        .unwrap();
+        assert_eq!(text.chat_completion(request).await.unwrap().choices[0].message.content, "hi");
+
+        // Disabling through one registry disables the shared instance
+        text.set_enabled(false);
+        assert!(!image.is_enabled());
+
+        // Image inputs the Images API cannot take are not advertised
+        let mut capabilities = config.clone();
+        capabilities.capabilities = vec!["chat".to_string(), "edit".to_string(), "img2img".to_string()];
+        let backend = MultiBackend::new(&capabilities).unwrap();
+        assert_eq!(ImageBackend::capabilities(&backend), vec!["chat", "edit"]);
+        assert_eq!(TextBackend::capabilities(&backend), vec!["chat", "edit", "img2img"]);
+
+        let mut anthropic = config.clone();
+        anthropic.protocol = ProtocolType::Anthropic;
+        assert!(register_multi_backend(&anthropic, &image_registry, &text_registry).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_images_use_openai_schema_and_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/images/edits"))
+            .and(body_string_contains("name=\"mask\"; filename=\"mask.png\""))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{"b64_json": "ZWRpdGVk"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/images/generations"))
+            .and(body_partial_json(serde_json::json!({"quality": "hd", "style": "natural"})))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": {"message": "Rejected by the safety system.", "code": "content_policy_violation"}
+            })))
+            .mount(&server)
+            .await;
+
+        let backend = MultiBackend::new(&BackendConfig {
+            name: "local-ai".to_string(),
+            backend_type: BackendType::Multi,
+            protocol: ProtocolType::OpenAI,
+            endpoints: vec![server.uri()],
+            retry_count: Some(0),
+            ..Default::default()
+        })
+        .unwrap();
+        let request = GenerateRequest {
+            prompt: "a lighthouse".to_string(),
+            n: 1,
+            width: 1024,
+            height: 1024,
+            quality: Some("hd".to_string()),
+            style: Some("natural".to_string()),
+            response_format: "b64_json".to_string(),
+            ..Default::default()
+        };
+
+        let edit = GenerateRequest {
+            image: Some("aW1hZ2U=".to_string()),
+            mask: Some("bWFzaw==".to_string()),
+            ..request.clone()
+        };
+        let response = backend.generate(edit).await.unwrap();
+        assert_eq!(response.images[0].b64_json.as_deref(), Some("ZWRpdGVk"));
+
+        let error = backend.generate(request).await.unwrap_err();
+        assert!(matches!(error, AppError::InvalidRequest(message) if message.starts_with("content_policy_violation")));
+    }
+}
@@ -0,0 +1,422 @@
+//! OpenAI Images API backend (DALL-E and compatible services)
+//!
+//! [`OpenAIImageBackend`] sends `POST /images/generations` with the exact
+//! OpenAI request schema, or a multipart `POST /images/edits` or
+//! `/images/variations` when the request carries a source image. It
+//! authenticates with the backend's `auth` settings: a bearer token by
+//! default, or the configured `header_name`. OpenAI error bodies are mapped
+//! into [`AppError`] so clients see the upstream message.
+
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::backend::text_backend::{OpenAICompatibleBackend, TextBackend};
+use crate::backend::traits::{
+    BackendStatus, EndpointCircuit, GenerateRequest, GenerateResponse, GeneratedImage, ImageBackend,
+    CAPABILITY_CONTROLNET, CAPABILITY_IMG2IMG,
+};
+use crate::config::BackendConfig;
+use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckSchedule;
+use crate::response::{base64, ResponseFormat};
+
+/// Image backend for the OpenAI Images API
+pub struct OpenAIImageBackend {
+    inner: OpenAICompatibleBackend,
+    weight: u32,
+}
+
+/// OpenAI images request
+#[derive(Debug, Serialize)]
+struct ImagesRequest {
+    prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    n: u32,
+    size: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quality: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    style: Option<String>,
+    response_format: &'static str,
+}
+
+/// OpenAI images response
+#[derive(Debug, Deserialize)]
+struct ImagesResponse {
+    #[serde(default)]
+    data: Vec<ImageData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageData {
+    #[serde(default)]
+    b64_json: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    revised_prompt: Option<String>,
+}
+
+/// OpenAI error body
+#[derive(Debug, Deserialize)]
+struct OpenAIErrorResponse {
+    error: OpenAIErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIErrorDetail {
+    message: String,
+    #[serde(rename = "type", default)]
+    error_type: Option<String>,
+    #[serde(default)]
+    code: Option<String>,
+}
+
+impl OpenAIImageBackend {
+    /// Create a new OpenAI image backend from configuration
+    pub fn new(config: &BackendConfig) -> Result<Self> {
+        Ok(Self {
+            inner: OpenAICompatibleBackend::new(config)?,
+            weight: config.weight,
+        })
+    }
+}
+
+/// Generate images through the OpenAI Images API on `inner`'s endpoints
+///
+/// Also used by [`MultiBackend`](crate::backend::multi_backend::MultiBackend),
+/// which serves the Images API next to the text APIs.
+pub(crate) async fn generate(inner: &OpenAICompatibleBackend, request: GenerateRequest) -> Result<GenerateResponse> {
+    // Files are written by the gateway, so anything but a URL is fetched as base64
+    let response_format = match ResponseFormat::from_str(&request.response_format) {
+        ResponseFormat::Url => "url",
+        _ => "b64_json",
+    };
+    let body = ImagesRequest {
+        prompt: request.prompt,
+        model: request.model.clone(),
+        n: request.n,
+        size: format!("{}x{}", request.width, request.height),
+        quality: request.quality,
+        style: request.style,
+        response_format,
+    };
+
+    let response = match (request.image, request.mask) {
+        (Some(image), _) if request.variation => {
+            send_upload(inner, "/images/variations", body, &image, None, false).await?
+        }
+        (Some(image), mask) => send_upload(inner, "/images/edits", body, &image, mask.as_deref(), true).await?,
+        (None, _) => {
+            debug!(backend = %TextBackend::name(inner), model = ?body.model, "Sending image generation request");
+            inner.send("/images/generations", inner.get_headers(), &body).await?
+        }
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(map_error(status, body));
+    }
+
+    let response: ImagesResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::BackendError(format!("Failed to parse response: {}", e)))?;
+
+    Ok(GenerateResponse {
+        images: response
+            .data
+            .into_iter()
+            .map(|image| GeneratedImage {
+                b64_json: image.b64_json,
+                url: image.url,
+                revised_prompt: image.revised_prompt,
+                seed: None,
+            })
+            .collect(),
+        model: request.model,
+        backend: None,
+    })
+}
+
+/// Configured capabilities of `inner` that the Images API can serve
+///
+/// It has no init image or control inputs, so img2img and ControlNet are
+/// left out even when configured.
+pub(crate) fn capabilities(inner: &OpenAICompatibleBackend) -> Vec<String> {
+    inner
+        .capabilities()
+        .into_iter()
+        .filter(|c| c != CAPABILITY_IMG2IMG && c != CAPABILITY_CONTROLNET)
+        .collect()
+}
+
+/// Send an edit or variation request as a multipart form
+///
+/// Variations take no prompt, so it is only sent when `with_prompt` is set.
+async fn send_upload(
+    inner: &OpenAICompatibleBackend,
+    path: &str,
+    body: ImagesRequest,
+    image: &str,
+    mask: Option<&str>,
+    with_prompt: bool,
+) -> Result<reqwest::Response> {
+    let image = base64::decode(image)?;
+    let mask = mask.map(base64::decode).transpose()?;
+
+    // The form sets its own content type with the boundary
+    let mut headers = inner.get_headers();
+    headers.remove(CONTENT_TYPE);
+
+    debug!(backend = %TextBackend::name(inner), model = ?body.model, path = %path, "Sending image upload request");
+    inner
+        .send_with(path, headers, |request| {
+            let mut form = Form::new()
+                .part("image", Part::bytes(image.clone()).file_name("image.png"))
+                .text("n", body.n.to_string())
+                .text("size", body.size.clone())
+                .text("response_format", body.response_format);
+            if with_prompt {
+                form = form.text("prompt", body.prompt.clone());
+            }
+            if let Some(mask) = &mask {
+                form = form.part("mask", Part::bytes(mask.clone()).file_name("mask.png"));
+            }
+            if let Some(model) = &body.model {
+                form = form.text("model", model.clone());
+            }
+            request.multipart(form)
+        })
+        .await
+}
+
+/// Map a failed OpenAI response into an error, keeping the upstream message
+fn map_error(status: reqwest::StatusCode, body: String) -> AppError {
+    let message = match serde_json::from_str::<OpenAIErrorResponse>(&body) {
+        Ok(e) => match e.error.code.or(e.error.error_type) {
+            Some(code) => format!("{}: {}", code, e.error.message),
+            None => e.error.message,
+        },
+        Err(_) => body,
+    };
+
+    match status.as_u16() {
+        // Includes content policy rejections and unsupported sizes for the model
+        400 | 404 | 413 => AppError::InvalidRequest(message),
+        429 => AppError::RateLimitExceeded,
+        _ => AppError::BackendError(format!("Backend returned {}: {}", status, message)),
+    }
+}
+
+#[async_trait]
+impl ImageBackend for OpenAIImageBackend {
+    fn name(&self) -> &str {
+        TextBackend::name(&self.inner)
+    }
+
+    fn protocol(&self) -> &str {
+        "openai"
+    }
+
+    fn endpoints(&self) -> Vec<String> {
+        self.inner.status().endpoints
+    }
+
+    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
+        generate(&self.inner, request).await
+    }
+
+    async fn health_check(&self) -> bool {
+        TextBackend::health_check(&self.inner).await
+    }
+
+    fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    fn is_enabled(&self) -> bool {
+        TextBackend::is_enabled(&self.inner)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        TextBackend::set_enabled(&self.inner, enabled);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        TextBackend::health_check_schedule(&self.inner)
+    }
+
+    fn circuits(&self) -> Vec<EndpointCircuit> {
+        self.inner.status().circuits
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        capabilities(&self.inner)
+    }
+
+    fn status(&self) -> BackendStatus {
+        let status = self.inner.status();
+        BackendStatus {
+            name: status.name,
+            protocol: "openai".to_string(),
+            endpoints: status.endpoints,
+            healthy: status.healthy,
+            weight: self.weight,
+            enabled: status.enabled,
+            circuits: status.circuits,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BackendAuth, ProtocolType};
+    use wiremock::matchers::{body_json, body_string_contains, header, header_regex, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request(prompt: &str) -> GenerateRequest {
+        GenerateRequest {
+            prompt: prompt.to_string(),
+            negative_prompt: Some("blurry".to_string()),
+            n: 1,
+            width: 1792,
+            height: 1024,
+            model: Some("dall-e-3".to_string()),
+            seed: Some(7),
+            guidance_scale: None,
+            num_inference_steps: None,
+            quality: Some("hd".to_string()),
+            style: Some("natural".to_string()),
+            response_format: "file".to_string(),
+            extra_params: None,
+            ..Default::default()
+        }
+    }
+
+    async fn backend(server: &MockServer) -> OpenAIImageBackend {
+        std::env::set_var("TEST_OPENAI_IMAGE_KEY", "sk-test");
+        OpenAIImageBackend::new(&BackendConfig {
+            name: "dalle".to_string(),
+            protocol: ProtocolType::OpenAI,
+            endpoints: vec![format!("{}/v1", server.uri())],
+            retry_count: Some(0),
+            auth: BackendAuth {
+                auth_type: "bearer".to_string(),
+                token_env: Some("TEST_OPENAI_IMAGE_KEY".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sends_openai_schema() {
+        let server = MockServer::start().await;
+        // Only OpenAI fields are sent; gateway extensions are dropped
+        Mock::given(method("POST"))
+            .and(path("/v1/images/generations"))
+            .and(header("authorization", "Bearer sk-test"))
+            .and(body_json(serde_json::json!({
+                "prompt": "a lighthouse",
+                "model": "dall-e-3",
+                "n": 1,
+                "size": "1792x1024",
+                "quality": "hd",
+                "style": "natural",
+                "response_format": "b64_json"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "created": 0,
+                "data": [{"b64_json": "aGVsbG8=", "revised_prompt": "a tall lighthouse"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let response = backend(&server).await.generate(request("a lighthouse")).await.unwrap();
+        assert_eq!(response.images[0].b64_json.as_deref(), Some("aGVsbG8="));
+        assert_eq!(response.images[0].revised_prompt.as_deref(), Some("a tall lighthouse"));
+    }
+
+    #[tokio::test]
+    async fn test_sends_uploads_as_multipart() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/images/edits"))
+            .and(header("authorization", "Bearer sk-test"))
+            .and(header_regex("content-type", "^multipart/form-data; boundary="))
+            .and(body_string_contains("name=\"image\"; filename=\"image.png\""))
+            .and(body_string_contains("name=\"mask\"; filename=\"mask.png\""))
+            .and(body_string_contains("a lighthouse at night"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "created": 0,
+                "data": [{"b64_json": "ZWRpdGVk"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let edit = GenerateRequest {
+            image: Some("aW1hZ2U=".to_string()),
+            mask: Some("bWFzaw==".to_string()),
+            ..request("a lighthouse at night")
+        };
+        let response = backend(&server).await.generate(edit).await.unwrap();
+        assert_eq!(response.images[0].b64_json.as_deref(), Some("ZWRpdGVk"));
+
+        // Variations go to their own endpoint without a prompt
+        Mock::given(method("POST"))
+            .and(path("/v1/images/variations"))
+            .and(body_string_contains("name=\"image\"; filename=\"image.png\""))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "created": 0,
+                "data": [{"b64_json": "dmFyaWVk"}]
+            })))
+            .mount(&server)
+            .await;
+        let variation = GenerateRequest {
+            image: Some("aW1hZ2U=".to_string()),
+            variation: true,
+            ..request("")
+        };
+        let response = backend(&server).await.generate(variation).await.unwrap();
+        assert_eq!(response.images[0].b64_json.as_deref(), Some("dmFyaWVk"));
+        let received = server.received_requests().await.unwrap();
+        assert!(!String::from_utf8_lossy(&received[1].body).contains("name=\"prompt\""));
+    }
+
+    #[tokio::test]
+    async fn test_maps_error_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/images/generations"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": {
+                    "message": "Your request was rejected by the safety system.",
+                    "type": "invalid_request_error",
+                    "code": "content_policy_violation"
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let error = backend(&server).await.generate(request("a lighthouse")).await.unwrap_err();
+        match error {
+            AppError::InvalidRequest(message) => {
+                assert_eq!(message, "content_policy_violation: Your request was rejected by the safety system.")
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let error = map_error(reqwest::StatusCode::TOO_MANY_REQUESTS, "{}".to_string());
+        assert!(matches!(error, AppError::RateLimitExceeded));
+        let error = map_error(reqwest::StatusCode::UNAUTHORIZED, r#"{"error":{"message":"Incorrect API key"}}"#.to_string());
+        assert!(matches!(error, AppError::BackendError(message) if message.ends_with("Incorrect API key")));
+    }
+}
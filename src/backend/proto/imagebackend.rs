@@ -0,0 +1,631 @@
+// This file is @generated by prost-build.
+/// Image generation request
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GenerateRequest {
+    /// The prompt to generate images from
+    #[prost(string, tag = "1")]
+    pub prompt: ::prost::alloc::string::String,
+    /// Negative prompt (things to avoid in generation)
+    #[prost(string, tag = "2")]
+    pub negative_prompt: ::prost::alloc::string::String,
+    /// Number of images to generate
+    #[prost(int32, tag = "3")]
+    pub n: i32,
+    /// Image width
+    #[prost(int32, tag = "4")]
+    pub width: i32,
+    /// Image height
+    #[prost(int32, tag = "5")]
+    pub height: i32,
+    /// Model identifier (optional, uses default if not specified)
+    #[prost(string, tag = "6")]
+    pub model: ::prost::alloc::string::String,
+    /// Random seed for reproducibility (-1 for random)
+    #[prost(int64, tag = "7")]
+    pub seed: i64,
+    /// Guidance scale (CFG scale)
+    #[prost(float, tag = "8")]
+    pub guidance_scale: f32,
+    /// Number of inference steps
+    #[prost(int32, tag = "9")]
+    pub num_inference_steps: i32,
+    /// Response format: "b64_json", "url", or "file"
+    #[prost(string, tag = "10")]
+    pub response_format: ::prost::alloc::string::String,
+    /// Additional parameters as JSON string
+    #[prost(string, tag = "11")]
+    pub extra_params: ::prost::alloc::string::String,
+    /// Source image for edits (empty for generation)
+    #[prost(bytes = "vec", tag = "12")]
+    pub image: ::prost::alloc::vec::Vec<u8>,
+    /// Edit mask; transparent areas are repainted (optional)
+    #[prost(bytes = "vec", tag = "13")]
+    pub mask: ::prost::alloc::vec::Vec<u8>,
+    /// Generate variations of the image instead of editing it
+    #[prost(bool, tag = "14")]
+    pub variation: bool,
+    /// Image to start from (img2img, empty for text-to-image)
+    #[prost(bytes = "vec", tag = "15")]
+    pub init_image: ::prost::alloc::vec::Vec<u8>,
+    /// How far generation departs from init_image, 0.0 to 1.0
+    #[prost(float, optional, tag = "16")]
+    pub denoising_strength: ::core::option::Option<f32>,
+    /// Control inputs conditioning the generation (ControlNet)
+    #[prost(message, repeated, tag = "17")]
+    pub controls: ::prost::alloc::vec::Vec<ControlInput>,
+}
+/// Control input conditioning a generation
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ControlInput {
+    /// Kind of control, e.g. "canny", "depth" or "openpose"
+    #[prost(string, tag = "1")]
+    pub r#type: ::prost::alloc::string::String,
+    /// Control image
+    #[prost(bytes = "vec", tag = "2")]
+    pub image: ::prost::alloc::vec::Vec<u8>,
+    /// Control model to apply (optional)
+    #[prost(string, tag = "3")]
+    pub model: ::prost::alloc::string::String,
+    /// Strength of the control
+    #[prost(float, tag = "4")]
+    pub weight: f32,
+    /// Fraction of the steps at which the control starts applying
+    #[prost(float, tag = "5")]
+    pub start: f32,
+    /// Fraction of the steps at which the control stops applying
+    #[prost(float, tag = "6")]
+    pub end: f32,
+}
+/// Generated image data
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImageData {
+    /// Base64 encoded image (when response_format is b64_json)
+    #[prost(string, tag = "1")]
+    pub b64_json: ::prost::alloc::string::String,
+    /// URL to the generated image (when response_format is url)
+    #[prost(string, tag = "2")]
+    pub url: ::prost::alloc::string::String,
+    /// Revised prompt (if model modified the prompt)
+    #[prost(string, tag = "3")]
+    pub revised_prompt: ::prost::alloc::string::String,
+    /// Seed used for generation
+    #[prost(int64, tag = "4")]
+    pub seed: i64,
+}
+/// Image generation response
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GenerateResponse {
+    /// Unix timestamp of creation
+    #[prost(int64, tag = "1")]
+    pub created: i64,
+    /// List of generated images
+    #[prost(message, repeated, tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<ImageData>,
+    /// Model used for generation
+    #[prost(string, tag = "3")]
+    pub model: ::prost::alloc::string::String,
+}
+/// Health check request
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthCheckRequest {}
+/// Health check response
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthCheckResponse {
+    /// Whether the service is healthy
+    #[prost(bool, tag = "1")]
+    pub healthy: bool,
+    /// Optional message about health status
+    #[prost(string, tag = "2")]
+    pub message: ::prost::alloc::string::String,
+    /// Available models
+    #[prost(string, repeated, tag = "3")]
+    pub available_models: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Get info request
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetInfoRequest {}
+/// Get info response
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetInfoResponse {
+    /// Backend name
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// Backend version
+    #[prost(string, tag = "2")]
+    pub version: ::prost::alloc::string::String,
+    /// Supported models
+    #[prost(message, repeated, tag = "3")]
+    pub models: ::prost::alloc::vec::Vec<ModelInfo>,
+    /// Maximum batch size supported
+    #[prost(int32, tag = "4")]
+    pub max_batch_size: i32,
+}
+/// Model information
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ModelInfo {
+    /// Model identifier
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Human-readable name
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// Maximum supported width
+    #[prost(int32, tag = "3")]
+    pub max_width: i32,
+    /// Maximum supported height
+    #[prost(int32, tag = "4")]
+    pub max_height: i32,
+    /// Default width
+    #[prost(int32, tag = "5")]
+    pub default_width: i32,
+    /// Default height
+    #[prost(int32, tag = "6")]
+    pub default_height: i32,
+}
+/// Generated client implementations.
+pub mod image_backend_service_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    /// Image generation backend service
+    #[derive(Debug, Clone)]
+    pub struct ImageBackendServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl ImageBackendServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> ImageBackendServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> ImageBackendServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            ImageBackendServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// Generate images from a text prompt
+        pub async fn generate(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GenerateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GenerateResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/imagebackend.ImageBackendService/Generate",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("imagebackend.ImageBackendService", "Generate"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Health check endpoint
+        pub async fn health_check(
+            &mut self,
+            request: impl tonic::IntoRequest<super::HealthCheckRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::HealthCheckResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/imagebackend.ImageBackendService/HealthCheck",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("imagebackend.ImageBackendService", "HealthCheck"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Get backend information
+        pub async fn get_info(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetInfoRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetInfoResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/imagebackend.ImageBackendService/GetInfo",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("imagebackend.ImageBackendService", "GetInfo"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod image_backend_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with ImageBackendServiceServer.
+    #[async_trait]
+    pub trait ImageBackendService: Send + Sync + 'static {
+        /// Generate images from a text prompt
+        async fn generate(
+            &self,
+            request: tonic::Request<super::GenerateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GenerateResponse>,
+            tonic::Status,
+        >;
+        /// Health check endpoint
+        async fn health_check(
+            &self,
+            request: tonic::Request<super::HealthCheckRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::HealthCheckResponse>,
+            tonic::Status,
+        >;
+        /// Get backend information
+        async fn get_info(
+            &self,
+            request: tonic::Request<super::GetInfoRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetInfoResponse>, tonic::Status>;
+    }
+    /// Image generation backend service
+    #[derive(Debug)]
+    pub struct ImageBackendServiceServer<T: ImageBackendService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: ImageBackendService> ImageBackendServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for ImageBackendServiceServer<T>
+    where
+        T: ImageBackendService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/imagebackend.ImageBackendService/Generate" => {
+                    #[allow(non_camel_case_types)]
+                    struct GenerateSvc<T: ImageBackendService>(pub Arc<T>);
+                    impl<
+                        T: ImageBackendService,
+                    > tonic::server::UnaryService<super::GenerateRequest>
+                    for GenerateSvc<T> {
+                        type Response = super::GenerateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GenerateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ImageBackendService>::generate(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GenerateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/imagebackend.ImageBackendService/HealthCheck" => {
+                    #[allow(non_camel_case_types)]
+                    struct HealthCheckSvc<T: ImageBackendService>(pub Arc<T>);
+                    impl<
+                        T: ImageBackendService,
+                    > tonic::server::UnaryService<super::HealthCheckRequest>
+                    for HealthCheckSvc<T> {
+                        type Response = super::HealthCheckResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::HealthCheckRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ImageBackendService>::health_check(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = HealthCheckSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/imagebackend.ImageBackendService/GetInfo" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetInfoSvc<T: ImageBackendService>(pub Arc<T>);
+                    impl<
+                        T: ImageBackendService,
+                    > tonic::server::UnaryService<super::GetInfoRequest>
+                    for GetInfoSvc<T> {
+                        type Response = super::GetInfoResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetInfoRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ImageBackendService>::get_info(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetInfoSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: ImageBackendService> Clone for ImageBackendServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: ImageBackendService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: ImageBackendService> tonic::server::NamedService
+    for ImageBackendServiceServer<T> {
+        const NAME: &'static str = "imagebackend.ImageBackendService";
+    }
+}
@@ -4,8 +4,11 @@ use dashmap::DashMap;
 use std::sync::Arc;
 use tracing::{info, warn};
 
+use crate::backend::comfyui_backend::ComfyUiBackend;
 use crate::backend::grpc_backend::GrpcBackend;
 use crate::backend::http_backend::HttpBackend;
+use crate::backend::openai_image_backend::OpenAIImageBackend;
+use crate::backend::sdwebui_backend::SdWebUiBackend;
 use crate::backend::traits::{BackendStatus, ImageBackend};
 use crate::config::{BackendConfig, ProtocolType};
 use crate::error::{AppError, Result};
@@ -24,17 +27,20 @@ impl BackendRegistry {
     }
 
     /// Initialize the registry from configuration
+    ///
+    /// Disabled backends are registered too, so they can be enabled at
+    /// runtime; they receive no traffic until then.
     pub async fn initialize_from_config(&self, configs: &[BackendConfig]) -> Result<()> {
         for config in configs {
-            if !config.enabled {
-                info!(name = %config.name, "Skipping disabled backend");
-                continue;
-            }
-
             match self.create_backend(config).await {
                 Ok(backend) => {
                     self.backends.insert(config.name.clone(), backend);
-                    info!(name = %config.name, protocol = %config.protocol, "Registered backend");
+                    info!(
+                        name = %config.name,
+                        protocol = %config.protocol,
+                        enabled = config.enabled,
+                        "Registered backend"
+                    );
                 }
                 Err(e) => {
                     warn!(name = %config.name, error = %e, "Failed to create backend");
@@ -48,14 +54,26 @@ impl BackendRegistry {
     /// Create a backend from configuration
     async fn create_backend(&self, config: &BackendConfig) -> Result<Arc<dyn ImageBackend>> {
         match config.protocol {
-            ProtocolType::Http | ProtocolType::OpenAI => {
+            ProtocolType::Http => {
                 let backend = HttpBackend::new(config)?;
                 Ok(Arc::new(backend))
             }
+            ProtocolType::OpenAI => {
+                let backend = OpenAIImageBackend::new(config)?;
+                Ok(Arc::new(backend))
+            }
             ProtocolType::Grpc => {
                 let backend = GrpcBackend::new(config).await?;
                 Ok(Arc::new(backend))
             }
+            ProtocolType::SdWebUi => {
+                let backend = SdWebUiBackend::new(config)?;
+                Ok(Arc::new(backend))
+            }
+            ProtocolType::ComfyUi => {
+                let backend = ComfyUiBackend::new(config)?;
+                Ok(Arc::new(backend))
+            }
             _ => Err(AppError::Config(config::ConfigError::Message(format!(
                 "Unsupported protocol for image backend: {}",
                 config.protocol
@@ -79,6 +97,24 @@ impl BackendRegistry {
         Ok(())
     }
 
+    /// Add a backend, replacing any existing backend with the same name
+    ///
+    /// The old backend is only replaced once the new one has been created, and
+    /// requests already holding it run to completion.
+    pub async fn replace_backend(&self, config: BackendConfig) -> Result<()> {
+        let backend = self.create_backend(&config).await?;
+        self.insert(backend);
+
+        Ok(())
+    }
+
+    /// Register an already created backend, replacing any backend with the same name
+    pub fn insert(&self, backend: Arc<dyn ImageBackend>) {
+        let name = backend.name().to_string();
+        let replaced = self.backends.insert(name.clone(), backend).is_some();
+        info!(name = %name, replaced = replaced, "Registered backend");
+    }
+
     /// Remove a backend
     pub async fn remove_backend(&self, name: &str) -> Result<()> {
         if self.backends.remove(name).is_none() {
@@ -130,6 +166,7 @@ impl BackendRegistry {
                 healthy,
                 weight: backend.weight(),
                 enabled: backend.is_enabled(),
+                circuits: backend.circuits(),
             });
         }
 
@@ -0,0 +1,772 @@
+//! Automatic1111 / SD WebUI backend
+//!
+//! Talks to the WebUI's native API: `/sdapi/v1/txt2img`, or
+//! `/sdapi/v1/img2img` for edits, variations, an init image or when
+//! `init_images` are given in `extra_params`. Control inputs become
+//! ControlNet extension units.
+//! Generation fields map onto the WebUI names (`steps`, `cfg_scale`,
+//! `sampler_name`), any other `extra_params` are passed through as-is, and the
+//! seed of each image is read from the `info` JSON of the response. While a
+//! request runs, `/sdapi/v1/progress` on the same endpoint is polled for
+//! progress reporting.
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use crate::backend::traits::{
+    BackendEndpoint, ControlInput, EndpointCircuit, GenerateRequest, GenerateResponse, GeneratedImage,
+    GenerationProgress, ImageBackend, ProgressReporter,
+};
+use crate::config::BackendConfig;
+use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckSchedule;
+use crate::gateway::load_balancer::LoadBalancingStrategy;
+use crate::gateway::load_tracker::{select_endpoint, LoadStats};
+use crate::gateway::retry::{is_retryable_status, retry_after, Attempt, RetryPolicy};
+
+/// How often progress is polled while a request runs
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// img2img denoising strength for variations unless `extra_params` sets one
+const VARIATION_DENOISING_STRENGTH: f64 = 0.6;
+
+/// Request fields set from [`GenerateRequest`]; the same keys in
+/// `extra_params` are ignored
+const MAPPED_FIELDS: &[&str] = &[
+    "prompt",
+    "negative_prompt",
+    "width",
+    "height",
+    "batch_size",
+    "steps",
+    "cfg_scale",
+    "seed",
+];
+
+/// SD WebUI (Automatic1111) image generation backend
+pub struct SdWebUiBackend {
+    name: String,
+    client: Client,
+    endpoints: Arc<RwLock<Vec<BackendEndpoint>>>,
+    health_check_path: String,
+    health_check_schedule: HealthCheckSchedule,
+    weight: u32,
+    enabled: AtomicBool,
+    timeout: Duration,
+    retry: RetryPolicy,
+    strategy: RwLock<LoadBalancingStrategy>,
+    current_endpoint_index: Arc<RwLock<usize>>,
+    capabilities: Vec<String>,
+    /// `user:password` for the WebUI's `--api-auth`
+    basic_auth: Option<(String, String)>,
+}
+
+/// txt2img / img2img request
+#[derive(Debug, Serialize)]
+struct WebUiRequest {
+    prompt: String,
+    negative_prompt: String,
+    width: u32,
+    height: u32,
+    batch_size: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    steps: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cfg_scale: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sampler_name: Option<String>,
+    /// -1 lets the WebUI pick a random seed
+    seed: i64,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    override_settings: serde_json::Map<String, serde_json::Value>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// txt2img / img2img response
+#[derive(Debug, Deserialize)]
+struct WebUiResponse {
+    #[serde(default)]
+    images: Vec<String>,
+    /// JSON-encoded generation info
+    #[serde(default)]
+    info: Option<String>,
+}
+
+/// Fields of the response `info` JSON
+#[derive(Debug, Default, Deserialize)]
+struct WebUiInfo {
+    #[serde(default)]
+    seed: Option<i64>,
+    #[serde(default)]
+    all_seeds: Vec<i64>,
+}
+
+/// Progress of the job running on a WebUI endpoint
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebUiProgress {
+    /// Completion from 0.0 to 1.0
+    #[serde(default)]
+    pub progress: f32,
+    /// Estimated seconds remaining
+    #[serde(default)]
+    pub eta_relative: f32,
+    #[serde(default)]
+    pub state: WebUiProgressState,
+    /// Base64 preview of the image in progress
+    #[serde(default)]
+    pub current_image: Option<String>,
+}
+
+impl From<WebUiProgress> for GenerationProgress {
+    fn from(progress: WebUiProgress) -> Self {
+        Self {
+            step: progress.state.sampling_step,
+            total_steps: progress.state.sampling_steps,
+            eta_secs: Some(progress.eta_relative),
+            preview: progress.current_image,
+        }
+    }
+}
+
+/// Sampler state reported by `/sdapi/v1/progress`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebUiProgressState {
+    #[serde(default)]
+    pub sampling_step: u32,
+    #[serde(default)]
+    pub sampling_steps: u32,
+}
+
+impl SdWebUiBackend {
+    /// Create a new SD WebUI backend from configuration
+    pub fn new(config: &BackendConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
+
+        let endpoints: Vec<BackendEndpoint> = config
+            .endpoints
+            .iter()
+            .map(|url| BackendEndpoint::with_circuit_breaker(url.trim_end_matches('/').to_string(), &config.circuit_breaker))
+            .collect();
+
+        let credentials = match &config.auth.token_env {
+            Some(token_env) => std::env::var(token_env).ok(),
+            None => config.auth.api_key.clone(),
+        };
+        let basic_auth = credentials
+            .filter(|_| config.auth.auth_type == "basic")
+            .and_then(|c| {
+                c.split_once(':')
+                    .map(|(user, password)| (user.to_string(), password.to_string()))
+            });
+
+        Ok(Self {
+            name: config.name.clone(),
+            client,
+            endpoints: Arc::new(RwLock::new(endpoints)),
+            health_check_path: config.health_check.path.clone(),
+            health_check_schedule: HealthCheckSchedule::from_config(config),
+            weight: config.weight,
+            enabled: AtomicBool::new(config.enabled),
+            timeout: Duration::from_millis(config.timeout_ms),
+            retry: RetryPolicy::from_config(config),
+            strategy: RwLock::new(config.endpoint_strategy()?),
+            current_endpoint_index: Arc::new(RwLock::new(0)),
+            capabilities: config.capabilities.clone(),
+            basic_auth,
+        })
+    }
+
+    /// Progress of the job currently running on an endpoint
+    ///
+    /// The preview image is only rendered when `with_preview` is set.
+    pub async fn progress(&self, endpoint: &str, with_preview: bool) -> Result<WebUiProgress> {
+        let url = format!("{}/sdapi/v1/progress?skip_current_image={}", endpoint, !with_preview);
+        let response = self
+            .authorize(self.client.get(&url))
+            .timeout(self.health_check_schedule.timeout)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(AppError::BackendError(format!(
+                "Backend returned {} for progress",
+                response.status()
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| AppError::BackendError(format!("Failed to parse progress: {}", e)))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.basic_auth {
+            Some((user, password)) => request.basic_auth(user, Some(password)),
+            None => request,
+        }
+    }
+
+    /// Get the next endpoint whose circuit lets requests through, using the
+    /// backend's endpoint strategy
+    fn get_next_endpoint(&self) -> Option<(String, Arc<LoadStats>)> {
+        let endpoints = self.endpoints.read();
+        let available: Vec<_> = endpoints.iter().filter(|e| e.is_available()).collect();
+
+        if available.is_empty() {
+            return None;
+        }
+
+        let mut index = self.current_endpoint_index.write();
+        *index = (*index + 1) % available.len();
+        let selected = available[select_endpoint(*self.strategy.read(), &available, *index, |e| &e.load)?];
+        // A half-open circuit may have handed its last probe slot to a concurrent request
+        if !selected.breaker.try_acquire() {
+            return None;
+        }
+        Some((selected.url.clone(), selected.load.clone()))
+    }
+
+    /// Send one request to the next healthy endpoint, polling its progress
+    /// until the response arrives
+    async fn generate_once(
+        &self,
+        path: &str,
+        api_request: &WebUiRequest,
+        reporter: &ProgressReporter,
+    ) -> Attempt<WebUiResponse> {
+        let Some((endpoint, load)) = self.get_next_endpoint() else {
+            return Attempt::Done(Err(AppError::NoHealthyBackends(self.name.clone())));
+        };
+        let in_flight = load.start();
+        let started = Instant::now();
+
+        debug!(backend = %self.name, endpoint = %endpoint, path = %path, "Sending generate request");
+
+        let send = self
+            .authorize(self.client.post(format!("{}{}", endpoint, path)))
+            .json(api_request)
+            .send();
+        tokio::pin!(send);
+        let mut poll = tokio::time::interval_at(Instant::now() + PROGRESS_INTERVAL, PROGRESS_INTERVAL);
+        let result = loop {
+            tokio::select! {
+                result = &mut send => break result,
+                _ = poll.tick() => {
+                    if let Ok(progress) = self.progress(&endpoint, reporter.is_enabled()).await {
+                        debug!(
+                            backend = %self.name,
+                            endpoint = %endpoint,
+                            step = progress.state.sampling_step,
+                            steps = progress.state.sampling_steps,
+                            eta_secs = progress.eta_relative,
+                            "Generation progress"
+                        );
+                        // Nothing runs before the first step, or after the
+                        // last one while the WebUI encodes the images
+                        if progress.state.sampling_steps > 0 {
+                            reporter.report(progress.into());
+                        }
+                    }
+                }
+            }
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                self.mark_endpoint_unhealthy(&endpoint);
+                let retryable = e.is_connect();
+                let error = AppError::BackendError(format!("Request to {} failed: {}", endpoint, e));
+                // Nothing reached the backend on a connect error, so it is safe to retry
+                return if retryable {
+                    Attempt::Retry(Err(error), None)
+                } else {
+                    Attempt::Done(Err(error))
+                };
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let delay = retry_after(response.headers());
+            let body = response.text().await.unwrap_or_default();
+            let error = AppError::BackendError(format!("Backend returned {}: {}", status, body));
+            if is_retryable_status(status) {
+                self.mark_endpoint_unhealthy(&endpoint);
+                return Attempt::Retry(Err(error), delay);
+            }
+            if status.is_server_error() {
+                self.mark_endpoint_unhealthy(&endpoint);
+            } else {
+                self.mark_endpoint_healthy(&endpoint, None);
+            }
+            // The WebUI answers invalid parameters with 422
+            return Attempt::Done(Err(match status.as_u16() {
+                404 | 422 => AppError::InvalidRequest(format!("Backend rejected the request: {}", body)),
+                _ => error,
+            }));
+        }
+
+        match response.json::<WebUiResponse>().await {
+            Ok(response) => {
+                self.mark_endpoint_healthy(&endpoint, Some(started.elapsed()));
+                in_flight.finish();
+                Attempt::Done(Ok(response))
+            }
+            Err(e) => {
+                self.mark_endpoint_unhealthy(&endpoint);
+                Attempt::Done(Err(AppError::BackendError(format!("Failed to parse response: {}", e))))
+            }
+        }
+    }
+
+    /// Record a failure against an endpoint's circuit breaker
+    fn mark_endpoint_unhealthy(&self, url: &str) {
+        let mut endpoints = self.endpoints.write();
+        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
+            endpoint.mark_unhealthy();
+            warn!(backend = %self.name, url = %url, circuit = %endpoint.breaker.state(), "Endpoint request failed");
+        }
+    }
+
+    /// Record a success against an endpoint's circuit breaker
+    fn mark_endpoint_healthy(&self, url: &str, latency: Option<Duration>) {
+        let mut endpoints = self.endpoints.write();
+        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
+            endpoint.mark_healthy(latency);
+            debug!(backend = %self.name, url = %url, circuit = %endpoint.breaker.state(), "Endpoint request succeeded");
+        }
+    }
+}
+
+/// Build the WebUI request and pick txt2img or img2img
+fn webui_request(request: GenerateRequest) -> (&'static str, WebUiRequest) {
+    let mut extra = match request.extra_params {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    for field in MAPPED_FIELDS {
+        extra.remove(*field);
+    }
+    let sampler_name = extra
+        .remove("sampler_name")
+        .or_else(|| extra.remove("sampler"))
+        .and_then(|v| v.as_str().map(str::to_string));
+
+    let mut override_settings = match extra.remove("override_settings") {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    if let Some(model) = request.model {
+        override_settings.insert("sd_model_checkpoint".to_string(), model.into());
+    }
+
+    // Edits run as img2img inpainting on the source image
+    if let Some(image) = request.image.or(request.init_image) {
+        extra.insert("init_images".to_string(), serde_json::json!([image]));
+    }
+    if let Some(strength) = request.denoising_strength {
+        extra.insert("denoising_strength".to_string(), strength.into());
+    }
+    if let Some(mask) = request.mask {
+        extra.insert("mask".to_string(), mask.into());
+        // OpenAI masks repaint the transparent areas; the WebUI repaints the
+        // opaque areas of an RGBA mask unless told to invert it
+        extra.entry("inpainting_mask_invert").or_insert(1.into());
+    }
+    // Variations keep the composition but redraw the details
+    if request.variation {
+        extra
+            .entry("denoising_strength")
+            .or_insert(VARIATION_DENOISING_STRENGTH.into());
+    }
+
+    if !request.controls.is_empty() {
+        insert_controlnet(&mut extra, request.controls);
+    }
+
+    let path = if extra.contains_key("init_images") {
+        "/sdapi/v1/img2img"
+    } else {
+        "/sdapi/v1/txt2img"
+    };
+
+    let body = WebUiRequest {
+        prompt: request.prompt,
+        negative_prompt: request.negative_prompt.unwrap_or_default(),
+        width: request.width,
+        height: request.height,
+        batch_size: request.n,
+        steps: request.num_inference_steps,
+        cfg_scale: request.guidance_scale,
+        sampler_name,
+        seed: request.seed.unwrap_or(-1),
+        override_settings,
+        extra,
+    };
+    (path, body)
+}
+
+/// Add control inputs as ControlNet extension units
+///
+/// Units go to `alwayson_scripts.controlnet.args`, keeping any other scripts
+/// given in `extra_params`.
+fn insert_controlnet(extra: &mut serde_json::Map<String, serde_json::Value>, controls: Vec<ControlInput>) {
+    let units: Vec<serde_json::Value> = controls
+        .into_iter()
+        .map(|control| {
+            serde_json::json!({
+                "enabled": true,
+                "module": control.control_type,
+                "model": control.model.unwrap_or_else(|| "None".to_string()),
+                "image": control.image,
+                "weight": control.weight,
+                "guidance_start": control.start,
+                "guidance_end": control.end,
+            })
+        })
+        .collect();
+
+    let scripts = extra
+        .entry("alwayson_scripts")
+        .or_insert_with(|| serde_json::json!({}));
+    if !scripts.is_object() {
+        *scripts = serde_json::json!({});
+    }
+    scripts["controlnet"] = serde_json::json!({ "args": units });
+}
+
+#[async_trait]
+impl ImageBackend for SdWebUiBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol(&self) -> &str {
+        "sdwebui"
+    }
+
+    fn endpoints(&self) -> Vec<String> {
+        self.endpoints.read().iter().map(|e| e.url.clone()).collect()
+    }
+
+    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
+        self.generate_with_progress(request, ProgressReporter::default()).await
+    }
+
+    async fn generate_with_progress(
+        &self,
+        request: GenerateRequest,
+        progress: ProgressReporter,
+    ) -> Result<GenerateResponse> {
+        let n = request.n as usize;
+        let model = request.model.clone();
+        let (path, api_request) = webui_request(request);
+
+        let deadline = Instant::now() + self.timeout;
+        let response = self
+            .retry
+            .run(deadline, |_| self.generate_once(path, &api_request, &progress))
+            .await?;
+
+        let info: WebUiInfo = response
+            .info
+            .as_deref()
+            .and_then(|info| serde_json::from_str(info).ok())
+            .unwrap_or_default();
+
+        // Extensions such as ControlNet append extra images (e.g. detected maps) after the batch
+        let images = response
+            .images
+            .into_iter()
+            .take(n)
+            .enumerate()
+            .map(|(i, image)| GeneratedImage {
+                b64_json: Some(image),
+                url: None,
+                revised_prompt: None,
+                seed: info.all_seeds.get(i).copied().or(info.seed),
+            })
+            .collect();
+
+        Ok(GenerateResponse {
+            images,
+            model,
+            backend: None,
+        })
+    }
+
+    async fn health_check(&self) -> bool {
+        let endpoints = self.endpoints.read().clone();
+        let mut any_healthy = false;
+
+        for endpoint in &endpoints {
+            let url = format!("{}{}", endpoint.url, self.health_check_path);
+
+            match self
+                .authorize(self.client.get(&url))
+                .timeout(self.health_check_schedule.timeout)
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => {
+                    self.mark_endpoint_healthy(&endpoint.url, None);
+                    any_healthy = true;
+                    debug!(backend = %self.name, endpoint = %endpoint.url, "Health check passed");
+                }
+                Ok(response) => {
+                    self.mark_endpoint_unhealthy(&endpoint.url);
+                    debug!(
+                        backend = %self.name,
+                        endpoint = %endpoint.url,
+                        status = %response.status(),
+                        "Health check failed"
+                    );
+                }
+                Err(e) => {
+                    self.mark_endpoint_unhealthy(&endpoint.url);
+                    debug!(backend = %self.name, endpoint = %endpoint.url, error = %e, "Health check failed");
+                }
+            }
+        }
+
+        any_healthy
+    }
+
+    fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        self.health_check_schedule
+    }
+
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        *self.strategy.read()
+    }
+
+    fn set_endpoint_strategy(&self, strategy: LoadBalancingStrategy) {
+        *self.strategy.write() = strategy;
+    }
+
+    fn circuits(&self) -> Vec<EndpointCircuit> {
+        self.endpoints.read().iter().map(BackendEndpoint::circuit).collect()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.capabilities.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProtocolType;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request(extra_params: Option<serde_json::Value>) -> GenerateRequest {
+        GenerateRequest {
+            prompt: "a fox".to_string(),
+            negative_prompt: None,
+            n: 2,
+            width: 512,
+            height: 768,
+            model: Some("sdxl_base.safetensors".to_string()),
+            seed: None,
+            guidance_scale: Some(6.5),
+            num_inference_steps: Some(25),
+            quality: None,
+            style: None,
+            response_format: "b64_json".to_string(),
+            extra_params,
+            ..Default::default()
+        }
+    }
+
+    fn backend(server: &MockServer) -> SdWebUiBackend {
+        SdWebUiBackend::new(&BackendConfig {
+            name: "a1111".to_string(),
+            protocol: ProtocolType::SdWebUi,
+            endpoints: vec![server.uri()],
+            retry_count: Some(0),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_txt2img_maps_fields_and_seeds() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/sdapi/v1/txt2img"))
+            .and(body_partial_json(serde_json::json!({
+                "prompt": "a fox",
+                "negative_prompt": "",
+                "batch_size": 2,
+                "steps": 25,
+                "cfg_scale": 6.5,
+                "sampler_name": "DPM++ 2M",
+                "seed": -1,
+                "override_settings": {"sd_model_checkpoint": "sdxl_base.safetensors"},
+                "enable_hr": true
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "images": ["aW1nMQ==", "aW1nMg==", "ZGV0ZWN0ZWQ="],
+                "parameters": {},
+                "info": "{\"seed\": 101, \"all_seeds\": [101, 102]}"
+            })))
+            .mount(&server)
+            .await;
+
+        let extra = serde_json::json!({"sampler": "DPM++ 2M", "enable_hr": true, "steps": 99});
+        let response = backend(&server).generate(request(Some(extra))).await.unwrap();
+
+        assert_eq!(response.images.len(), 2);
+        assert_eq!(response.images[1].b64_json.as_deref(), Some("aW1nMg=="));
+        assert_eq!(response.images.iter().map(|i| i.seed).collect::<Vec<_>>(), vec![Some(101), Some(102)]);
+    }
+
+    #[test]
+    fn test_init_image_and_controls() {
+        let extra = serde_json::json!({"alwayson_scripts": {"adetailer": {"args": []}}});
+        let request = GenerateRequest {
+            init_image: Some("aW5pdA==".to_string()),
+            denoising_strength: Some(0.35),
+            controls: vec![ControlInput {
+                control_type: "canny".to_string(),
+                image: "Y2Fubnk=".to_string(),
+                model: Some("control_v11p_sd15_canny".to_string()),
+                weight: 0.8,
+                start: 0.0,
+                end: 0.5,
+            }],
+            ..request(Some(extra))
+        };
+
+        let (path, body) = webui_request(request);
+        assert_eq!(path, "/sdapi/v1/img2img");
+        let body = serde_json::to_value(body).unwrap();
+        assert_eq!(body["init_images"], serde_json::json!(["aW5pdA=="]));
+        assert_eq!(body["denoising_strength"], serde_json::json!(0.35f32));
+        assert_eq!(body["alwayson_scripts"]["adetailer"], serde_json::json!({"args": []}));
+        assert_eq!(
+            body["alwayson_scripts"]["controlnet"]["args"][0],
+            serde_json::json!({
+                "enabled": true,
+                "module": "canny",
+                "model": "control_v11p_sd15_canny",
+                "image": "Y2Fubnk=",
+                "weight": 0.8f32,
+                "guidance_start": 0.0,
+                "guidance_end": 0.5
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_img2img_and_progress() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/sdapi/v1/img2img"))
+            .and(body_partial_json(serde_json::json!({
+                "init_images": ["aW5pdA=="],
+                "mask": "bWFzaw==",
+                "inpainting_mask_invert": 1,
+                "denoising_strength": 0.6
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "images": ["aW1nMQ=="],
+                "info": "{\"seed\": 7}"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/sdapi/v1/progress"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "progress": 0.4,
+                "eta_relative": 3.5,
+                "state": {"sampling_step": 10, "sampling_steps": 25, "job_count": 1},
+                "current_image": null
+            })))
+            .mount(&server)
+            .await;
+
+        let backend = backend(&server);
+        let extra = serde_json::json!({"denoising_strength": 0.6});
+        let edit = GenerateRequest {
+            image: Some("aW5pdA==".to_string()),
+            mask: Some("bWFzaw==".to_string()),
+            ..request(Some(extra))
+        };
+        let response = backend.generate(edit).await.unwrap();
+        assert_eq!(response.images[0].seed, Some(7));
+
+        let progress = backend.progress(&server.uri(), false).await.unwrap();
+        assert_eq!(progress.state.sampling_step, 10);
+        assert_eq!(progress.state.sampling_steps, 25);
+    }
+
+    #[tokio::test]
+    async fn test_reports_progress_while_generating() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/sdapi/v1/txt2img"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"images": ["aW1nMQ=="], "info": "{}"}))
+                    .set_delay(PROGRESS_INTERVAL + Duration::from_millis(500)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/sdapi/v1/progress"))
+            .and(query_param("skip_current_image", "false"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "progress": 0.4,
+                "eta_relative": 3.5,
+                "state": {"sampling_step": 10, "sampling_steps": 25},
+                "current_image": "cHJldmlldw=="
+            })))
+            .mount(&server)
+            .await;
+
+        let updates = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let reporter = {
+            let updates = updates.clone();
+            ProgressReporter::new(move |progress| updates.lock().push(progress))
+        };
+        backend(&server)
+            .generate_with_progress(request(None), reporter)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            updates.lock().first(),
+            Some(&GenerationProgress {
+                step: 10,
+                total_steps: 25,
+                eta_secs: Some(3.5),
+                preview: Some("cHJldmlldw==".to_string()),
+            })
+        );
+    }
+}
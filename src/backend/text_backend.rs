@@ -1,17 +1,27 @@
 //! Text generation backend implementation for LLM models
-//! Supports OpenAI API compatible endpoints (OpenAI, Ollama, vLLM, etc.)
+//! Supports OpenAI API compatible endpoints (OpenAI, Ollama, vLLM, etc.),
+//! the Anthropic Messages API, and the native TGI `/generate` API
 
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
 use parking_lot::RwLock;
-use reqwest::{Client, header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE}};
-use serde::{Deserialize, Serialize};
+use reqwest::{Client, RequestBuilder, header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE}};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::time::Instant;
 use tracing::{debug, warn, error};
 use utoipa::ToSchema;
 
+use crate::backend::traits::{BackendEndpoint, EndpointCircuit};
 use crate::config::{BackendConfig, ProtocolType};
 use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckSchedule;
+use crate::gateway::load_balancer::LoadBalancingStrategy;
+use crate::gateway::load_tracker::{select_endpoint, LoadStats};
+use crate::gateway::retry::{is_retryable_status, retry_after, Attempt, RetryPolicy};
 
 /// Chat message for completion requests
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -83,6 +93,44 @@ pub struct ChatChoice {
     pub finish_reason: Option<String>,
 }
 
+/// Streaming chat completion chunk (OpenAI `chat.completion.chunk`)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChatCompletionChunk {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatChunkChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// Chat choice within a streaming chunk
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChatChunkChoice {
+    pub index: u32,
+    pub delta: ChatDelta,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+/// Incremental message content within a streaming chunk
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ChatDelta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+/// Stream of chat completion chunks from a backend
+pub type ChatCompletionStream = BoxStream<'static, Result<ChatCompletionChunk>>;
+
+/// Stream of text completion chunks from a backend
+///
+/// Text completion chunks share the shape of a full `TextCompletionResponse`.
+pub type TextCompletionStream = BoxStream<'static, Result<TextCompletionResponse>>;
+
 /// Text completion response
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TextCompletionResponse {
@@ -130,40 +178,6 @@ pub struct ModelsResponse {
     pub data: Vec<ModelInfo>,
 }
 
-/// Text backend endpoint status
-#[derive(Debug, Clone)]
-pub struct TextEndpoint {
-    pub url: String,
-    pub healthy: bool,
-    pub last_check: Option<std::time::Instant>,
-    pub consecutive_failures: u32,
-}
-
-impl TextEndpoint {
-    pub fn new(url: String) -> Self {
-        Self {
-            url,
-            healthy: true,
-            last_check: None,
-            consecutive_failures: 0,
-        }
-    }
-
-    pub fn mark_healthy(&mut self) {
-        self.healthy = true;
-        self.last_check = Some(std::time::Instant::now());
-        self.consecutive_failures = 0;
-    }
-
-    pub fn mark_unhealthy(&mut self) {
-        self.consecutive_failures += 1;
-        if self.consecutive_failures >= 3 {
-            self.healthy = false;
-        }
-        self.last_check = Some(std::time::Instant::now());
-    }
-}
-
 /// Text backend status
 #[derive(Debug, Clone)]
 pub struct TextBackendStatus {
@@ -174,6 +188,8 @@ pub struct TextBackendStatus {
     pub models: Vec<String>,
     pub capabilities: Vec<String>,
     pub enabled: bool,
+    /// Circuit breaker state per endpoint
+    pub circuits: Vec<EndpointCircuit>,
 }
 
 /// Trait for text generation backends
@@ -197,6 +213,26 @@ pub trait TextBackend: Send + Sync {
     /// Text completion
     async fn text_completion(&self, request: TextCompletionRequest) -> Result<TextCompletionResponse>;
     
+    /// Streaming chat completion
+    ///
+    /// Dropping the returned stream cancels the upstream request.
+    async fn chat_completion_stream(&self, _request: ChatCompletionRequest) -> Result<ChatCompletionStream> {
+        Err(AppError::InvalidRequest(format!(
+            "Backend '{}' does not support streaming",
+            self.name()
+        )))
+    }
+    
+    /// Streaming text completion
+    ///
+    /// Dropping the returned stream cancels the upstream request.
+    async fn text_completion_stream(&self, _request: TextCompletionRequest) -> Result<TextCompletionStream> {
+        Err(AppError::InvalidRequest(format!(
+            "Backend '{}' does not support streaming",
+            self.name()
+        )))
+    }
+    
     /// List available models from the backend
     async fn list_models(&self) -> Result<ModelsResponse>;
     
@@ -206,6 +242,26 @@ pub trait TextBackend: Send + Sync {
     /// Check if enabled
     fn is_enabled(&self) -> bool;
     
+    /// Enable or disable the backend; disabled backends stay registered but
+    /// receive no traffic
+    fn set_enabled(&self, _enabled: bool) {}
+    
+    /// Get the strategy used to pick between this backend's endpoints
+    ///
+    /// Text backends themselves are picked by model and routing rules, never
+    /// by a load balancing strategy.
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        LoadBalancingStrategy::RoundRobin
+    }
+    
+    /// Change the strategy used to pick between this backend's endpoints
+    fn set_endpoint_strategy(&self, _strategy: LoadBalancingStrategy) {}
+    
+    /// Get how often the backend is health checked and how long a check may take
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        HealthCheckSchedule::default()
+    }
+    
     /// Get status
     fn status(&self) -> TextBackendStatus;
 }
@@ -215,11 +271,18 @@ pub struct OpenAICompatibleBackend {
     name: String,
     protocol: ProtocolType,
     client: Client,
-    endpoints: Arc<RwLock<Vec<TextEndpoint>>>,
+    /// Client for streaming requests, which may outlive `timeout`; they are
+    /// bounded by an idle timeout instead
+    stream_client: Client,
+    endpoints: Arc<RwLock<Vec<BackendEndpoint>>>,
     health_check_path: String,
+    health_check_schedule: HealthCheckSchedule,
     models: Vec<String>,
     capabilities: Vec<String>,
-    enabled: bool,
+    enabled: AtomicBool,
+    timeout: Duration,
+    retry: RetryPolicy,
+    strategy: RwLock<LoadBalancingStrategy>,
     current_endpoint_index: Arc<RwLock<usize>>,
     auth_token: Option<String>,
     auth_header_name: Option<String>,
@@ -228,15 +291,20 @@ pub struct OpenAICompatibleBackend {
 impl OpenAICompatibleBackend {
     /// Create a new OpenAI compatible backend
     pub fn new(config: &BackendConfig) -> Result<Self> {
+        let timeout = Duration::from_millis(config.timeout_ms);
         let client = Client::builder()
-            .timeout(Duration::from_millis(config.timeout_ms))
+            .timeout(timeout)
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
+        let stream_client = Client::builder()
+            .connect_timeout(timeout)
             .build()
             .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
 
-        let endpoints: Vec<TextEndpoint> = config
+        let endpoints: Vec<BackendEndpoint> = config
             .endpoints
             .iter()
-            .map(|url| TextEndpoint::new(url.clone()))
+            .map(|url| BackendEndpoint::with_circuit_breaker(url.clone(), &config.circuit_breaker))
             .collect();
 
         // Get auth token from environment if specified
@@ -252,11 +320,16 @@ impl OpenAICompatibleBackend {
             name: config.name.clone(),
             protocol: config.protocol.clone(),
             client,
+            stream_client,
             endpoints: Arc::new(RwLock::new(endpoints)),
             health_check_path: config.health_check.path.clone(),
+            health_check_schedule: HealthCheckSchedule::from_config(config),
             models: config.models.clone(),
             capabilities: config.capabilities.clone(),
-            enabled: config.enabled,
+            enabled: AtomicBool::new(config.enabled),
+            timeout,
+            retry: RetryPolicy::from_config(config),
+            strategy: RwLock::new(config.endpoint_strategy()?),
             current_endpoint_index: Arc::new(RwLock::new(0)),
             auth_token,
             auth_header_name,
@@ -264,7 +337,7 @@ impl OpenAICompatibleBackend {
     }
 
     /// Get headers with authentication
-    fn get_headers(&self) -> HeaderMap {
+    pub(crate) fn get_headers(&self) -> HeaderMap {
         let mut headers = HeaderMap::new();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
@@ -290,28 +363,34 @@ impl OpenAICompatibleBackend {
         headers
     }
 
-    /// Get the next healthy endpoint
-    fn get_next_endpoint(&self) -> Option<String> {
+    /// Get the next endpoint whose circuit lets requests through, picked by
+    /// the backend's strategy
+    fn get_next_endpoint(&self) -> Option<(String, Arc<LoadStats>)> {
         let endpoints = self.endpoints.read();
-        let healthy_endpoints: Vec<_> = endpoints
+        let available: Vec<_> = endpoints
             .iter()
-            .filter(|e| e.healthy)
+            .filter(|e| e.is_available())
             .collect();
 
-        if healthy_endpoints.is_empty() {
+        if available.is_empty() {
             return None;
         }
 
         let mut index = self.current_endpoint_index.write();
-        *index = (*index + 1) % healthy_endpoints.len();
-        Some(healthy_endpoints[*index].url.clone())
+        *index = (*index + 1) % available.len();
+        let selected = available[select_endpoint(*self.strategy.read(), &available, *index, |e| &e.load)?];
+        // A half-open circuit may have handed its last probe slot to a concurrent request
+        if !selected.breaker.try_acquire() {
+            return None;
+        }
+        Some((selected.url.clone(), selected.load.clone()))
     }
 
-    fn mark_endpoint_healthy(&self, url: &str) {
+    fn mark_endpoint_healthy(&self, url: &str, latency: Option<Duration>) {
         let mut endpoints = self.endpoints.write();
         if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
-            endpoint.mark_healthy();
-            debug!(backend = %self.name, url = %url, "Marked endpoint as healthy");
+            endpoint.mark_healthy(latency);
+            debug!(backend = %self.name, url = %url, circuit = %endpoint.breaker.state(), "Endpoint request succeeded");
         }
     }
 
@@ -319,11 +398,221 @@ impl OpenAICompatibleBackend {
         let mut endpoints = self.endpoints.write();
         if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
             endpoint.mark_unhealthy();
-            warn!(backend = %self.name, url = %url, "Marked endpoint as unhealthy");
+            warn!(backend = %self.name, url = %url, circuit = %endpoint.breaker.state(), "Endpoint request failed");
+        }
+    }
+
+    /// POST a JSON body to `path` on the next healthy endpoint
+    ///
+    /// Connect errors and 429/502/503 responses are retried on the next
+    /// endpoint with backoff, honouring `Retry-After`. Any other response, and
+    /// the last retryable one once retries are spent, is returned for the
+    /// caller to map into an error.
+    pub(crate) async fn send<T: Serialize + ?Sized>(&self, path: &str, headers: HeaderMap, body: &T) -> Result<reqwest::Response> {
+        self.send_with(path, headers, |request| request.json(body)).await
+    }
+
+    /// POST a JSON body for a streamed response
+    ///
+    /// Like [`send`](Self::send), but the response body is not bounded by the
+    /// backend timeout, only the wait for the response headers. Read it with
+    /// [`sse_data_stream`], which applies the timeout between chunks.
+    pub(crate) async fn send_stream<T: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        headers: HeaderMap,
+        body: &T,
+    ) -> Result<reqwest::Response> {
+        let deadline = Instant::now() + self.timeout;
+        let body = |request: RequestBuilder| request.json(body);
+        self.retry
+            .run(deadline, |_| self.send_once(path, &headers, &body, true))
+            .await
+    }
+
+    /// POST with a body set by `body`, which runs again for each retry
+    pub(crate) async fn send_with(
+        &self,
+        path: &str,
+        headers: HeaderMap,
+        body: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let deadline = Instant::now() + self.timeout;
+        self.retry
+            .run(deadline, |_| self.send_once(path, &headers, &body, false))
+            .await
+    }
+
+    async fn send_once(
+        &self,
+        path: &str,
+        headers: &HeaderMap,
+        body: &impl Fn(RequestBuilder) -> RequestBuilder,
+        streaming: bool,
+    ) -> Attempt<reqwest::Response> {
+        let Some((endpoint, load)) = self.get_next_endpoint() else {
+            return Attempt::Done(Err(AppError::NoHealthyBackends(self.name.clone())));
+        };
+        // Requests count as in flight until their response headers arrive
+        let in_flight = load.start();
+
+        debug!(backend = %self.name, endpoint = %endpoint, path = %path, "Sending request");
+
+        let url = format!("{}{}", endpoint.trim_end_matches('/'), path);
+        let started = Instant::now();
+
+        let client = if streaming { &self.stream_client } else { &self.client };
+        let send = body(client.post(&url).headers(headers.clone())).send();
+        // The stream client has no overall timeout, so bound the wait for headers here
+        let result = if streaming {
+            match tokio::time::timeout(self.timeout, send).await {
+                Ok(result) => result,
+                Err(_) => {
+                    self.mark_endpoint_unhealthy(&endpoint);
+                    return Attempt::Done(Err(AppError::Timeout(format!(
+                        "No response from {} within {:?}",
+                        endpoint, self.timeout
+                    ))));
+                }
+            }
+        } else {
+            send.await
+        };
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                self.mark_endpoint_unhealthy(&endpoint);
+                // Nothing reached the backend on a connect error, so it is safe to retry
+                let retryable = e.is_connect();
+                let outcome = Err(AppError::HttpClient(e));
+                return if retryable {
+                    Attempt::Retry(outcome, None)
+                } else {
+                    Attempt::Done(outcome)
+                };
+            }
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            // Streams are timed to the response headers
+            self.mark_endpoint_healthy(&endpoint, Some(started.elapsed()));
+            in_flight.finish();
+            return Attempt::Done(Ok(response));
+        }
+
+        // Server errors, including overload, count against the endpoint's circuit
+        if status.is_server_error() || is_retryable_status(status) {
+            self.mark_endpoint_unhealthy(&endpoint);
+        } else {
+            self.mark_endpoint_healthy(&endpoint, None);
+        }
+        if is_retryable_status(status) {
+            let delay = retry_after(response.headers());
+            return Attempt::Retry(Ok(response), delay);
+        }
+
+        Attempt::Done(Ok(response))
+    }
+
+    /// POST a JSON request and parse the JSON response
+    pub(crate) async fn post_json<T: Serialize, R: DeserializeOwned>(&self, path: &str, body: &T) -> Result<R> {
+        let response = self.send(path, self.get_headers(), body).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::BackendError(format!(
+                "Backend returned {}: {}",
+                status, body
+            )));
+        }
+
+        response.json::<R>().await.map_err(|e| {
+            error!(backend = %self.name, path = %path, error = %e, "Failed to parse response");
+            AppError::BackendError(format!("Failed to parse response: {}", e))
+        })
+    }
+
+    /// POST a streaming request and return the `data:` payloads of the response
+    async fn post_stream<T: Serialize>(&self, path: &str, body: &T) -> Result<BoxStream<'static, Result<String>>> {
+        debug!(backend = %self.name, path = %path, "Opening streaming request");
+
+        let response = self.send_stream(path, self.get_headers(), body).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+
+            return Err(AppError::BackendError(format!(
+                "Backend returned {}: {}",
+                status, body
+            )));
         }
+
+        Ok(sse_data_stream(response, self.timeout))
     }
 }
 
+/// Split a `text/event-stream` response body into the payloads of its `data:`
+/// lines, ending at the OpenAI `[DONE]` sentinel
+///
+/// The response body is owned by the stream, so dropping the stream closes the
+/// upstream connection. A backend that sends nothing for `idle_timeout` ends
+/// the stream with a timeout error, so clients can tell it was cut short.
+fn sse_data_stream(response: reqwest::Response, idle_timeout: Duration) -> BoxStream<'static, Result<String>> {
+    let state = (response.bytes_stream().boxed(), Vec::<u8>::new(), VecDeque::<String>::new());
+
+    stream::unfold(Some(state), move |state| async move {
+        let (mut body, mut buffer, mut pending) = state?;
+
+        loop {
+            if let Some(data) = pending.pop_front() {
+                if data == "[DONE]" {
+                    return None;
+                }
+                return Some((Ok(data), Some((body, buffer, pending))));
+            }
+
+            let Ok(next) = tokio::time::timeout(idle_timeout, body.next()).await else {
+                let error = AppError::Timeout(format!("Backend stream idle for {:?}", idle_timeout));
+                return Some((Err(error), None));
+            };
+            match next {
+                Some(Ok(bytes)) => {
+                    buffer.extend_from_slice(&bytes);
+                    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = buffer.drain(..=pos).collect();
+                        let line = String::from_utf8_lossy(&line);
+                        if let Some(data) = line.trim_end().strip_prefix("data:") {
+                            pending.push_back(data.trim_start().to_string());
+                        }
+                    }
+                }
+                Some(Err(e)) => return Some((Err(AppError::HttpClient(e)), None)),
+                None => return None,
+            }
+        }
+    })
+    .boxed()
+}
+
+/// Parse each SSE payload of a stream as a JSON chunk
+fn parse_chunks<T>(backend: String, events: BoxStream<'static, Result<String>>) -> BoxStream<'static, Result<T>>
+where
+    T: serde::de::DeserializeOwned + Send + 'static,
+{
+    events
+        .map(move |event| {
+            let data = event?;
+            serde_json::from_str::<T>(&data).map_err(|e| {
+                error!(backend = %backend, error = %e, "Failed to parse stream chunk");
+                AppError::BackendError(format!("Failed to parse stream chunk: {}", e))
+            })
+        })
+        .boxed()
+}
+
 #[async_trait]
 impl TextBackend for OpenAICompatibleBackend {
     fn name(&self) -> &str {
@@ -337,6 +626,8 @@ impl TextBackend for OpenAICompatibleBackend {
             ProtocolType::Tgi => "tgi",
             ProtocolType::Http => "http",
             ProtocolType::Grpc => "grpc",
+            ProtocolType::SdWebUi => "sdwebui",
+            ProtocolType::ComfyUi => "comfyui",
         }
     }
 
@@ -349,95 +640,29 @@ impl TextBackend for OpenAICompatibleBackend {
     }
 
     async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
-        let endpoint = self
-            .get_next_endpoint()
-            .ok_or_else(|| AppError::NoHealthyBackends(self.name.clone()))?;
-
-        debug!(backend = %self.name, endpoint = %endpoint, model = %request.model, "Sending chat completion request");
-
-        let url = format!("{}/chat/completions", endpoint.trim_end_matches('/'));
-        
-        let response = self
-            .client
-            .post(&url)
-            .headers(self.get_headers())
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| {
-                self.mark_endpoint_unhealthy(&endpoint);
-                AppError::HttpClient(e)
-            })?;
-
-        if response.status().is_success() {
-            let result = response.json::<ChatCompletionResponse>().await.map_err(|e| {
-                error!(backend = %self.name, error = %e, "Failed to parse chat completion response");
-                AppError::BackendError(format!("Failed to parse response: {}", e))
-            })?;
-            
-            self.mark_endpoint_healthy(&endpoint);
-            Ok(result)
-        } else {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            
-            if status.as_u16() >= 500 {
-                self.mark_endpoint_unhealthy(&endpoint);
-            }
-            
-            Err(AppError::BackendError(format!(
-                "Backend returned {}: {}",
-                status, body
-            )))
-        }
+        debug!(backend = %self.name, model = %request.model, "Sending chat completion request");
+        self.post_json("/chat/completions", &request).await
     }
 
     async fn text_completion(&self, request: TextCompletionRequest) -> Result<TextCompletionResponse> {
-        let endpoint = self
-            .get_next_endpoint()
-            .ok_or_else(|| AppError::NoHealthyBackends(self.name.clone()))?;
-
-        debug!(backend = %self.name, endpoint = %endpoint, model = %request.model, "Sending text completion request");
+        debug!(backend = %self.name, model = %request.model, "Sending text completion request");
+        self.post_json("/completions", &request).await
+    }
 
-        let url = format!("{}/completions", endpoint.trim_end_matches('/'));
-        
-        let response = self
-            .client
-            .post(&url)
-            .headers(self.get_headers())
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| {
-                self.mark_endpoint_unhealthy(&endpoint);
-                AppError::HttpClient(e)
-            })?;
+    async fn chat_completion_stream(&self, mut request: ChatCompletionRequest) -> Result<ChatCompletionStream> {
+        request.stream = Some(true);
+        let events = self.post_stream("/chat/completions", &request).await?;
+        Ok(parse_chunks(self.name.clone(), events))
+    }
 
-        if response.status().is_success() {
-            let result = response.json::<TextCompletionResponse>().await.map_err(|e| {
-                error!(backend = %self.name, error = %e, "Failed to parse text completion response");
-                AppError::BackendError(format!("Failed to parse response: {}", e))
-            })?;
-            
-            self.mark_endpoint_healthy(&endpoint);
-            Ok(result)
-        } else {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            
-            if status.as_u16() >= 500 {
-                self.mark_endpoint_unhealthy(&endpoint);
-            }
-            
-            Err(AppError::BackendError(format!(
-                "Backend returned {}: {}",
-                status, body
-            )))
-        }
+    async fn text_completion_stream(&self, mut request: TextCompletionRequest) -> Result<TextCompletionStream> {
+        request.stream = Some(true);
+        let events = self.post_stream("/completions", &request).await?;
+        Ok(parse_chunks(self.name.clone(), events))
     }
 
     async fn list_models(&self) -> Result<ModelsResponse> {
-        let endpoint = self
+        let (endpoint, _) = self
             .get_next_endpoint()
             .ok_or_else(|| AppError::NoHealthyBackends(self.name.clone()))?;
 
@@ -454,14 +679,20 @@ impl TextBackend for OpenAICompatibleBackend {
                 AppError::HttpClient(e)
             })?;
 
-        if response.status().is_success() {
+        // Server errors, including overload, count against the endpoint's circuit
+        let status = response.status();
+        if status.is_server_error() || is_retryable_status(status) {
+            self.mark_endpoint_unhealthy(&endpoint);
+        } else {
+            self.mark_endpoint_healthy(&endpoint, None);
+        }
+
+        if status.is_success() {
             let result = response.json::<ModelsResponse>().await.map_err(|e| {
                 // If we can't parse the response, return configured models instead
                 warn!(backend = %self.name, error = %e, "Failed to parse models response, using configured models");
                 AppError::BackendError(format!("Failed to parse response: {}", e))
             })?;
-            
-            self.mark_endpoint_healthy(&endpoint);
             Ok(result)
         } else {
             // Return configured models if the endpoint doesn't support /models
@@ -488,13 +719,14 @@ impl TextBackend for OpenAICompatibleBackend {
                 .client
                 .get(&url)
                 .headers(self.get_headers())
+                .timeout(self.health_check_schedule.timeout)
                 .send()
                 .await;
 
             match result {
                 Ok(response) if response.status().is_success() || response.status().as_u16() == 401 => {
                     // 401 means server is up but needs auth - still healthy
-                    self.mark_endpoint_healthy(&endpoint.url);
+                    self.mark_endpoint_healthy(&endpoint.url, None);
                     any_healthy = true;
                     debug!(
                         backend = %self.name,
@@ -527,12 +759,28 @@ impl TextBackend for OpenAICompatibleBackend {
     }
 
     fn is_enabled(&self) -> bool {
-        self.enabled
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        *self.strategy.read()
+    }
+
+    fn set_endpoint_strategy(&self, strategy: LoadBalancingStrategy) {
+        *self.strategy.write() = strategy;
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        self.health_check_schedule
     }
 
     fn status(&self) -> TextBackendStatus {
         let endpoints = self.endpoints.read();
-        let any_healthy = endpoints.iter().any(|e| e.healthy);
+        let any_healthy = endpoints.iter().any(|e| e.is_available());
         
         TextBackendStatus {
             name: self.name.clone(),
@@ -541,78 +789,1049 @@ impl TextBackend for OpenAICompatibleBackend {
             healthy: any_healthy,
             models: self.models.clone(),
             capabilities: self.capabilities.clone(),
-            enabled: self.enabled,
+            enabled: self.is_enabled(),
+            circuits: endpoints.iter().map(BackendEndpoint::circuit).collect(),
         }
     }
 }
 
-/// Anthropic-specific backend (Claude API)
-pub struct AnthropicBackend {
-    inner: OpenAICompatibleBackend,
+/// Anthropic Messages API version sent with every request
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` is required by the Messages API; used when the client omits it
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Anthropic Messages API request
+#[derive(Debug, Serialize)]
+struct AnthropicMessagesRequest {
+    model: String,
+    max_tokens: u32,
+    messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<AnthropicMetadata>,
 }
 
-impl AnthropicBackend {
-    pub fn new(config: &BackendConfig) -> Result<Self> {
-        Ok(Self {
-            inner: OpenAICompatibleBackend::new(config)?,
-        })
-    }
+#[derive(Debug, Serialize)]
+struct AnthropicMessage {
+    role: String,
+    content: String,
 }
 
-#[async_trait]
-impl TextBackend for AnthropicBackend {
-    fn name(&self) -> &str {
-        self.inner.name()
-    }
+#[derive(Debug, Serialize)]
+struct AnthropicMetadata {
+    user_id: String,
+}
 
-    fn protocol(&self) -> &str {
-        "anthropic"
-    }
+/// Anthropic Messages API response
+#[derive(Debug, Deserialize)]
+struct AnthropicMessagesResponse {
+    id: String,
+    model: String,
+    #[serde(default)]
+    content: Vec<AnthropicContentBlock>,
+    #[serde(default)]
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: AnthropicUsage,
+}
 
-    fn models(&self) -> Vec<String> {
-        self.inner.models()
-    }
+#[derive(Debug, Deserialize)]
+struct AnthropicContentBlock {
+    #[serde(rename = "type")]
+    block_type: String,
+    #[serde(default)]
+    text: Option<String>,
+}
 
-    fn capabilities(&self) -> Vec<String> {
-        self.inner.capabilities()
-    }
+#[derive(Debug, Clone, Default, Deserialize)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
 
-    async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
-        // Convert to Anthropic format
-        // For now, use the OpenAI compatible endpoint
-        // TODO: Implement native Anthropic API format
-        self.inner.chat_completion(request).await
+/// Anthropic error body (`{"type": "error", "error": {...}}`)
+#[derive(Debug, Deserialize)]
+struct AnthropicErrorResponse {
+    error: AnthropicErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicErrorDetail {
+    #[serde(rename = "type")]
+    error_type: String,
+    message: String,
+}
+
+/// Server-sent event from a streaming Messages API response
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicStreamEvent {
+    MessageStart {
+        message: AnthropicStreamMessage,
+    },
+    ContentBlockDelta {
+        delta: AnthropicContentDelta,
+    },
+    MessageDelta {
+        delta: AnthropicMessageDelta,
+        #[serde(default)]
+        usage: Option<AnthropicUsage>,
+    },
+    Error {
+        error: AnthropicErrorDetail,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicStreamMessage {
+    id: String,
+    model: String,
+    #[serde(default)]
+    usage: AnthropicUsage,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicContentDelta {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicMessageDelta {
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+/// Message id, model and prompt usage carried across a streaming response
+#[derive(Debug, Default)]
+struct AnthropicStreamState {
+    id: String,
+    model: String,
+    created: i64,
+    input_tokens: u32,
+}
+
+impl AnthropicStreamState {
+    /// Build a single-choice chunk for the current message
+    fn chunk(&self, delta: ChatDelta, finish_reason: Option<String>, usage: Option<Usage>) -> ChatCompletionChunk {
+        ChatCompletionChunk {
+            id: self.id.clone(),
+            object: "chat.completion.chunk".to_string(),
+            created: self.created,
+            model: self.model.clone(),
+            choices: vec![ChatChunkChoice {
+                index: 0,
+                delta,
+                finish_reason,
+            }],
+            usage,
+        }
     }
+}
 
-    async fn text_completion(&self, request: TextCompletionRequest) -> Result<TextCompletionResponse> {
-        self.inner.text_completion(request).await
+/// Translate an OpenAI-style chat request into the Messages API shape
+///
+/// System messages are hoisted into the top-level `system` field and any
+/// role other than `assistant` is sent as `user`.
+fn to_anthropic_request(request: ChatCompletionRequest, stream: bool) -> AnthropicMessagesRequest {
+    let mut system_prompts = Vec::new();
+    let mut messages = Vec::new();
+
+    for message in request.messages {
+        match message.role.as_str() {
+            "system" => system_prompts.push(message.content),
+            "assistant" => messages.push(AnthropicMessage {
+                role: "assistant".to_string(),
+                content: message.content,
+            }),
+            _ => messages.push(AnthropicMessage {
+                role: "user".to_string(),
+                content: message.content,
+            }),
+        }
     }
 
-    async fn list_models(&self) -> Result<ModelsResponse> {
-        // Anthropic doesn't have a models endpoint, return configured models
-        Ok(ModelsResponse {
-            object: "list".to_string(),
-            data: self.inner.models().iter().map(|id| ModelInfo {
-                id: id.clone(),
-                object: "model".to_string(),
-                created: None,
-                owned_by: Some("anthropic".to_string()),
-            }).collect(),
-        })
+    AnthropicMessagesRequest {
+        model: request.model,
+        max_tokens: request.max_tokens.unwrap_or(ANTHROPIC_DEFAULT_MAX_TOKENS),
+        messages,
+        system: if system_prompts.is_empty() {
+            None
+        } else {
+            Some(system_prompts.join("\n\n"))
+        },
+        temperature: request.temperature,
+        top_p: request.top_p,
+        stop_sequences: request.stop,
+        stream: if stream { Some(true) } else { None },
+        metadata: request.user.map(|user_id| AnthropicMetadata { user_id }),
     }
+}
 
-    async fn health_check(&self) -> bool {
-        self.inner.health_check().await
+/// Map an Anthropic stop reason onto the OpenAI `finish_reason` vocabulary
+fn map_stop_reason(stop_reason: &str) -> String {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        other => other,
     }
+    .to_string()
+}
 
-    fn is_enabled(&self) -> bool {
-        self.inner.is_enabled()
+impl From<AnthropicUsage> for Usage {
+    fn from(usage: AnthropicUsage) -> Self {
+        Usage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+            total_tokens: usage.input_tokens + usage.output_tokens,
+        }
     }
+}
 
-    fn status(&self) -> TextBackendStatus {
-        let mut status = self.inner.status();
-        status.protocol = "anthropic".to_string();
-        status
+/// Translate a Messages API response into an OpenAI-style chat completion
+fn from_anthropic_response(response: AnthropicMessagesResponse) -> ChatCompletionResponse {
+    let content = response
+        .content
+        .into_iter()
+        .filter(|block| block.block_type == "text")
+        .filter_map(|block| block.text)
+        .collect::<Vec<_>>()
+        .join("");
+
+    ChatCompletionResponse {
+        id: response.id,
+        object: "chat.completion".to_string(),
+        created: chrono::Utc::now().timestamp(),
+        model: response.model,
+        choices: vec![ChatChoice {
+            index: 0,
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                content,
+                name: None,
+            },
+            finish_reason: response.stop_reason.as_deref().map(map_stop_reason),
+        }],
+        usage: Some(response.usage.into()),
+    }
+}
+
+/// Translate one streaming event into an OpenAI-style chunk, if it carries one
+fn from_anthropic_event(
+    state: &mut AnthropicStreamState,
+    event: AnthropicStreamEvent,
+) -> Option<Result<ChatCompletionChunk>> {
+    match event {
+        AnthropicStreamEvent::MessageStart { message } => {
+            state.id = message.id;
+            state.model = message.model;
+            state.created = chrono::Utc::now().timestamp();
+            state.input_tokens = message.usage.input_tokens;
+            let delta = ChatDelta {
+                role: Some("assistant".to_string()),
+                content: None,
+            };
+            Some(Ok(state.chunk(delta, None, None)))
+        }
+        AnthropicStreamEvent::ContentBlockDelta { delta } => {
+            let delta = ChatDelta {
+                role: None,
+                content: Some(delta.text?),
+            };
+            Some(Ok(state.chunk(delta, None, None)))
+        }
+        AnthropicStreamEvent::MessageDelta { delta, usage } => {
+            let usage = usage.map(|usage| {
+                Usage::from(AnthropicUsage {
+                    input_tokens: state.input_tokens,
+                    output_tokens: usage.output_tokens,
+                })
+            });
+            let finish_reason = delta.stop_reason.as_deref().map(map_stop_reason);
+            Some(Ok(state.chunk(ChatDelta::default(), finish_reason, usage)))
+        }
+        AnthropicStreamEvent::Error { error } => Some(Err(AppError::BackendError(format!(
+            "{}: {}",
+            error.error_type, error.message
+        )))),
+        AnthropicStreamEvent::Other => None,
+    }
+}
+
+/// Convert a chat chunk into the text completion chunk shape
+fn chat_chunk_to_text_chunk(chunk: ChatCompletionChunk) -> TextCompletionResponse {
+    TextCompletionResponse {
+        id: chunk.id,
+        object: "text_completion".to_string(),
+        created: chunk.created,
+        model: chunk.model,
+        choices: chunk
+            .choices
+            .into_iter()
+            .map(|choice| TextChoice {
+                index: choice.index,
+                text: choice.delta.content.unwrap_or_default(),
+                finish_reason: choice.finish_reason,
+            })
+            .collect(),
+        usage: chunk.usage,
+    }
+}
+
+/// Wrap a text completion prompt as a single-turn chat request
+fn text_to_chat_request(request: TextCompletionRequest) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: request.model,
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: request.prompt,
+            name: None,
+        }],
+        temperature: request.temperature,
+        top_p: request.top_p,
+        max_tokens: request.max_tokens,
+        stream: request.stream,
+        stop: request.stop,
+        presence_penalty: None,
+        frequency_penalty: None,
+        user: None,
+    }
+}
+
+/// Anthropic-specific backend (Claude Messages API)
+pub struct AnthropicBackend {
+    inner: OpenAICompatibleBackend,
+}
+
+impl AnthropicBackend {
+    pub fn new(config: &BackendConfig) -> Result<Self> {
+        Ok(Self {
+            inner: OpenAICompatibleBackend::new(config)?,
+        })
+    }
+
+    /// Get headers for the Messages API (`x-api-key` and `anthropic-version`)
+    fn get_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert("anthropic-version", HeaderValue::from_static(ANTHROPIC_VERSION));
+
+        if let Some(token) = &self.inner.auth_token {
+            let header_name = self.inner.auth_header_name.as_deref().unwrap_or("x-api-key");
+            if let (Ok(name), Ok(value)) = (
+                reqwest::header::HeaderName::from_bytes(header_name.as_bytes()),
+                HeaderValue::from_str(token),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+
+        headers
+    }
+
+    /// POST a request to `/messages`, mapping Anthropic error bodies into `AppError`
+    async fn send_messages(&self, body: &AnthropicMessagesRequest) -> Result<reqwest::Response> {
+        debug!(backend = %self.inner.name, model = %body.model, "Sending messages request");
+
+        let response = if body.stream == Some(true) {
+            self.inner.send_stream("/messages", self.get_headers(), body).await?
+        } else {
+            self.inner.send("/messages", self.get_headers(), body).await?
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<AnthropicErrorResponse>(&body)
+            .map(|e| format!("{}: {}", e.error.error_type, e.error.message))
+            .unwrap_or(body);
+
+        Err(match status.as_u16() {
+            400 | 404 | 413 => AppError::InvalidRequest(message),
+            429 => AppError::RateLimitExceeded,
+            _ => AppError::BackendError(format!("Backend returned {}: {}", status, message)),
+        })
+    }
+}
+
+#[async_trait]
+impl TextBackend for AnthropicBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn protocol(&self) -> &str {
+        "anthropic"
+    }
+
+    fn models(&self) -> Vec<String> {
+        self.inner.models()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.inner.capabilities()
+    }
+
+    async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
+        let body = to_anthropic_request(request, false);
+        let response = self.send_messages(&body).await?;
+
+        let result = response.json::<AnthropicMessagesResponse>().await.map_err(|e| {
+            error!(backend = %self.inner.name, error = %e, "Failed to parse messages response");
+            AppError::BackendError(format!("Failed to parse response: {}", e))
+        })?;
+
+        Ok(from_anthropic_response(result))
+    }
+
+    async fn text_completion(&self, request: TextCompletionRequest) -> Result<TextCompletionResponse> {
+        // The Messages API has no plain completion endpoint, so send the prompt as a user turn
+        let response = self.chat_completion(text_to_chat_request(request)).await?;
+
+        Ok(TextCompletionResponse {
+            id: response.id,
+            object: "text_completion".to_string(),
+            created: response.created,
+            model: response.model,
+            choices: response
+                .choices
+                .into_iter()
+                .map(|choice| TextChoice {
+                    index: choice.index,
+                    text: choice.message.content,
+                    finish_reason: choice.finish_reason,
+                })
+                .collect(),
+            usage: response.usage,
+        })
+    }
+
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> Result<ChatCompletionStream> {
+        let body = to_anthropic_request(request, true);
+        let response = self.send_messages(&body).await?;
+        let backend = self.inner.name.clone();
+
+        let chunks = sse_data_stream(response, self.inner.timeout)
+            .scan(AnthropicStreamState::default(), move |state, data| {
+                let item = match data.and_then(|data| {
+                    serde_json::from_str::<AnthropicStreamEvent>(&data).map_err(|e| {
+                        error!(backend = %backend, error = %e, "Failed to parse stream event");
+                        AppError::BackendError(format!("Failed to parse stream event: {}", e))
+                    })
+                }) {
+                    Ok(event) => from_anthropic_event(state, event),
+                    Err(e) => Some(Err(e)),
+                };
+                futures::future::ready(Some(item))
+            })
+            .filter_map(futures::future::ready)
+            .boxed();
+
+        Ok(chunks)
+    }
+
+    async fn text_completion_stream(&self, request: TextCompletionRequest) -> Result<TextCompletionStream> {
+        let chunks = self.chat_completion_stream(text_to_chat_request(request)).await?;
+        Ok(chunks.map(|chunk| chunk.map(chat_chunk_to_text_chunk)).boxed())
+    }
+
+    async fn list_models(&self) -> Result<ModelsResponse> {
+        // Anthropic doesn't have a models endpoint, return configured models
+        Ok(ModelsResponse {
+            object: "list".to_string(),
+            data: self.inner.models().iter().map(|id| ModelInfo {
+                id: id.clone(),
+                object: "model".to_string(),
+                created: None,
+                owned_by: Some("anthropic".to_string()),
+            }).collect(),
+        })
+    }
+
+    async fn health_check(&self) -> bool {
+        self.inner.health_check().await
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.inner.is_enabled()
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.inner.set_enabled(enabled);
+    }
+
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        self.inner.endpoint_strategy()
+    }
+
+    fn set_endpoint_strategy(&self, strategy: LoadBalancingStrategy) {
+        self.inner.set_endpoint_strategy(strategy);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        self.inner.health_check_schedule()
+    }
+
+    fn status(&self) -> TextBackendStatus {
+        let mut status = self.inner.status();
+        status.protocol = "anthropic".to_string();
+        status
+    }
+}
+
+/// Prompt template used to flatten chat messages into a single TGI input
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatTemplate {
+    /// Per-message format with `{role}` and `{content}` placeholders; the generation
+    /// prompt is the format rendered for `assistant` up to `{content}`
+    Format(String),
+    /// Mistral / Llama 2 `[INST]` format, with system prompts folded into the next user turn
+    Instruct,
+}
+
+impl Default for ChatTemplate {
+    fn default() -> Self {
+        Self::from_config("chatml")
+    }
+}
+
+impl ChatTemplate {
+    /// Resolve a configured template name, treating anything else as a custom format
+    pub fn from_config(template: &str) -> Self {
+        match template {
+            "chatml" => Self::Format("<|im_start|>{role}\n{content}<|im_end|>\n".to_string()),
+            "llama3" => Self::Format("<|start_header_id|>{role}<|end_header_id|>\n\n{content}<|eot_id|>".to_string()),
+            "mistral" | "llama2" => Self::Instruct,
+            custom => Self::Format(custom.to_string()),
+        }
+    }
+
+    /// Render messages into a prompt ending where the assistant reply should begin
+    ///
+    /// No BOS token is emitted; TGI's tokenizer adds it.
+    pub fn render(&self, messages: &[ChatMessage]) -> String {
+        match self {
+            Self::Format(message) => {
+                let mut prompt = String::new();
+                for m in messages {
+                    prompt.push_str(&message.replace("{role}", &m.role).replace("{content}", &m.content));
+                }
+                let generation = message.replace("{role}", "assistant");
+                let end = generation.find("{content}").unwrap_or(generation.len());
+                prompt.push_str(&generation[..end]);
+                prompt
+            }
+            Self::Instruct => {
+                let mut prompt = String::new();
+                let mut system: Option<String> = None;
+                for m in messages {
+                    match m.role.as_str() {
+                        "system" => system = Some(m.content.clone()),
+                        "assistant" => {
+                            prompt.push_str(&format!(" {}</s>", m.content));
+                        }
+                        _ => {
+                            let content = match system.take() {
+                                Some(system) => format!("{}\n\n{}", system, m.content),
+                                None => m.content.clone(),
+                            };
+                            prompt.push_str(&format!("[INST] {} [/INST]", content));
+                        }
+                    }
+                }
+                prompt
+            }
+        }
+    }
+}
+
+/// TGI `/generate` and `/generate_stream` request
+#[derive(Debug, Serialize)]
+struct TgiGenerateRequest {
+    inputs: String,
+    parameters: TgiParameters,
+}
+
+/// TGI sampling parameters
+#[derive(Debug, Default, Serialize)]
+struct TgiParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_new_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    do_sample: bool,
+    details: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    decoder_input_details: bool,
+    return_full_text: bool,
+}
+
+impl TgiParameters {
+    /// Map OpenAI-style sampling parameters onto TGI's constraints
+    ///
+    /// TGI rejects `temperature <= 0` and `top_p` outside `(0, 1)`, so those
+    /// values are dropped and a zero temperature selects greedy decoding.
+    fn new(
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        top_p: Option<f32>,
+        frequency_penalty: Option<f32>,
+        stop: Option<Vec<String>>,
+        stream: bool,
+    ) -> Self {
+        let temperature = temperature.filter(|t| *t > 0.0);
+        let top_p = top_p.filter(|p| *p > 0.0 && *p < 1.0);
+
+        Self {
+            max_new_tokens: max_tokens,
+            do_sample: temperature.is_some() || top_p.is_some(),
+            temperature,
+            top_p,
+            frequency_penalty,
+            stop: stop.unwrap_or_default(),
+            details: true,
+            // Prefill tokens give the prompt token count but are not allowed when streaming
+            decoder_input_details: !stream,
+            return_full_text: false,
+        }
+    }
+}
+
+/// TGI `/generate` response
+#[derive(Debug, Deserialize)]
+struct TgiGenerateResponse {
+    generated_text: String,
+    #[serde(default)]
+    details: Option<TgiDetails>,
+}
+
+/// TGI generation details
+#[derive(Debug, Deserialize)]
+struct TgiDetails {
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    generated_tokens: u32,
+    #[serde(default)]
+    prefill: Vec<serde_json::Value>,
+}
+
+impl TgiDetails {
+    fn usage(&self) -> Usage {
+        let prompt_tokens = self.prefill.len() as u32;
+        Usage {
+            prompt_tokens,
+            completion_tokens: self.generated_tokens,
+            total_tokens: prompt_tokens + self.generated_tokens,
+        }
+    }
+}
+
+/// One `/generate_stream` event
+#[derive(Debug, Deserialize)]
+struct TgiStreamResponse {
+    token: TgiToken,
+    #[serde(default)]
+    details: Option<TgiDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TgiToken {
+    text: String,
+    #[serde(default)]
+    special: bool,
+}
+
+/// TGI `/info` response
+#[derive(Debug, Deserialize)]
+struct TgiInfo {
+    model_id: String,
+}
+
+/// Map a TGI finish reason onto the OpenAI `finish_reason` vocabulary
+fn map_tgi_finish_reason(finish_reason: &str) -> String {
+    match finish_reason {
+        "eos_token" | "stop_sequence" => "stop",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Completion id for TGI responses, which carry none of their own
+fn tgi_completion_id(prefix: &str) -> String {
+    format!("{}-{}", prefix, uuid::Uuid::new_v4().simple())
+}
+
+/// Hugging Face Text Generation Inference backend using the native `/generate` API
+pub struct TgiBackend {
+    inner: OpenAICompatibleBackend,
+    chat_template: ChatTemplate,
+}
+
+impl TgiBackend {
+    pub fn new(config: &BackendConfig) -> Result<Self> {
+        Ok(Self {
+            inner: OpenAICompatibleBackend::new(config)?,
+            chat_template: config
+                .chat_template
+                .as_deref()
+                .map(ChatTemplate::from_config)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// POST a request to a TGI route on the next healthy endpoint
+    async fn post(&self, path: &str, body: &TgiGenerateRequest, stream: bool) -> Result<reqwest::Response> {
+        debug!(backend = %self.inner.name, path = %path, "Sending generate request");
+
+        let response = if stream {
+            self.inner.send_stream(path, self.inner.get_headers(), body).await?
+        } else {
+            self.inner.send(path, self.inner.get_headers(), body).await?
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+
+        // TGI reports errors as `{"error": "...", "error_type": "..."}`
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string))
+            .unwrap_or(body);
+
+        Err(match status.as_u16() {
+            400 | 422 => AppError::InvalidRequest(message),
+            429 => AppError::RateLimitExceeded,
+            _ => AppError::BackendError(format!("Backend returned {}: {}", status, message)),
+        })
+    }
+
+    /// Run `/generate` and return the generated text with its details
+    async fn generate(&self, inputs: String, parameters: TgiParameters) -> Result<TgiGenerateResponse> {
+        let response = self.post("/generate", &TgiGenerateRequest { inputs, parameters }, false).await?;
+
+        response.json::<TgiGenerateResponse>().await.map_err(|e| {
+            error!(backend = %self.inner.name, error = %e, "Failed to parse generate response");
+            AppError::BackendError(format!("Failed to parse response: {}", e))
+        })
+    }
+
+    /// Run `/generate_stream` and yield each token's text, with the final
+    /// event's finish reason and usage
+    async fn generate_stream(
+        &self,
+        inputs: String,
+        parameters: TgiParameters,
+    ) -> Result<BoxStream<'static, Result<(String, Option<String>, Option<Usage>)>>> {
+        let response = self
+            .post("/generate_stream", &TgiGenerateRequest { inputs, parameters }, true)
+            .await?;
+        let backend = self.inner.name.clone();
+
+        Ok(sse_data_stream(response, self.inner.timeout)
+            .map(move |data| {
+                let event = serde_json::from_str::<TgiStreamResponse>(&data?).map_err(|e| {
+                    error!(backend = %backend, error = %e, "Failed to parse stream event");
+                    AppError::BackendError(format!("Failed to parse stream event: {}", e))
+                })?;
+                let text = if event.token.special { String::new() } else { event.token.text };
+                let finish_reason = event
+                    .details
+                    .as_ref()
+                    .and_then(|d| d.finish_reason.as_deref())
+                    .map(map_tgi_finish_reason);
+                Ok((text, finish_reason, event.details.map(|d| d.usage())))
+            })
+            .boxed())
+    }
+
+    /// Fetch `/info` from an endpoint
+    async fn info(&self, endpoint: &str) -> Result<TgiInfo> {
+        let url = format!("{}/info", endpoint.trim_end_matches('/'));
+
+        let response = self
+            .inner
+            .client
+            .get(&url)
+            .headers(self.inner.get_headers())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::BackendError(format!(
+                "Backend returned {} for /info",
+                response.status()
+            )));
+        }
+
+        response
+            .json::<TgiInfo>()
+            .await
+            .map_err(|e| AppError::BackendError(format!("Failed to parse /info response: {}", e)))
+    }
+}
+
+#[async_trait]
+impl TextBackend for TgiBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn protocol(&self) -> &str {
+        "tgi"
+    }
+
+    fn models(&self) -> Vec<String> {
+        self.inner.models()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.inner.capabilities()
+    }
+
+    async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
+        let inputs = self.chat_template.render(&request.messages);
+        let parameters = TgiParameters::new(
+            request.max_tokens,
+            request.temperature,
+            request.top_p,
+            request.frequency_penalty,
+            request.stop,
+            false,
+        );
+        let result = self.generate(inputs, parameters).await?;
+
+        Ok(ChatCompletionResponse {
+            id: tgi_completion_id("chatcmpl"),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: request.model,
+            choices: vec![ChatChoice {
+                index: 0,
+                message: ChatMessage {
+                    role: "assistant".to_string(),
+                    content: result.generated_text,
+                    name: None,
+                },
+                finish_reason: result
+                    .details
+                    .as_ref()
+                    .and_then(|d| d.finish_reason.as_deref())
+                    .map(map_tgi_finish_reason),
+            }],
+            usage: result.details.map(|d| d.usage()),
+        })
+    }
+
+    async fn text_completion(&self, request: TextCompletionRequest) -> Result<TextCompletionResponse> {
+        let parameters = TgiParameters::new(
+            request.max_tokens,
+            request.temperature,
+            request.top_p,
+            None,
+            request.stop,
+            false,
+        );
+        let result = self.generate(request.prompt, parameters).await?;
+
+        Ok(TextCompletionResponse {
+            id: tgi_completion_id("cmpl"),
+            object: "text_completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: request.model,
+            choices: vec![TextChoice {
+                index: 0,
+                text: result.generated_text,
+                finish_reason: result
+                    .details
+                    .as_ref()
+                    .and_then(|d| d.finish_reason.as_deref())
+                    .map(map_tgi_finish_reason),
+            }],
+            usage: result.details.map(|d| d.usage()),
+        })
+    }
+
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> Result<ChatCompletionStream> {
+        let inputs = self.chat_template.render(&request.messages);
+        let parameters = TgiParameters::new(
+            request.max_tokens,
+            request.temperature,
+            request.top_p,
+            request.frequency_penalty,
+            request.stop,
+            true,
+        );
+        let tokens = self.generate_stream(inputs, parameters).await?;
+
+        let id = tgi_completion_id("chatcmpl");
+        let created = chrono::Utc::now().timestamp();
+        let model = request.model;
+
+        Ok(tokens
+            .enumerate()
+            .map(move |(i, token)| {
+                let (text, finish_reason, usage) = token?;
+                Ok(ChatCompletionChunk {
+                    id: id.clone(),
+                    object: "chat.completion.chunk".to_string(),
+                    created,
+                    model: model.clone(),
+                    choices: vec![ChatChunkChoice {
+                        index: 0,
+                        delta: ChatDelta {
+                            role: (i == 0).then(|| "assistant".to_string()),
+                            content: Some(text),
+                        },
+                        finish_reason,
+                    }],
+                    usage,
+                })
+            })
+            .boxed())
+    }
+
+    async fn text_completion_stream(&self, request: TextCompletionRequest) -> Result<TextCompletionStream> {
+        let parameters = TgiParameters::new(
+            request.max_tokens,
+            request.temperature,
+            request.top_p,
+            None,
+            request.stop,
+            true,
+        );
+        let tokens = self.generate_stream(request.prompt, parameters).await?;
+
+        let id = tgi_completion_id("cmpl");
+        let created = chrono::Utc::now().timestamp();
+        let model = request.model;
+
+        Ok(tokens
+            .map(move |token| {
+                let (text, finish_reason, usage) = token?;
+                Ok(TextCompletionResponse {
+                    id: id.clone(),
+                    object: "text_completion".to_string(),
+                    created,
+                    model: model.clone(),
+                    choices: vec![TextChoice {
+                        index: 0,
+                        text,
+                        finish_reason,
+                    }],
+                    usage,
+                })
+            })
+            .boxed())
+    }
+
+    async fn list_models(&self) -> Result<ModelsResponse> {
+        let (endpoint, _) = self
+            .inner
+            .get_next_endpoint()
+            .ok_or_else(|| AppError::NoHealthyBackends(self.inner.name.clone()))?;
+
+        // A TGI server hosts exactly one model; fall back to configured models if /info fails
+        let models = match self.info(&endpoint).await {
+            Ok(info) => vec![info.model_id],
+            Err(e) => {
+                warn!(backend = %self.inner.name, error = %e, "Failed to fetch /info, using configured models");
+                self.inner.models()
+            }
+        };
+
+        Ok(ModelsResponse {
+            object: "list".to_string(),
+            data: models.into_iter().map(|id| ModelInfo {
+                id,
+                object: "model".to_string(),
+                created: None,
+                owned_by: Some(self.inner.name.clone()),
+            }).collect(),
+        })
+    }
+
+    async fn health_check(&self) -> bool {
+        let endpoints = self.inner.endpoints.read().clone();
+        let mut any_healthy = false;
+
+        for endpoint in &endpoints {
+            match self.info(&endpoint.url).await {
+                Ok(info) => {
+                    self.inner.mark_endpoint_healthy(&endpoint.url, None);
+                    any_healthy = true;
+                    debug!(
+                        backend = %self.inner.name,
+                        endpoint = %endpoint.url,
+                        model = %info.model_id,
+                        "Health check passed"
+                    );
+                }
+                Err(e) => {
+                    self.inner.mark_endpoint_unhealthy(&endpoint.url);
+                    debug!(
+                        backend = %self.inner.name,
+                        endpoint = %endpoint.url,
+                        error = %e,
+                        "Health check failed"
+                    );
+                }
+            }
+        }
+
+        any_healthy
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.inner.is_enabled()
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.inner.set_enabled(enabled);
+    }
+
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        self.inner.endpoint_strategy()
+    }
+
+    fn set_endpoint_strategy(&self, strategy: LoadBalancingStrategy) {
+        self.inner.set_endpoint_strategy(strategy);
+    }
+
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        self.inner.health_check_schedule()
+    }
+
+    fn status(&self) -> TextBackendStatus {
+        self.inner.status()
     }
 }
 
@@ -622,12 +1841,534 @@ pub fn create_text_backend(config: &BackendConfig) -> Result<Arc<dyn TextBackend
         ProtocolType::Anthropic => {
             Ok(Arc::new(AnthropicBackend::new(config)?))
         }
-        ProtocolType::OpenAI | ProtocolType::Http | ProtocolType::Tgi => {
+        ProtocolType::Tgi => {
+            Ok(Arc::new(TgiBackend::new(config)?))
+        }
+        ProtocolType::OpenAI | ProtocolType::Http => {
             Ok(Arc::new(OpenAICompatibleBackend::new(config)?))
         }
         ProtocolType::Grpc => {
             Err(AppError::Internal("gRPC text backends not yet supported".to_string()))
         }
+        ProtocolType::SdWebUi | ProtocolType::ComfyUi => Err(AppError::Internal(format!(
+            "{} backends only serve image generation",
+            config.protocol
+        ))),
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn test_config(endpoint: String) -> BackendConfig {
+        BackendConfig {
+            name: "openai-test".to_string(),
+            backend_type: crate::config::BackendType::Text,
+            protocol: ProtocolType::OpenAI,
+            endpoints: vec![endpoint],
+            ..Default::default()
+        }
+    }
+
+    fn chat_request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "llama3".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hi".to_string(),
+                name: None,
+            }],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            user: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_stream() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"llama3\",",
+            "\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"llama3\",",
+            "\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\r\n\r\n",
+            "data: [DONE]\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({ "stream": true })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let backend = OpenAICompatibleBackend::new(&test_config(server.uri())).unwrap();
+        let chunks: Vec<_> = backend
+            .chat_completion_stream(chat_request())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 2);
+        let first = chunks[0].as_ref().unwrap();
+        assert_eq!(first.choices[0].delta.role.as_deref(), Some("assistant"));
+        assert_eq!(first.choices[0].delta.content.as_deref(), Some("Hel"));
+        let second = chunks[1].as_ref().unwrap();
+        assert_eq!(second.choices[0].finish_reason.as_deref(), Some("stop"));
+    }
+
+    /// Serve one streaming chat completion, sending each chunk after its delay
+    async fn slow_stream_server(chunks: Vec<(Duration, &'static str)>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            // Read the whole request before answering
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .filter_map(|line| line.split_once(':'))
+                        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                        .map_or(0, |(_, value)| value.trim().parse::<usize>().unwrap());
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            for (delay, data) in chunks {
+                tokio::time::sleep(delay).await;
+                let chunk = format!("{:x}\r\n{}\r\n", data.len(), data);
+                if socket.write_all(chunk.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+            let _ = socket.write_all(b"0\r\n\r\n").await;
+        });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_stream_outlives_timeout() {
+        const CHUNK: &str = "data: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"llama3\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"x\"}}]}\n\n";
+        let gap = Duration::from_millis(150);
+        let endpoint = slow_stream_server(vec![(gap, CHUNK), (gap, CHUNK), (gap, CHUNK), (gap, CHUNK), (gap, "data: [DONE]\n\n")]).await;
+
+        // The stream runs for 750ms, well past the 300ms timeout, but never idles that long
+        let config = BackendConfig {
+            timeout_ms: 300,
+            ..test_config(endpoint)
+        };
+        let backend = OpenAICompatibleBackend::new(&config).unwrap();
+        let chunks: Vec<_> = backend
+            .chat_completion_stream(chat_request())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|chunk| chunk.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_stream_idle_timeout() {
+        const CHUNK: &str = "data: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"llama3\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"x\"}}]}\n\n";
+        let endpoint = slow_stream_server(vec![
+            (Duration::ZERO, CHUNK),
+            (Duration::from_millis(600), "data: [DONE]\n\n"),
+        ])
+        .await;
+
+        let config = BackendConfig {
+            timeout_ms: 200,
+            ..test_config(endpoint)
+        };
+        let backend = OpenAICompatibleBackend::new(&config).unwrap();
+        let chunks: Vec<_> = backend
+            .chat_completion_stream(chat_request())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        // A stalled backend ends the stream with an error rather than silently
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].is_ok());
+        assert!(matches!(chunks[1], Err(AppError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_stream_upstream_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("bad model"))
+            .mount(&server)
+            .await;
+
+        let backend = OpenAICompatibleBackend::new(&test_config(server.uri())).unwrap();
+        let result = backend.chat_completion_stream(chat_request()).await;
+
+        assert!(matches!(result, Err(AppError::BackendError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(503).insert_header("retry-after", "0"))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "c1",
+                "object": "chat.completion",
+                "created": 1,
+                "model": "llama3",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hello"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let backend = OpenAICompatibleBackend::new(&test_config(server.uri())).unwrap();
+        let response = backend.chat_completion(chat_request()).await.unwrap();
+        assert_eq!(response.choices[0].message.content, "Hello");
+
+        // With retries disabled the 503 reaches the caller
+        server.reset().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let backend = OpenAICompatibleBackend::new(&BackendConfig {
+            retry_count: Some(0),
+            ..test_config(server.uri())
+        })
+        .unwrap();
+        let result = backend.chat_completion(chat_request()).await;
+        assert!(matches!(result, Err(AppError::BackendError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_list_models_server_error_counts_as_failure() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let backend = OpenAICompatibleBackend::new(&BackendConfig {
+            models: vec!["llama3".to_string()],
+            ..test_config(server.uri())
+        })
+        .unwrap();
+        // Configured models stand in for the failed listing
+        let models = backend.list_models().await.unwrap();
+        assert_eq!(models.data[0].id, "llama3");
+
+        let circuit = &backend.status().circuits[0].circuit;
+        assert_eq!(circuit.requests, 1);
+        assert_eq!(circuit.failure_rate, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_strategy() {
+        let busy = MockServer::start().await;
+        let idle = MockServer::start().await;
+        let models = ResponseTemplate::new(200).set_body_json(serde_json::json!({"object": "list", "data": []}));
+        Mock::given(method("GET")).respond_with(models.clone()).expect(1).mount(&busy).await;
+        Mock::given(method("GET")).respond_with(models).expect(4).mount(&idle).await;
+
+        let mut config = test_config(busy.uri());
+        config.endpoints.push(idle.uri());
+        config.load_balancer.strategy = "least_connections".to_string();
+        let backend = OpenAICompatibleBackend::new(&config).unwrap();
+        assert_eq!(backend.endpoint_strategy(), LoadBalancingStrategy::LeastConnections);
+
+        // A request held open on the first endpoint steers traffic to the second
+        let held = backend.endpoints.read()[0].load.start();
+        for _ in 0..3 {
+            backend.list_models().await.unwrap();
+        }
+        drop(held);
+
+        // Round-robin ignores load and alternates
+        backend.set_endpoint_strategy(LoadBalancingStrategy::RoundRobin);
+        for _ in 0..2 {
+            backend.list_models().await.unwrap();
+        }
+    }
+
+    fn anthropic_config(endpoint: String) -> BackendConfig {
+        BackendConfig {
+            name: "anthropic-test".to_string(),
+            protocol: ProtocolType::Anthropic,
+            auth: crate::config::BackendAuth {
+                api_key: Some("test-key".to_string()),
+                ..Default::default()
+            },
+            ..test_config(endpoint)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_chat_completion() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(header("anthropic-version", ANTHROPIC_VERSION))
+            .and(header("x-api-key", "test-key"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "claude-3-haiku",
+                "max_tokens": ANTHROPIC_DEFAULT_MAX_TOKENS,
+                "system": "Be brief.",
+                "messages": [{ "role": "user", "content": "Hi" }],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-haiku",
+                "content": [{ "type": "text", "text": "Hello!" }],
+                "stop_reason": "max_tokens",
+                "usage": { "input_tokens": 10, "output_tokens": 3 },
+            })))
+            .mount(&server)
+            .await;
+
+        let mut request = chat_request();
+        request.model = "claude-3-haiku".to_string();
+        request.messages.insert(0, ChatMessage {
+            role: "system".to_string(),
+            content: "Be brief.".to_string(),
+            name: None,
+        });
+
+        let backend = AnthropicBackend::new(&anthropic_config(server.uri())).unwrap();
+        let response = backend.chat_completion(request).await.unwrap();
+
+        assert_eq!(response.id, "msg_1");
+        assert_eq!(response.object, "chat.completion");
+        assert_eq!(response.choices[0].message.content, "Hello!");
+        assert_eq!(response.choices[0].finish_reason.as_deref(), Some("length"));
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 10);
+        assert_eq!(usage.completion_tokens, 3);
+        assert_eq!(usage.total_tokens, 13);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_error_mapping() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "type": "error",
+                "error": { "type": "invalid_request_error", "message": "max_tokens: too large" },
+            })))
+            .mount(&server)
+            .await;
+
+        let backend = AnthropicBackend::new(&anthropic_config(server.uri())).unwrap();
+        let result = backend.chat_completion(chat_request()).await;
+
+        match result {
+            Err(AppError::InvalidRequest(message)) => assert!(message.contains("max_tokens: too large")),
+            other => panic!("unexpected result: {:?}", other.map(|r| r.id)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_chat_completion_stream() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_2\",\"model\":\"claude-3-haiku\",",
+            "\"usage\":{\"input_tokens\":7,\"output_tokens\":1}}}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":2}}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(body_partial_json(serde_json::json!({ "stream": true })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let backend = AnthropicBackend::new(&anthropic_config(server.uri())).unwrap();
+        let chunks: Vec<_> = backend
+            .chat_completion_stream(chat_request())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 3);
+        let role = chunks[0].as_ref().unwrap();
+        assert_eq!(role.id, "msg_2");
+        assert_eq!(role.choices[0].delta.role.as_deref(), Some("assistant"));
+        assert_eq!(chunks[1].as_ref().unwrap().choices[0].delta.content.as_deref(), Some("Hi"));
+        let last = chunks[2].as_ref().unwrap();
+        assert_eq!(last.choices[0].finish_reason.as_deref(), Some("stop"));
+        assert_eq!(last.usage.as_ref().unwrap().total_tokens, 9);
+    }
+    fn tgi_config(endpoint: String) -> BackendConfig {
+        BackendConfig {
+            name: "tgi-test".to_string(),
+            protocol: ProtocolType::Tgi,
+            ..test_config(endpoint)
+        }
+    }
+
+    #[test]
+    fn test_chat_templates() {
+        let messages = vec![
+            ChatMessage { role: "system".to_string(), content: "Be brief.".to_string(), name: None },
+            ChatMessage { role: "user".to_string(), content: "Hi".to_string(), name: None },
+        ];
+
+        assert_eq!(
+            ChatTemplate::default().render(&messages),
+            "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n<|im_start|>assistant\n"
+        );
+        assert_eq!(
+            ChatTemplate::from_config("mistral").render(&messages),
+            "[INST] Be brief.\n\nHi [/INST]"
+        );
+        assert_eq!(
+            ChatTemplate::from_config("{role}: {content}\n").render(&messages),
+            "system: Be brief.\nuser: Hi\nassistant: "
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tgi_chat_completion() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/generate"))
+            .and(body_partial_json(serde_json::json!({
+                "inputs": "<|im_start|>user\nHi<|im_end|>\n<|im_start|>assistant\n",
+                "parameters": { "max_new_tokens": 16, "do_sample": false, "details": true },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "generated_text": "Hello!",
+                "details": {
+                    "finish_reason": "eos_token",
+                    "generated_tokens": 3,
+                    "prefill": [{ "id": 1 }, { "id": 2 }, { "id": 3 }, { "id": 4 }],
+                },
+            })))
+            .mount(&server)
+            .await;
+
+        let mut request = chat_request();
+        request.max_tokens = Some(16);
+        request.temperature = Some(0.0);
+
+        let backend = TgiBackend::new(&tgi_config(server.uri())).unwrap();
+        let response = backend.chat_completion(request).await.unwrap();
+
+        assert_eq!(response.model, "llama3");
+        assert_eq!(response.choices[0].message.content, "Hello!");
+        assert_eq!(response.choices[0].finish_reason.as_deref(), Some("stop"));
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 4);
+        assert_eq!(usage.completion_tokens, 3);
+        assert_eq!(usage.total_tokens, 7);
+    }
+
+    #[tokio::test]
+    async fn test_tgi_text_completion_stream() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data:{\"token\":{\"id\":1,\"text\":\"Hel\",\"logprob\":-0.1,\"special\":false},",
+            "\"generated_text\":null,\"details\":null}\n\n",
+            "data:{\"token\":{\"id\":2,\"text\":\"</s>\",\"logprob\":-0.1,\"special\":true},",
+            "\"generated_text\":\"Hel\",\"details\":{\"finish_reason\":\"length\",\"generated_tokens\":2}}\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/generate_stream"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let backend = TgiBackend::new(&tgi_config(server.uri())).unwrap();
+        let request = TextCompletionRequest {
+            model: "llama3".to_string(),
+            prompt: "Say hello".to_string(),
+            max_tokens: Some(2),
+            temperature: None,
+            top_p: None,
+            stop: None,
+            stream: Some(true),
+        };
+        let chunks: Vec<_> = backend
+            .text_completion_stream(request)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].as_ref().unwrap().choices[0].text, "Hel");
+        let last = chunks[1].as_ref().unwrap();
+        assert_eq!(last.choices[0].text, "");
+        assert_eq!(last.choices[0].finish_reason.as_deref(), Some("length"));
+        assert_eq!(last.usage.as_ref().unwrap().completion_tokens, 2);
+    }
+
+    #[tokio::test]
+    async fn test_tgi_info_discovery() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/info"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model_id": "mistralai/Mistral-7B-Instruct-v0.2",
+                "max_total_tokens": 4096,
+            })))
+            .mount(&server)
+            .await;
+
+        let backend = TgiBackend::new(&tgi_config(server.uri())).unwrap();
+        assert!(backend.health_check().await);
+
+        let models = backend.list_models().await.unwrap();
+        assert_eq!(models.data.len(), 1);
+        assert_eq!(models.data[0].id, "mistralai/Mistral-7B-Instruct-v0.2");
+    }
+}
@@ -2,16 +2,19 @@
 
 use std::sync::Arc;
 use dashmap::DashMap;
+use parking_lot::RwLock;
 use tracing::{info, warn};
 
 use crate::backend::text_backend::{TextBackend, TextBackendStatus, create_text_backend};
 use crate::config::{BackendConfig, BackendType};
 use crate::error::{AppError, Result};
+use crate::gateway::router::RoutingRules;
 
 /// Registry for text generation backends
 pub struct TextBackendRegistry {
     backends: DashMap<String, Arc<dyn TextBackend>>,
     model_to_backend: DashMap<String, String>,
+    routing: RwLock<RoutingRules>,
 }
 
 impl TextBackendRegistry {
@@ -20,14 +23,16 @@ impl TextBackendRegistry {
         Self {
             backends: DashMap::new(),
             model_to_backend: DashMap::new(),
+            routing: RwLock::new(RoutingRules::default()),
         }
     }
 
     /// Add a backend from configuration
     pub async fn add_backend(&self, config: BackendConfig) -> Result<()> {
-        if config.backend_type != BackendType::Text {
-            return Err(AppError::Internal(format!(
-                "Backend '{}' is not a text backend",
+        check_text_backend(&config)?;
+        if self.backends.contains_key(&config.name) {
+            return Err(AppError::InvalidRequest(format!(
+                "Backend '{}' already exists",
                 config.name
             )));
         }
@@ -46,6 +51,31 @@ impl TextBackendRegistry {
         Ok(())
     }
 
+    /// Add a backend, replacing any existing backend with the same name
+    ///
+    /// The old backend is only replaced once the new one has been created, and
+    /// requests already holding it run to completion.
+    pub async fn replace_backend(&self, config: BackendConfig) -> Result<()> {
+        check_text_backend(&config)?;
+
+        self.insert(create_text_backend(&config)?);
+
+        Ok(())
+    }
+
+    /// Register an already created backend, replacing any backend with the same name
+    pub fn insert(&self, backend: Arc<dyn TextBackend>) {
+        let name = backend.name().to_string();
+
+        self.model_to_backend.retain(|_, v| *v != name);
+        for model in backend.models() {
+            self.model_to_backend.insert(model, name.clone());
+        }
+
+        let replaced = self.backends.insert(name.clone(), backend).is_some();
+        info!(name = %name, replaced = replaced, "Text backend registered");
+    }
+
     /// Remove a backend
     pub async fn remove_backend(&self, name: &str) -> Result<()> {
         if self.backends.remove(name).is_none() {
@@ -64,21 +94,82 @@ impl TextBackendRegistry {
         self.backends.get(name).map(|b| b.value().clone())
     }
 
+    /// Check if a backend exists
+    pub fn contains(&self, name: &str) -> bool {
+        self.backends.contains_key(name)
+    }
+
+    /// Set the model mappings and fallback chains used to route requests
+    pub fn set_routing(&self, rules: RoutingRules) {
+        *self.routing.write() = rules;
+    }
+
+    /// Whether a model is configured on a backend or in the routing mappings
+    pub fn is_configured_model(&self, model: &str) -> bool {
+        self.model_to_backend.contains_key(model) || self.routing.read().has_model(model)
+    }
+
     /// Get a backend for a specific model
     pub async fn get_backend_for_model(
         &self,
         model: &str,
         preferred_backend: Option<&str>,
+    ) -> Result<Arc<dyn TextBackend>> {
+        let mut backends = self.get_backends_for_model(model, preferred_backend).await?;
+        Ok(backends.remove(0))
+    }
+
+    /// Get the backends to try for a model, in order
+    ///
+    /// The first is the backend [`get_backend_for_model`](Self::get_backend_for_model)
+    /// would pick, followed by its configured fallbacks; unhealthy backends
+    /// move behind healthy ones. Never empty on success.
+    pub async fn get_backends_for_model(
+        &self,
+        model: &str,
+        preferred_backend: Option<&str>,
+    ) -> Result<Vec<Arc<dyn TextBackend>>> {
+        let primary = self.select_primary(model, preferred_backend)?;
+
+        let mut backends = vec![primary.clone()];
+        for name in self.routing.read().fallbacks(primary.name()) {
+            match self.backends.get(&name) {
+                Some(backend) if backend.is_enabled() => backends.push(backend.value().clone()),
+                _ => {}
+            }
+        }
+        backends.sort_by_key(|backend| !backend.status().healthy);
+
+        Ok(backends)
+    }
+
+    fn select_primary(
+        &self,
+        model: &str,
+        preferred_backend: Option<&str>,
     ) -> Result<Arc<dyn TextBackend>> {
         // If preferred backend specified, use it
         if let Some(backend_name) = preferred_backend {
-            if let Some(backend) = self.backends.get(backend_name) {
+            match self.backends.get(backend_name) {
+                Some(backend) if backend.is_enabled() => return Ok(backend.value().clone()),
+                Some(_) => warn!(backend = %backend_name, "Preferred backend is disabled, falling back"),
+                None => warn!(backend = %backend_name, "Preferred backend not found, falling back"),
+            }
+        }
+
+        // Try the configured routing mappings
+        let mapped = self
+            .routing
+            .read()
+            .backend_for_model(model, |name| self.backends.contains_key(name))
+            .map(String::from);
+        if let Some(backend) = mapped.and_then(|name| self.backends.get(&name)) {
+            if backend.is_enabled() {
                 return Ok(backend.value().clone());
             }
-            warn!(backend = %backend_name, "Preferred backend not found, falling back");
         }
 
-        // Try to find backend by model mapping
+        // Try to find backend by the backends' configured models
         if let Some(backend_name) = self.model_to_backend.get(model) {
             if let Some(backend) = self.backends.get(backend_name.value()) {
                 let b = backend.value().clone();
@@ -145,9 +236,70 @@ impl TextBackendRegistry {
     }
 }
 
+/// Only text backends are created here; multi backends are shared with the
+/// image registry through `register_multi_backend`
+fn check_text_backend(config: &BackendConfig) -> Result<()> {
+    if config.backend_type != BackendType::Text {
+        return Err(AppError::Internal(format!(
+            "Backend '{}' is not a text backend",
+            config.name
+        )));
+    }
+    Ok(())
+}
+
 impl Default for TextBackendRegistry {
     fn default() -> Self {
         Self::new()
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ProtocolType, RoutingConfig};
+
+    async fn registry_with(names: &[&str]) -> TextBackendRegistry {
+        let registry = TextBackendRegistry::new();
+        for name in names {
+            registry
+                .add_backend(BackendConfig {
+                    name: name.to_string(),
+                    backend_type: BackendType::Text,
+                    protocol: ProtocolType::OpenAI,
+                    endpoints: vec!["http://localhost:8000/v1".to_string()],
+                    models: vec![format!("{}-model", name)],
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_routing_mappings_and_fallbacks() {
+        let registry = registry_with(&["openai", "azure", "local"]).await;
+        registry.set_routing(RoutingRules::from_config(&RoutingConfig {
+            model_mappings: [("gpt-*".to_string(), "openai".to_string())].into_iter().collect(),
+            fallbacks: [("openai".to_string(), vec!["azure".to_string(), "missing".to_string()])]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }));
+
+        let names = |backends: Vec<Arc<dyn TextBackend>>| {
+            backends.iter().map(|b| b.name().to_string()).collect::<Vec<_>>()
+        };
+
+        let backends = registry.get_backends_for_model("gpt-4o", None).await.unwrap();
+        assert_eq!(names(backends), vec!["openai", "azure"]);
+
+        // Explicit backends and configured models still take precedence
+        let backends = registry.get_backends_for_model("gpt-4o", Some("local")).await.unwrap();
+        assert_eq!(names(backends), vec!["local"]);
+        let backends = registry.get_backends_for_model("azure-model", None).await.unwrap();
+        assert_eq!(names(backends), vec!["azure"]);
+    }
+}
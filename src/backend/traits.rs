@@ -2,11 +2,66 @@
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
 
+use crate::config::CircuitBreakerConfig;
 use crate::error::Result;
+use crate::gateway::circuit_breaker::{CircuitBreaker, CircuitSnapshot};
+use crate::gateway::health_check::HealthCheckSchedule;
+use crate::gateway::load_balancer::LoadBalancingStrategy;
+use crate::gateway::load_tracker::LoadStats;
+
+/// Capability of image backends that edit a source image within a mask
+pub const CAPABILITY_EDIT: &str = "edit";
+
+/// Capability of image backends that generate variations of a source image
+pub const CAPABILITY_VARIATIONS: &str = "variations";
+
+/// Capability of image backends that start from an init image (img2img)
+pub const CAPABILITY_IMG2IMG: &str = "img2img";
+
+/// Capability of image backends that take ControlNet-style control inputs
+pub const CAPABILITY_CONTROLNET: &str = "controlnet";
+
+/// Control input conditioning a generation (ControlNet)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ControlInput {
+    /// Kind of control, e.g. "canny", "depth" or "openpose"
+    #[serde(rename = "type")]
+    pub control_type: String,
+    
+    /// Control image, base64 encoded
+    pub image: String,
+    
+    /// Control model to apply, for backends that need it named
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    
+    /// Strength of the control
+    #[serde(default = "default_control_weight")]
+    pub weight: f32,
+    
+    /// Fraction of the steps at which the control starts applying
+    #[serde(default)]
+    pub start: f32,
+    
+    /// Fraction of the steps at which the control stops applying
+    #[serde(default = "default_control_end")]
+    pub end: f32,
+}
+
+fn default_control_weight() -> f32 {
+    1.0
+}
+
+fn default_control_end() -> f32 {
+    1.0
+}
 
 /// Request to generate images
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GenerateRequest {
     /// The prompt to generate images from
     pub prompt: String,
@@ -35,8 +90,62 @@ pub struct GenerateRequest {
     /// Number of inference steps
     pub num_inference_steps: Option<u32>,
     
+    /// Image quality, e.g. "standard" or "hd" (OpenAI)
+    #[serde(default)]
+    pub quality: Option<String>,
+    
+    /// Image style, e.g. "vivid" or "natural" (OpenAI)
+    #[serde(default)]
+    pub style: Option<String>,
+    
     /// Response format: "b64_json", "url", or "file"
     pub response_format: String,
+    
+    /// Additional backend-specific parameters
+    pub extra_params: Option<serde_json::Value>,
+    
+    /// Source image to edit, base64 encoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    
+    /// Mask whose transparent pixels mark the area to edit, base64 encoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<String>,
+    
+    /// Generate variations of `image` instead of editing it; the prompt is unused
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub variation: bool,
+    
+    /// Image to start from (img2img), base64 encoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_image: Option<String>,
+    
+    /// How far generation departs from `init_image`, from 0.0 to 1.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denoising_strength: Option<f32>,
+    
+    /// Control inputs conditioning the generation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub controls: Vec<ControlInput>,
+}
+
+impl GenerateRequest {
+    /// Capabilities a backend needs to serve this request
+    pub fn required_capabilities(&self) -> Vec<&'static str> {
+        let mut capabilities = Vec::new();
+        if self.variation {
+            capabilities.push(CAPABILITY_VARIATIONS);
+        } else if self.image.is_some() {
+            capabilities.push(CAPABILITY_EDIT);
+        }
+        if self.init_image.is_some() {
+            capabilities.push(CAPABILITY_IMG2IMG);
+        }
+        if !self.controls.is_empty() {
+            capabilities.push(CAPABILITY_CONTROLNET);
+        }
+        capabilities
+    }
 }
 
 /// Generated image data
@@ -63,6 +172,60 @@ pub struct GenerateResponse {
     
     /// Model used for generation
     pub model: Option<String>,
+    
+    /// Backend that served the request, filled in by the request queue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+}
+
+/// Progress of a running generation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GenerationProgress {
+    /// Sampling steps completed
+    pub step: u32,
+    
+    /// Sampling steps in total
+    pub total_steps: u32,
+    
+    /// Estimated seconds remaining
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eta_secs: Option<f32>,
+    
+    /// Base64 preview of the image in progress
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+/// Receives progress updates from a backend while it generates
+///
+/// The default reporter discards updates, so backends can skip progress
+/// polling when [`ProgressReporter::is_enabled`] is false.
+#[derive(Clone, Default)]
+pub struct ProgressReporter(Option<Arc<dyn Fn(GenerationProgress) + Send + Sync>>);
+
+impl ProgressReporter {
+    /// Create a reporter passing updates to `report`
+    pub fn new(report: impl Fn(GenerationProgress) + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(report)))
+    }
+
+    /// Whether anyone listens for updates
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Report an update
+    pub fn report(&self, progress: GenerationProgress) {
+        if let Some(report) = &self.0 {
+            report(progress);
+        }
+    }
+}
+
+impl std::fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ProgressReporter").field(&self.is_enabled()).finish()
+    }
 }
 
 /// Backend status information
@@ -74,6 +237,8 @@ pub struct BackendStatus {
     pub healthy: bool,
     pub weight: u32,
     pub enabled: bool,
+    /// Circuit breaker state per endpoint
+    pub circuits: Vec<EndpointCircuit>,
 }
 
 /// Trait for image generation backends
@@ -91,6 +256,19 @@ pub trait ImageBackend: Send + Sync {
     /// Generate images from a request
     async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse>;
     
+    /// Generate images, reporting progress while they are produced
+    ///
+    /// Backends that cannot observe progress keep the default, which
+    /// generates without reporting.
+    async fn generate_with_progress(
+        &self,
+        request: GenerateRequest,
+        progress: ProgressReporter,
+    ) -> Result<GenerateResponse> {
+        let _ = progress;
+        self.generate(request).await
+    }
+    
     /// Check if the backend is healthy
     async fn health_check(&self) -> bool;
     
@@ -100,6 +278,39 @@ pub trait ImageBackend: Send + Sync {
     /// Check if the backend is enabled
     fn is_enabled(&self) -> bool;
     
+    /// Enable or disable the backend; disabled backends stay registered but
+    /// receive no traffic
+    fn set_enabled(&self, _enabled: bool) {}
+    
+    /// Get the strategy used to pick between this backend's endpoints
+    fn endpoint_strategy(&self) -> LoadBalancingStrategy {
+        LoadBalancingStrategy::RoundRobin
+    }
+    
+    /// Change the strategy used to pick between this backend's endpoints
+    fn set_endpoint_strategy(&self, _strategy: LoadBalancingStrategy) {}
+    
+    /// Get how often the backend is health checked and how long a check may take
+    fn health_check_schedule(&self) -> HealthCheckSchedule {
+        HealthCheckSchedule::default()
+    }
+    
+    /// Get the circuit breaker state of each endpoint
+    fn circuits(&self) -> Vec<EndpointCircuit> {
+        Vec::new()
+    }
+    
+    /// Get the request types the backend supports beyond text-to-image,
+    /// such as "edit", "variations", "img2img" or "controlnet"
+    fn capabilities(&self) -> Vec<String> {
+        Vec::new()
+    }
+    
+    /// Check whether the backend supports a capability
+    fn supports(&self, capability: &str) -> bool {
+        self.capabilities().iter().any(|c| c == capability)
+    }
+    
     /// Get current status
     fn status(&self) -> BackendStatus {
         BackendStatus {
@@ -109,41 +320,67 @@ pub trait ImageBackend: Send + Sync {
             healthy: true, // Will be updated by health check
             weight: self.weight(),
             enabled: self.is_enabled(),
+            circuits: self.circuits(),
         }
     }
 }
 
-/// Backend endpoint with health status
+/// Backend endpoint with its circuit breaker and load stats
 #[derive(Debug, Clone)]
 pub struct BackendEndpoint {
     pub url: String,
-    pub healthy: bool,
     pub last_check: Option<std::time::Instant>,
-    pub consecutive_failures: u32,
+    /// Circuit breaker deciding whether the endpoint takes traffic, shared
+    /// between clones of the endpoint
+    pub breaker: Arc<CircuitBreaker>,
+    /// In-flight requests and latency, shared between clones of the endpoint
+    pub load: Arc<LoadStats>,
 }
 
 impl BackendEndpoint {
     pub fn new(url: String) -> Self {
+        Self::with_circuit_breaker(url, &CircuitBreakerConfig::default())
+    }
+    
+    /// Create an endpoint with a configured circuit breaker
+    pub fn with_circuit_breaker(url: String, config: &CircuitBreakerConfig) -> Self {
         Self {
+            breaker: Arc::new(CircuitBreaker::new(url.clone(), config)),
             url,
-            healthy: true, // Assume healthy until proven otherwise
             last_check: None,
-            consecutive_failures: 0,
+            load: Arc::new(LoadStats::new()),
         }
     }
     
-    pub fn mark_healthy(&mut self) {
-        self.healthy = true;
+    /// Whether the circuit breaker would let a request through
+    pub fn is_available(&self) -> bool {
+        self.breaker.allows_request()
+    }
+    
+    /// Record a success; `latency` is checked against the slow-call threshold
+    pub fn mark_healthy(&mut self, latency: Option<Duration>) {
+        self.breaker.record_success(latency);
         self.last_check = Some(std::time::Instant::now());
-        self.consecutive_failures = 0;
     }
     
+    /// Record a failure
     pub fn mark_unhealthy(&mut self) {
-        self.consecutive_failures += 1;
-        if self.consecutive_failures >= 3 {
-            self.healthy = false;
-        }
+        self.breaker.record_failure();
         self.last_check = Some(std::time::Instant::now());
     }
+    
+    /// Circuit breaker state for status reporting
+    pub fn circuit(&self) -> EndpointCircuit {
+        EndpointCircuit {
+            url: self.url.clone(),
+            circuit: self.breaker.snapshot(),
+        }
+    }
 }
 
+/// Circuit breaker state of one endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointCircuit {
+    pub url: String,
+    pub circuit: CircuitSnapshot,
+}
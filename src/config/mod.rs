@@ -1,5 +1,6 @@
 //! Configuration module for the image serving framework
 
+pub mod reload;
 mod settings;
 
 pub use settings::*;
@@ -0,0 +1,360 @@
+//! Hot reload of gateway.yaml and backends.yaml
+//!
+//! [`ConfigReloader`] re-reads both files when either changes on disk or the
+//! process receives SIGHUP. New settings are validated before anything is
+//! applied, so a broken file leaves the running configuration untouched.
+//! Backends are diffed by name: unchanged backends keep their connections,
+//! circuit breakers and in-flight requests.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::backend::multi_backend::register_multi_backend;
+use crate::config::{BackendConfig, BackendType, Settings};
+use crate::error::Result;
+use crate::gateway::router::RoutingRules;
+use crate::AppState;
+
+/// How often the watched files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Backend changes between two configurations, matched by name
+#[derive(Debug, Default)]
+pub struct BackendChanges {
+    pub added: Vec<BackendConfig>,
+    pub updated: Vec<BackendConfig>,
+    pub removed: Vec<String>,
+}
+
+impl BackendChanges {
+    /// Diff two backend lists
+    pub fn diff<'a>(
+        old: impl IntoIterator<Item = &'a BackendConfig>,
+        new: impl IntoIterator<Item = &'a BackendConfig>,
+    ) -> Self {
+        let old: Vec<&BackendConfig> = old.into_iter().collect();
+        let new: Vec<&BackendConfig> = new.into_iter().collect();
+        let mut changes = Self::default();
+
+        for config in &new {
+            match old.iter().find(|o| o.name == config.name) {
+                None => changes.added.push((*config).clone()),
+                Some(previous) if previous != config => changes.updated.push((*config).clone()),
+                Some(_) => {}
+            }
+        }
+        changes.removed = old
+            .iter()
+            .filter(|o| !new.iter().any(|n| n.name == o.name))
+            .map(|o| o.name.clone())
+            .collect();
+
+        changes
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Reloads configuration files into the running gateway
+pub struct ConfigReloader {
+    state: Arc<AppState>,
+    gateway_path: PathBuf,
+    backends_path: PathBuf,
+}
+
+impl ConfigReloader {
+    /// Create a reloader for the given configuration files
+    pub fn new(state: Arc<AppState>, gateway_path: impl Into<PathBuf>, backends_path: impl Into<PathBuf>) -> Self {
+        Self {
+            state,
+            gateway_path: gateway_path.into(),
+            backends_path: backends_path.into(),
+        }
+    }
+
+    /// Re-read both files and apply the differences
+    ///
+    /// Backends are added, replaced or removed in the image and text
+    /// registries, multi backends in both; routing, auth keys and rate limits are swapped in place.
+    /// Server address changes need a restart: the running address is kept and
+    /// the change is only logged.
+    pub async fn reload(&self) -> Result<()> {
+        let mut settings = Settings::load_from_paths(&self.gateway_path, Some(&self.backends_path))?;
+        settings.validate()?;
+        if let Ok(key) = std::env::var("GEN_GATEWAY_API_KEY") {
+            if !key.is_empty() {
+                settings.merge_api_key(key);
+            }
+        }
+        let strategy = settings.routing.strategy()?;
+
+        // Holding the write lock serialises reloads
+        let mut current = self.state.settings.write().await;
+
+        // The listener is not rebound, so keep reporting the address in use
+        if settings.server != current.server {
+            warn!(
+                host = %settings.server.host,
+                port = settings.server.port,
+                "Server address changes take effect after a restart"
+            );
+            settings.server = current.server.clone();
+        }
+
+        let diff = |backend_type: BackendType| {
+            BackendChanges::diff(
+                current.backends.iter().filter(|b| b.backend_type == backend_type),
+                settings.backends.iter().filter(|b| b.backend_type == backend_type),
+            )
+        };
+        let image = diff(BackendType::Image);
+        let text = diff(BackendType::Text);
+        let multi = diff(BackendType::Multi);
+
+        // Remove first, so a backend that changed type is not removed again
+        // after being registered under its new type
+        self.remove_backends(&image.removed, true, false).await;
+        self.remove_backends(&text.removed, false, true).await;
+        self.remove_backends(&multi.removed, true, true).await;
+        self.register_backends(&image, &text, &multi).await;
+
+        let load_balancer = &self.state.load_balancer;
+        load_balancer.set_strategy(strategy);
+        load_balancer.set_routing(RoutingRules::from_config(&settings.routing));
+        load_balancer.set_fail_open(settings.routing.fail_open);
+        self.state.text_registry.set_routing(RoutingRules::from_config(&settings.routing));
+
+        self.state
+            .request_queue
+            .set_job_retention(Duration::from_secs(settings.queue.job_retention_secs));
+        self.state
+            .request_queue
+            .set_job_timeout(Duration::from_secs(settings.queue.job_timeout_secs));
+
+        self.state.auth.update(&settings.auth);
+        // Rebuilding the limiter refills its burst, so only do it on change
+        if settings.rate_limit != current.rate_limit {
+            self.state.rate_limit.update(&settings.rate_limit);
+        }
+
+        info!(
+            image_added = image.added.len(),
+            image_updated = image.updated.len(),
+            image_removed = image.removed.len(),
+            text_added = text.added.len(),
+            text_updated = text.updated.len(),
+            text_removed = text.removed.len(),
+            multi_added = multi.added.len(),
+            multi_updated = multi.updated.len(),
+            multi_removed = multi.removed.len(),
+            api_keys = settings.auth.api_keys.len(),
+            "Configuration reloaded"
+        );
+
+        *current = settings;
+        Ok(())
+    }
+
+    async fn remove_backends(&self, names: &[String], image: bool, text: bool) {
+        for name in names {
+            if image {
+                if let Err(e) = self.state.backend_registry.remove_backend(name).await {
+                    warn!(backend = %name, error = %e, "Failed to remove image backend");
+                }
+            }
+            if text {
+                if let Err(e) = self.state.text_registry.remove_backend(name).await {
+                    warn!(backend = %name, error = %e, "Failed to remove text backend");
+                }
+            }
+            self.state.health_manager.forget(name);
+        }
+    }
+
+    async fn register_backends(&self, image: &BackendChanges, text: &BackendChanges, multi: &BackendChanges) {
+        for config in image.added.iter().chain(&image.updated) {
+            if let Err(e) = self.state.backend_registry.replace_backend(config.clone()).await {
+                warn!(backend = %config.name, error = %e, "Failed to register image backend");
+            }
+        }
+        for config in text.added.iter().chain(&text.updated) {
+            if let Err(e) = self.state.text_registry.replace_backend(config.clone()).await {
+                warn!(backend = %config.name, error = %e, "Failed to register text backend");
+            }
+        }
+        for config in multi.added.iter().chain(&multi.updated) {
+            let registered =
+                register_multi_backend(config, &self.state.backend_registry, &self.state.text_registry);
+            if let Err(e) = registered {
+                warn!(backend = %config.name, error = %e, "Failed to register multi backend");
+            }
+        }
+    }
+
+    /// Reload whenever a watched file changes or SIGHUP is received
+    ///
+    /// Files are polled for modification time changes, which also catches
+    /// editors that replace the file rather than writing it in place.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut modified = self.modified_times();
+            let mut poll = tokio::time::interval(POLL_INTERVAL);
+            let mut hangup = hangup_signal();
+
+            loop {
+                let trigger = tokio::select! {
+                    _ = poll.tick() => {
+                        let latest = self.modified_times();
+                        if latest == modified {
+                            continue;
+                        }
+                        modified = latest;
+                        "file change"
+                    }
+                    _ = next_hangup(&mut hangup) => "SIGHUP",
+                };
+
+                info!(trigger = trigger, "Reloading configuration");
+                if let Err(e) = self.reload().await {
+                    error!(error = %e, "Configuration reload failed, keeping the running configuration");
+                }
+            }
+        })
+    }
+
+    fn modified_times(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+        let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        (modified(&self.gateway_path), modified(&self.backends_path))
+    }
+}
+
+#[cfg(unix)]
+type Hangup = tokio::signal::unix::Signal;
+
+#[cfg(not(unix))]
+type Hangup = ();
+
+#[cfg(unix)]
+fn hangup_signal() -> Option<Hangup> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    signal(SignalKind::hangup())
+        .map_err(|e| warn!(error = %e, "Failed to listen for SIGHUP"))
+        .ok()
+}
+
+#[cfg(not(unix))]
+fn hangup_signal() -> Option<Hangup> {
+    None
+}
+
+async fn next_hangup(hangup: &mut Option<Hangup>) {
+    #[cfg(unix)]
+    if let Some(signal) = hangup {
+        if signal.recv().await.is_some() {
+            return;
+        }
+        // The signal stream is gone; rely on file changes from now on
+        *hangup = None;
+    }
+    #[cfg(not(unix))]
+    let _ = hangup;
+    std::future::pending::<()>().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{registry::BackendRegistry, TextBackendRegistry};
+    use crate::gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer};
+    use crate::metrics::Metrics;
+    use crate::middleware::{auth::AuthLayer, rate_limit::RateLimitLayer};
+    use crate::queue::request_queue::RequestQueue;
+
+    fn backend(name: &str, endpoint: &str) -> BackendConfig {
+        BackendConfig {
+            name: name.to_string(),
+            endpoints: vec![endpoint.to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_backends() {
+        let old = vec![
+            backend("kept", "http://a:1"),
+            backend("changed", "http://b:1"),
+            backend("gone", "http://c:1"),
+        ];
+        let new = vec![
+            backend("kept", "http://a:1"),
+            backend("changed", "http://b:2"),
+            backend("fresh", "http://d:1"),
+        ];
+
+        let changes = BackendChanges::diff(&old, &new);
+        assert_eq!(changes.added.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(), vec!["fresh"]);
+        assert_eq!(changes.updated.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(), vec!["changed"]);
+        assert_eq!(changes.removed, vec!["gone"]);
+        assert!(BackendChanges::diff(&old, &old).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reload_applies_backends_and_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let gateway_path = dir.path().join("gateway.yaml");
+        let backends_path = dir.path().join("backends.yaml");
+        std::fs::write(
+            &gateway_path,
+            "server:\n  port: 19999\nauth:\n  enabled: true\n  api_keys: [\"new-key\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &backends_path,
+            "backends:\n  image:\n    - name: sd-new\n      type: image\n      endpoints: [\"http://localhost:7860\"]\n",
+        )
+        .unwrap();
+
+        let settings = Settings {
+            backends: vec![backend("sd-old", "http://localhost:8001")],
+            ..Default::default()
+        };
+        let backend_registry = Arc::new(BackendRegistry::new());
+        backend_registry.add_backend(settings.backends[0].clone()).await.unwrap();
+        let load_balancer = Arc::new(LoadBalancer::new(backend_registry.clone()));
+        let state = Arc::new(AppState {
+            settings: Arc::new(tokio::sync::RwLock::new(settings)),
+            backend_registry: backend_registry.clone(),
+            text_registry: Arc::new(TextBackendRegistry::new()),
+            load_balancer: load_balancer.clone(),
+            health_manager: Arc::new(HealthCheckManager::new(backend_registry.clone())),
+            request_queue: Arc::new(RequestQueue::new(load_balancer)),
+            metrics: Arc::new(Metrics::new()),
+            auth: AuthLayer::new(vec!["old-key".to_string()]),
+            rate_limit: RateLimitLayer::new(100, 200),
+        });
+
+        let reloader = ConfigReloader::new(state.clone(), &gateway_path, &backends_path);
+        reloader.reload().await.unwrap();
+
+        assert!(backend_registry.get("sd-old").is_none());
+        assert!(backend_registry.get("sd-new").is_some());
+        let api_keys = state.settings.read().await.auth.api_keys.clone();
+        assert!(api_keys.contains(&"new-key".to_string()));
+        assert!(!api_keys.contains(&"old-key".to_string()));
+        // The listener is not rebound, so the running address is kept
+        assert_ne!(state.settings.read().await.server.port, 19999);
+
+        // An invalid file leaves the running configuration in place
+        std::fs::write(&backends_path, "backends:\n  image:\n    - name: broken\n      type: image\n      endpoints: []\n").unwrap();
+        assert!(reloader.reload().await.is_err());
+        assert!(backend_registry.get("sd-new").is_some());
+        assert_eq!(state.settings.read().await.backends[0].name, "sd-new");
+    }
+}
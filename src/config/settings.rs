@@ -1,10 +1,20 @@
 //! Application settings and configuration management
 
+use crate::backend::http_backend::ApiStyle;
 use crate::error::{AppError, Result};
+use crate::gateway::load_balancer::LoadBalancingStrategy;
 use config::{Config, Environment, File, FileFormat};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// Default path of the gateway configuration file
+pub const GATEWAY_CONFIG_PATH: &str = "config/gateway.yaml";
+
+/// Default path of the backends configuration file
+pub const BACKENDS_CONFIG_PATH: &str = "config/backends.yaml";
 
 /// Root configuration structure
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -16,10 +26,23 @@ pub struct Settings {
     pub logging: LoggingConfig,
     #[serde(default)]
     pub backends: Vec<BackendConfig>,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// Connection defaults from backends.yaml, applied to backends added at runtime
+    #[serde(default)]
+    pub connection: ConnectionDefaults,
+    #[serde(default)]
+    pub management: ManagementConfig,
+    #[serde(default)]
+    pub queue: QueueSettings,
+    /// Backends file the settings were loaded from, where management API
+    /// changes are persisted
+    #[serde(skip)]
+    pub backends_path: Option<PathBuf>,
 }
 
 /// Server configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct ServerConfig {
     #[serde(default = "default_host")]
     pub host: String,
@@ -32,7 +55,7 @@ fn default_host() -> String {
 }
 
 fn default_port() -> u16 {
-    8080
+    15115
 }
 
 /// Authentication configuration
@@ -50,8 +73,45 @@ fn default_true() -> bool {
     true
 }
 
+/// Management API configuration
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ManagementConfig {
+    /// Write backends added, updated or removed through the management API
+    /// back to backends.yaml
+    #[serde(default)]
+    pub persist_changes: bool,
+}
+
+/// Request queue configuration
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct QueueSettings {
+    /// How long finished image jobs are kept for polling, in seconds
+    #[serde(default = "default_job_retention_secs")]
+    pub job_retention_secs: u64,
+    /// How long an image job may take before failing with a timeout, in seconds
+    #[serde(default = "default_job_timeout_secs")]
+    pub job_timeout_secs: u64,
+}
+
+fn default_job_retention_secs() -> u64 {
+    3600
+}
+
+fn default_job_timeout_secs() -> u64 {
+    3600
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        Self {
+            job_retention_secs: default_job_retention_secs(),
+            job_timeout_secs: default_job_timeout_secs(),
+        }
+    }
+}
+
 /// Rate limiting configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct RateLimitConfig {
     #[serde(default = "default_true")]
     pub enabled: bool,
@@ -104,35 +164,30 @@ fn default_log_format() -> String {
 }
 
 /// Backend type enum
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum BackendType {
+    #[default]
     Image,
     Text,
     Multi, // For backends that support both
 }
 
-impl Default for BackendType {
-    fn default() -> Self {
-        BackendType::Image
-    }
-}
-
 /// Protocol type enum
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum ProtocolType {
+    #[default]
     Http,
     Grpc,
     OpenAI,
     Anthropic,
     Tgi, // Text Generation Inference
-}
-
-impl Default for ProtocolType {
-    fn default() -> Self {
-        ProtocolType::Http
-    }
+    /// Automatic1111 / SD WebUI native API
+    #[serde(alias = "a1111")]
+    SdWebUi,
+    /// ComfyUI workflow API
+    ComfyUi,
 }
 
 impl std::fmt::Display for ProtocolType {
@@ -143,12 +198,14 @@ impl std::fmt::Display for ProtocolType {
             ProtocolType::OpenAI => write!(f, "openai"),
             ProtocolType::Anthropic => write!(f, "anthropic"),
             ProtocolType::Tgi => write!(f, "tgi"),
+            ProtocolType::SdWebUi => write!(f, "sdwebui"),
+            ProtocolType::ComfyUi => write!(f, "comfyui"),
         }
     }
 }
 
 /// Authentication type for backend
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub struct BackendAuth {
     #[serde(rename = "type", default = "default_auth_type")]
     pub auth_type: String,
@@ -165,14 +222,26 @@ fn default_auth_type() -> String {
 }
 
 /// Health check configuration for backend
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct BackendHealthCheck {
     #[serde(default = "default_health_check_path")]
     pub path: String,
-    #[serde(default = "default_health_check_interval")]
-    pub interval_secs: u64,
-    #[serde(default = "default_health_timeout")]
-    pub timeout_secs: u64,
+    /// Seconds between checks; defaults to `defaults.health_check.interval_secs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_secs: Option<u64>,
+    /// Seconds before a check counts as failed; defaults to `defaults.health_check.timeout_secs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl Default for BackendHealthCheck {
+    fn default() -> Self {
+        Self {
+            path: default_health_check_path(),
+            interval_secs: None,
+            timeout_secs: None,
+        }
+    }
 }
 
 fn default_health_timeout() -> u64 {
@@ -180,20 +249,108 @@ fn default_health_timeout() -> u64 {
 }
 
 /// Load balancer configuration for backend
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct BackendLoadBalancer {
+    /// Strategy for picking between this backend's endpoints
     #[serde(default = "default_lb_strategy")]
     pub strategy: String,
     #[serde(default = "default_weight")]
     pub weight: u32,
 }
 
+impl Default for BackendLoadBalancer {
+    fn default() -> Self {
+        Self {
+            strategy: default_lb_strategy(),
+            weight: default_weight(),
+        }
+    }
+}
+
 fn default_lb_strategy() -> String {
     "round_robin".to_string()
 }
 
+/// Circuit breaker configuration for each of a backend's endpoints
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Failure rate (0.0-1.0) over the window that opens the circuit
+    #[serde(default = "default_failure_rate_threshold")]
+    pub failure_rate_threshold: f64,
+    /// Successful calls slower than this count as failures; unset disables the check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slow_call_ms: Option<u64>,
+    /// Calls needed in the window before the failure rate is evaluated
+    #[serde(default = "default_minimum_requests")]
+    pub minimum_requests: u32,
+    /// Rolling window for the failure rate
+    #[serde(default = "default_breaker_window")]
+    pub window_secs: u64,
+    /// Cool-down before an open circuit lets probe requests through
+    #[serde(default = "default_breaker_open")]
+    pub open_secs: u64,
+    /// Concurrent probes allowed while half-open; this many successes close the circuit
+    #[serde(default = "default_half_open_probes")]
+    pub half_open_probes: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate_threshold: default_failure_rate_threshold(),
+            slow_call_ms: None,
+            minimum_requests: default_minimum_requests(),
+            window_secs: default_breaker_window(),
+            open_secs: default_breaker_open(),
+            half_open_probes: default_half_open_probes(),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    fn validate(&self, backend: &str) -> Result<()> {
+        if !(self.failure_rate_threshold > 0.0 && self.failure_rate_threshold <= 1.0) {
+            return Err(config_error(format!(
+                "Backend '{}': circuit_breaker.failure_rate_threshold must be in (0, 1]",
+                backend
+            )));
+        }
+        if self.minimum_requests == 0 || self.half_open_probes == 0 {
+            return Err(config_error(format!(
+                "Backend '{}': circuit_breaker.minimum_requests and half_open_probes must be at least 1",
+                backend
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn default_failure_rate_threshold() -> f64 {
+    0.5
+}
+
+fn default_minimum_requests() -> u32 {
+    3
+}
+
+fn default_breaker_window() -> u64 {
+    60
+}
+
+fn default_breaker_open() -> u64 {
+    30
+}
+
+fn default_half_open_probes() -> u32 {
+    1
+}
+
+fn config_error(message: String) -> AppError {
+    AppError::Config(config::ConfigError::Message(message))
+}
+
 /// Backend configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct BackendConfig {
     pub name: String,
     
@@ -217,12 +374,35 @@ pub struct BackendConfig {
     #[serde(default)]
     pub load_balancer: BackendLoadBalancer,
     
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    
     #[serde(default)]
     pub models: Vec<String>,
     
     #[serde(default)]
     pub capabilities: Vec<String>,
     
+    /// Retries for transient failures (connect errors, 429/502/503);
+    /// defaults to `defaults.connection.retry_count`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_count: Option<u32>,
+    
+    /// Prompt template for backends that take raw text (e.g. TGI):
+    /// `chatml`, `llama3`, `mistral`, or a custom `{role}`/`{content}` format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_template: Option<String>,
+    
+    /// Workflow template for ComfyUI backends: path to a workflow exported in
+    /// API format, with `{{prompt}}`-style placeholders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<String>,
+    
+    /// Request API of a generic HTTP image backend: `openai`, `generate`,
+    /// `api_generate` or `a1111`; learned per endpoint when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_style: Option<String>,
+    
     // Legacy fields for backward compatibility
     #[serde(default = "default_health_check_path")]
     pub health_check_path: String,
@@ -237,6 +417,63 @@ pub struct BackendConfig {
     pub weight: u32,
 }
 
+impl BackendConfig {
+    /// Parse the strategy used to pick between this backend's endpoints
+    pub fn endpoint_strategy(&self) -> Result<LoadBalancingStrategy> {
+        self.load_balancer.strategy.parse::<LoadBalancingStrategy>()
+            .map_err(|e| config_error(format!("Backend '{}': {}", self.name, e)))
+    }
+    
+    /// Parse the pinned request API style, if any
+    pub fn api_style(&self) -> Result<Option<ApiStyle>> {
+        self.api_style
+            .as_deref()
+            .map(|style| style.parse::<ApiStyle>())
+            .transpose()
+            .map_err(|e| config_error(format!("Backend '{}': {}", self.name, e)))
+    }
+    
+    /// Interval between background health checks, falling back to the
+    /// legacy `health_check_interval_secs`
+    pub fn health_check_interval(&self) -> Duration {
+        Duration::from_secs(self.health_check.interval_secs.unwrap_or(self.health_check_interval_secs))
+    }
+    
+    /// Time a health check may take before it counts as failed
+    pub fn health_check_timeout(&self) -> Duration {
+        Duration::from_secs(self.health_check.timeout_secs.unwrap_or_else(default_health_timeout))
+    }
+    
+    /// Validate a single backend
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(config_error("Backend name cannot be empty".to_string()));
+        }
+        if self.endpoints.is_empty() {
+            return Err(config_error(format!(
+                "Backend '{}' must have at least one endpoint",
+                self.name
+            )));
+        }
+        self.endpoint_strategy()?;
+        self.api_style()?;
+        self.circuit_breaker.validate(&self.name)?;
+        if self.health_check_interval().is_zero() || self.health_check_timeout().is_zero() {
+            return Err(config_error(format!(
+                "Backend '{}': health check interval and timeout must be at least 1 second",
+                self.name
+            )));
+        }
+        if self.protocol == ProtocolType::ComfyUi && self.workflow.is_none() {
+            return Err(config_error(format!(
+                "Backend '{}': comfyui backends need a workflow template",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
 fn default_health_check_path() -> String {
     "/health".to_string()
 }
@@ -278,7 +515,7 @@ pub struct BackendsDefaults {
     pub connection: ConnectionDefaults,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ConnectionDefaults {
     #[serde(default = "default_timeout")]
     pub timeout_ms: u64,
@@ -287,6 +524,15 @@ pub struct ConnectionDefaults {
     pub retry_count: u32,
 }
 
+impl Default for ConnectionDefaults {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_timeout(),
+            retry_count: default_retry_count(),
+        }
+    }
+}
+
 fn default_retry_count() -> u32 {
     3
 }
@@ -301,9 +547,50 @@ pub struct BackendGroups {
     
     #[serde(default)]
     pub grpc: Vec<BackendConfig>,
+    
+    /// Backends serving both image and text generation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub multi: Vec<BackendConfig>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+impl BackendGroups {
+    /// Insert a backend, replacing any backend with the same name in place
+    ///
+    /// New backends go to the group matching their type. Entries in the
+    /// `image`, `text` and `multi` groups take the group's type when loaded;
+    /// gRPC image backends go to the `grpc` group, which keeps their own type.
+    pub fn upsert(&mut self, backend: BackendConfig) {
+        if let Some(existing) = self.group_for(&backend).iter_mut().find(|b| b.name == backend.name) {
+            *existing = backend;
+            return;
+        }
+        // A backend whose type or protocol changed moves to its new group
+        self.remove(&backend.name);
+        self.group_for(&backend).push(backend);
+    }
+    
+    /// Remove a backend from whichever group holds it
+    pub fn remove(&mut self, name: &str) -> bool {
+        let mut removed = false;
+        for group in [&mut self.image, &mut self.text, &mut self.grpc, &mut self.multi] {
+            let before = group.len();
+            group.retain(|b| b.name != name);
+            removed |= group.len() != before;
+        }
+        removed
+    }
+    
+    fn group_for(&mut self, backend: &BackendConfig) -> &mut Vec<BackendConfig> {
+        match (&backend.backend_type, &backend.protocol) {
+            (BackendType::Text, _) => &mut self.text,
+            (BackendType::Multi, _) => &mut self.multi,
+            (BackendType::Image, ProtocolType::Grpc) => &mut self.grpc,
+            (BackendType::Image, _) => &mut self.image,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RoutingConfig {
     #[serde(default = "default_lb_strategy")]
     pub default_strategy: String,
@@ -313,12 +600,50 @@ pub struct RoutingConfig {
     
     #[serde(default)]
     pub fallbacks: HashMap<String, Vec<String>>,
+    
+    /// Keep routing to unhealthy backends when none are healthy,
+    /// instead of rejecting requests
+    #[serde(default)]
+    pub fail_open: bool,
+}
+
+impl RoutingConfig {
+    /// Parse the strategy used to pick between backends
+    pub fn strategy(&self) -> Result<LoadBalancingStrategy> {
+        self.default_strategy.parse::<LoadBalancingStrategy>()
+            .map_err(|e| config_error(format!("routing.default_strategy: {}", e)))
+    }
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            default_strategy: default_lb_strategy(),
+            model_mappings: HashMap::new(),
+            fallbacks: HashMap::new(),
+            fail_open: false,
+        }
+    }
 }
 
 impl Settings {
     /// Load settings from configuration files and environment variables
     pub fn load() -> Result<Self> {
-        Self::load_from_paths("config/gateway.yaml", Some("config/backends.yaml"))
+        Self::load_from_paths(GATEWAY_CONFIG_PATH, Some(BACKENDS_CONFIG_PATH))
+    }
+    
+    /// Add an API key loaded from the environment or `.env`
+    ///
+    /// The key becomes the only key when auth is enabled without configured
+    /// keys, and is added alongside configured keys otherwise.
+    pub fn merge_api_key(&mut self, key: String) {
+        if self.auth.api_keys.is_empty() {
+            if self.auth.enabled {
+                self.auth.api_keys.push(key);
+            }
+        } else if !self.auth.api_keys.contains(&key) {
+            self.auth.api_keys.push(key);
+        }
     }
 
     /// Load settings from a specific configuration file path (TOML - legacy)
@@ -352,7 +677,7 @@ impl Settings {
         let gateway_path = gateway_config.as_ref();
         
         // Determine file format
-        let format = if gateway_path.extension().map_or(false, |ext| ext == "yaml" || ext == "yml") {
+        let format = if gateway_path.extension().is_some_and(|ext| ext == "yaml" || ext == "yml") {
             FileFormat::Yaml
         } else {
             FileFormat::Toml
@@ -391,8 +716,11 @@ impl Settings {
         // Load backends from separate file if provided
         if let Some(backends_path) = backends_config {
             let backends_path = backends_path.as_ref();
+            settings.backends_path = Some(backends_path.to_path_buf());
             if backends_path.exists() {
                 let backends_config = Self::load_backends_config(backends_path)?;
+                settings.routing = backends_config.routing.clone();
+                settings.connection = backends_config.defaults.connection.clone();
                 settings.backends = Self::flatten_backends(backends_config);
             }
         }
@@ -416,16 +744,82 @@ impl Settings {
     }
     
     /// Save backends configuration to YAML file
+    ///
+    /// The file is written next to its destination and renamed into place, so
+    /// readers never see a partial file.
     pub fn save_backends_config<P: AsRef<Path>>(path: P, config: &BackendsConfig) -> Result<()> {
+        let path = path.as_ref();
         let content = serde_yaml::to_string(config)
             .map_err(|e| AppError::Config(config::ConfigError::Message(
                 format!("Failed to serialize backends config: {}", e)
             )))?;
         
-        std::fs::write(path.as_ref(), content)
-            .map_err(|e| AppError::Config(config::ConfigError::Message(
-                format!("Failed to write backends config: {}", e)
-            )))?;
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(".tmp");
+        let temp_path = path.with_file_name(temp_name);
+        std::fs::write(&temp_path, content)
+            .and_then(|_| std::fs::rename(&temp_path, path))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&temp_path);
+                AppError::Config(config::ConfigError::Message(
+                    format!("Failed to write backends config: {}", e)
+                ))
+            })?;
+        
+        Ok(())
+    }
+    
+    /// Whether management API changes are written to the backends file
+    pub fn persists_backends(&self) -> bool {
+        self.management.persist_changes && self.backends_path.is_some()
+    }
+    
+    /// Write a backend to the backends file, replacing any backend with the
+    /// same name; does nothing unless persistence is enabled
+    ///
+    /// The backend is also recorded in these settings, so reloading the
+    /// rewritten file does not count it as a change.
+    pub fn persist_backend(&mut self, backend: &BackendConfig) -> Result<()> {
+        self.update_backends_file(&backend.name, |groups| groups.upsert(backend.clone()))
+    }
+    
+    /// Remove a backend from the backends file; does nothing unless
+    /// persistence is enabled
+    pub fn persist_backend_removal(&mut self, name: &str) -> Result<()> {
+        self.update_backends_file(name, |groups| {
+            groups.remove(name);
+        })
+    }
+    
+    fn update_backends_file(&mut self, name: &str, update: impl FnOnce(&mut BackendGroups)) -> Result<()> {
+        let path = match &self.backends_path {
+            Some(path) if self.management.persist_changes => path.clone(),
+            _ => return Ok(()),
+        };
+        
+        let mut config = if path.exists() {
+            Self::load_backends_config(&path)?
+        } else {
+            BackendsConfig {
+                version: "1.0".to_string(),
+                ..Default::default()
+            }
+        };
+        update(&mut config.backends);
+        Self::save_backends_config(&path, &config)?;
+        info!(backend = %name, path = %path.display(), "Persisted backend change");
+        
+        // Record the backend as it will be loaded, with file defaults applied
+        let persisted = Self::flatten_backends(config).into_iter().find(|b| b.name == name);
+        let existing = self.backends.iter().position(|b| b.name == name);
+        match (existing, persisted) {
+            (Some(index), Some(backend)) => self.backends[index] = backend,
+            (None, Some(backend)) => self.backends.push(backend),
+            (Some(index), None) => {
+                self.backends.remove(index);
+            }
+            (None, None) => {}
+        }
         
         Ok(())
     }
@@ -433,6 +827,8 @@ impl Settings {
     /// Flatten backend groups into a single list
     fn flatten_backends(config: BackendsConfig) -> Vec<BackendConfig> {
         let mut backends = Vec::new();
+        let retry_count = config.defaults.connection.retry_count;
+        let health_check = config.defaults.health_check;
         
         // Add image backends with type set
         for mut backend in config.backends.image {
@@ -451,6 +847,18 @@ impl Settings {
             backends.push(backend);
         }
         
+        // Add multi-modal backends with type set
+        for mut backend in config.backends.multi {
+            backend.backend_type = BackendType::Multi;
+            backends.push(backend);
+        }
+        
+        for backend in &mut backends {
+            backend.retry_count.get_or_insert(retry_count);
+            backend.health_check.interval_secs = backend.health_check.interval_secs.or(health_check.interval_secs);
+            backend.health_check.timeout_secs = backend.health_check.timeout_secs.or(health_check.timeout_secs);
+        }
+        
         backends
     }
 
@@ -465,18 +873,12 @@ impl Settings {
 
         // Validate backends
         for backend in &self.backends {
-            if backend.name.is_empty() {
-                return Err(AppError::Config(config::ConfigError::Message(
-                    "Backend name cannot be empty".to_string(),
-                )));
-            }
-            if backend.endpoints.is_empty() {
-                return Err(AppError::Config(config::ConfigError::Message(
-                    format!("Backend '{}' must have at least one endpoint", backend.name),
-                )));
-            }
+            backend.validate()?;
         }
 
+        // Validate routing config
+        self.routing.strategy()?;
+
         Ok(())
     }
     
@@ -523,6 +925,11 @@ impl Default for Settings {
                 format: default_log_format(),
             },
             backends: vec![],
+            routing: RoutingConfig::default(),
+            connection: ConnectionDefaults::default(),
+            management: ManagementConfig::default(),
+            queue: QueueSettings::default(),
+            backends_path: None,
         }
     }
 }
@@ -538,8 +945,13 @@ impl Default for BackendConfig {
             auth: BackendAuth::default(),
             health_check: BackendHealthCheck::default(),
             load_balancer: BackendLoadBalancer::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
             models: vec![],
             capabilities: vec![],
+            retry_count: None,
+            chat_template: None,
+            workflow: None,
+            api_style: None,
             health_check_path: default_health_check_path(),
             health_check_interval_secs: default_health_check_interval(),
             timeout_ms: default_timeout(),
@@ -572,4 +984,92 @@ mod tests {
         let yaml = serde_yaml::to_string(&backend).unwrap();
         assert!(yaml.contains("type: text"));
     }
+
+    #[test]
+    fn test_validate_strategies() {
+        let mut settings = Settings::default();
+        settings.backends.push(BackendConfig {
+            name: "sd".to_string(),
+            endpoints: vec!["http://localhost:8001".to_string()],
+            ..Default::default()
+        });
+        assert!(settings.validate().is_ok());
+
+        settings.backends[0].load_balancer.strategy = "fastest".to_string();
+        assert!(settings.validate().is_err());
+
+        settings.backends[0].load_balancer.strategy = "least_connections".to_string();
+        settings.routing.default_strategy = "fastest".to_string();
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_circuit_breaker() {
+        let mut settings = Settings::default();
+        settings.backends.push(BackendConfig {
+            name: "sd".to_string(),
+            endpoints: vec!["http://localhost:8001".to_string()],
+            ..Default::default()
+        });
+
+        settings.backends[0].circuit_breaker.failure_rate_threshold = 1.5;
+        assert!(settings.validate().is_err());
+
+        settings.backends[0].circuit_breaker.failure_rate_threshold = 0.5;
+        settings.backends[0].circuit_breaker.half_open_probes = 0;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_persist_backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backends.yaml");
+        std::fs::write(
+            &path,
+            "defaults:\n  connection:\n    retry_count: 5\nbackends:\n  text:\n    - name: ollama\n      endpoints: [\"http://localhost:11434/v1\"]\n",
+        )
+        .unwrap();
+
+        let mut settings = Settings {
+            backends_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let sd = BackendConfig {
+            name: "sd".to_string(),
+            endpoints: vec!["http://localhost:7860".to_string()],
+            ..Default::default()
+        };
+
+        // Nothing is written unless persistence is enabled
+        settings.persist_backend(&sd).unwrap();
+        assert!(Settings::load_backends_config(&path).unwrap().backends.image.is_empty());
+
+        settings.management.persist_changes = true;
+        settings.persist_backend(&sd).unwrap();
+        settings.persist_backend(&BackendConfig {
+            name: "sd-grpc".to_string(),
+            protocol: ProtocolType::Grpc,
+            ..sd.clone()
+        }).unwrap();
+
+        let config = Settings::load_backends_config(&path).unwrap();
+        assert_eq!(config.defaults.connection.retry_count, 5);
+        assert_eq!(config.backends.text[0].name, "ollama");
+        assert_eq!(config.backends.image[0].name, "sd");
+        assert_eq!(config.backends.grpc[0].name, "sd-grpc");
+        assert_eq!(settings.backends.len(), 2);
+
+        // Disabling replaces the entry in place
+        settings.persist_backend(&BackendConfig { enabled: false, ..sd.clone() }).unwrap();
+        let config = Settings::load_backends_config(&path).unwrap();
+        assert_eq!(config.backends.image.len(), 1);
+        assert!(!config.backends.image[0].enabled);
+        assert!(!settings.backends.iter().find(|b| b.name == "sd").unwrap().enabled);
+
+        settings.persist_backend_removal("sd").unwrap();
+        let config = Settings::load_backends_config(&path).unwrap();
+        assert!(config.backends.image.is_empty());
+        assert!(settings.backends.iter().all(|b| b.name != "sd"));
+        assert!(!dir.path().join("backends.yaml.tmp").exists());
+    }
 }
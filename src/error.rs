@@ -24,11 +24,14 @@ pub enum AppError {
     HttpClient(#[from] reqwest::Error),
 
     #[error("gRPC error: {0}")]
-    Grpc(#[from] tonic::Status),
+    Grpc(Box<tonic::Status>),
 
     #[error("Backend not found: {0}")]
     BackendNotFound(String),
 
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+
     #[error("No healthy backends available for: {0}")]
     NoHealthyBackends(String),
 
@@ -51,6 +54,30 @@ pub enum AppError {
     Internal(String),
 }
 
+impl From<tonic::Status> for AppError {
+    /// Map a gRPC status onto the closest gateway error so clients see a
+    /// meaningful HTTP status instead of a blanket 502
+    fn from(status: tonic::Status) -> Self {
+        use tonic::Code;
+
+        let message = status.message().to_string();
+        match status.code() {
+            Code::InvalidArgument | Code::OutOfRange | Code::FailedPrecondition | Code::NotFound => {
+                AppError::InvalidRequest(message)
+            }
+            Code::DeadlineExceeded => AppError::Timeout(message),
+            // Backend capacity or memory, not client throttling; 429 is kept
+            // for the gateway's own rate limit
+            Code::ResourceExhausted => AppError::BackendError(format!("Backend overloaded: {}", message)),
+            Code::Unavailable => AppError::BackendError(format!("Backend unavailable: {}", message)),
+            Code::Unauthenticated | Code::PermissionDenied => {
+                AppError::BackendError(format!("Backend rejected credentials: {}", message))
+            }
+            _ => AppError::Grpc(Box::new(status)),
+        }
+    }
+}
+
 /// Error response format (OpenAI compatible)
 #[derive(Serialize)]
 pub struct ErrorResponse {
@@ -64,15 +91,17 @@ pub struct ErrorDetail {
     pub code: Option<String>,
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error_type, code) = match &self {
+impl AppError {
+    /// HTTP status and OpenAI-compatible error body for this error
+    pub fn to_error_response(&self) -> (StatusCode, ErrorResponse) {
+        let (status, error_type, code) = match self {
             AppError::Config(_) => (StatusCode::INTERNAL_SERVER_ERROR, "server_error", None),
             AppError::Io(_) => (StatusCode::INTERNAL_SERVER_ERROR, "server_error", None),
             AppError::Json(_) => (StatusCode::BAD_REQUEST, "invalid_request_error", Some("invalid_json")),
             AppError::HttpClient(_) => (StatusCode::BAD_GATEWAY, "backend_error", None),
             AppError::Grpc(_) => (StatusCode::BAD_GATEWAY, "backend_error", None),
             AppError::BackendNotFound(_) => (StatusCode::NOT_FOUND, "not_found_error", Some("backend_not_found")),
+            AppError::JobNotFound(_) => (StatusCode::NOT_FOUND, "not_found_error", Some("job_not_found")),
             AppError::NoHealthyBackends(_) => (StatusCode::SERVICE_UNAVAILABLE, "server_error", Some("no_healthy_backends")),
             AppError::AuthenticationFailed(_) => (StatusCode::UNAUTHORIZED, "authentication_error", Some("invalid_api_key")),
             AppError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "rate_limit_error", Some("rate_limit_exceeded")),
@@ -82,15 +111,22 @@ impl IntoResponse for AppError {
             AppError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "server_error", None),
         };
 
-        let body = Json(ErrorResponse {
+        let body = ErrorResponse {
             error: ErrorDetail {
                 message: self.to_string(),
                 r#type: error_type.to_string(),
                 code: code.map(|c| c.to_string()),
             },
-        });
+        };
+
+        (status, body)
+    }
+}
 
-        (status, body).into_response()
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, body) = self.to_error_response();
+        (status, Json(body)).into_response()
     }
 }
 
@@ -0,0 +1,357 @@
+//! Per-endpoint circuit breaker
+//!
+//! A closed circuit passes all traffic and records outcomes over a rolling
+//! window. It opens once the failure rate (slow calls count as failures)
+//! crosses the configured threshold, rejects traffic for a cool-down, then
+//! goes half-open and lets a limited number of probe requests through. Enough
+//! successful probes close it again; a failed probe reopens it.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::config::CircuitBreakerConfig;
+
+/// Circuit breaker state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Traffic flows normally
+    Closed,
+    /// Traffic is rejected until the cool-down has passed
+    Open,
+    /// A limited number of probe requests decide whether to close again
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Name used in configuration and API responses
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Point-in-time view of a circuit breaker
+#[derive(Debug, Clone)]
+pub struct CircuitSnapshot {
+    pub state: CircuitState,
+    /// Failure rate over the rolling window, from 0.0 to 1.0
+    pub failure_rate: f64,
+    /// Outcomes recorded in the rolling window
+    pub requests: usize,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    /// Outcomes in the rolling window as (time, failed)
+    outcomes: VecDeque<(Instant, bool)>,
+    opened_at: Instant,
+    probes_in_flight: u32,
+    probe_successes: u32,
+    last_probe: Instant,
+}
+
+/// Circuit breaker guarding one endpoint
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    endpoint: String,
+    failure_rate_threshold: f64,
+    slow_call: Option<Duration>,
+    minimum_requests: usize,
+    window: Duration,
+    cool_down: Duration,
+    half_open_probes: u32,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed circuit breaker for an endpoint
+    pub fn new(endpoint: impl Into<String>, config: &CircuitBreakerConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            endpoint: endpoint.into(),
+            failure_rate_threshold: config.failure_rate_threshold,
+            slow_call: config.slow_call_ms.map(Duration::from_millis),
+            minimum_requests: config.minimum_requests.max(1) as usize,
+            window: Duration::from_secs(config.window_secs),
+            cool_down: Duration::from_secs(config.open_secs),
+            half_open_probes: config.half_open_probes.max(1),
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                outcomes: VecDeque::new(),
+                opened_at: now,
+                probes_in_flight: 0,
+                probe_successes: 0,
+                last_probe: now,
+            }),
+        }
+    }
+
+    /// Current state; an open circuit whose cool-down has passed reports half-open
+    pub fn state(&self) -> CircuitState {
+        let inner = self.inner.lock();
+        match inner.state {
+            CircuitState::Open if inner.opened_at.elapsed() >= self.cool_down => CircuitState::HalfOpen,
+            state => state,
+        }
+    }
+
+    /// Whether a request would currently be let through, without reserving a probe
+    pub fn allows_request(&self) -> bool {
+        let inner = self.inner.lock();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => inner.opened_at.elapsed() >= self.cool_down,
+            CircuitState::HalfOpen => self.probe_available(&inner),
+        }
+    }
+
+    /// Let a request through, reserving a probe slot when half-open
+    ///
+    /// Every acquired request must be followed by [`record_success`] or
+    /// [`record_failure`].
+    ///
+    /// [`record_success`]: CircuitBreaker::record_success
+    /// [`record_failure`]: CircuitBreaker::record_failure
+    pub fn try_acquire(&self) -> bool {
+        let mut inner = self.inner.lock();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open if inner.opened_at.elapsed() >= self.cool_down => {
+                inner.state = CircuitState::HalfOpen;
+                inner.probes_in_flight = 1;
+                inner.probe_successes = 0;
+                inner.last_probe = Instant::now();
+                true
+            }
+            CircuitState::Open => false,
+            CircuitState::HalfOpen if self.probe_available(&inner) => {
+                // Probes that never reported back are given up after a cool-down
+                if inner.probes_in_flight >= self.half_open_probes {
+                    inner.probes_in_flight = 0;
+                }
+                inner.probes_in_flight += 1;
+                inner.last_probe = Instant::now();
+                true
+            }
+            CircuitState::HalfOpen => false,
+        }
+    }
+
+    /// Record a successful call, with its latency if it should be checked
+    /// against the slow-call threshold
+    pub fn record_success(&self, latency: Option<Duration>) {
+        let slow = matches!((latency, self.slow_call), (Some(latency), Some(limit)) if latency > limit);
+        self.record(slow);
+    }
+
+    /// Record a failed call
+    pub fn record_failure(&self) {
+        self.record(true);
+    }
+
+    /// Snapshot of the current state and rolling-window statistics
+    pub fn snapshot(&self) -> CircuitSnapshot {
+        let state = self.state();
+        let mut inner = self.inner.lock();
+        self.prune(&mut inner);
+        CircuitSnapshot {
+            state,
+            failure_rate: failure_rate(&inner.outcomes),
+            requests: inner.outcomes.len(),
+        }
+    }
+
+    fn record(&self, failed: bool) {
+        let mut inner = self.inner.lock();
+        match inner.state {
+            CircuitState::Closed => {
+                inner.outcomes.push_back((Instant::now(), failed));
+                self.prune(&mut inner);
+                let rate = failure_rate(&inner.outcomes);
+                if inner.outcomes.len() >= self.minimum_requests && rate >= self.failure_rate_threshold {
+                    warn!(
+                        endpoint = %self.endpoint,
+                        failure_rate = rate,
+                        requests = inner.outcomes.len(),
+                        "Circuit opened"
+                    );
+                    self.open(&mut inner);
+                }
+            }
+            CircuitState::HalfOpen => {
+                inner.probes_in_flight = inner.probes_in_flight.saturating_sub(1);
+                if failed {
+                    warn!(endpoint = %self.endpoint, "Probe failed, circuit reopened");
+                    self.open(&mut inner);
+                } else {
+                    inner.probe_successes += 1;
+                    if inner.probe_successes >= self.half_open_probes {
+                        info!(endpoint = %self.endpoint, "Circuit closed");
+                        inner.state = CircuitState::Closed;
+                        inner.outcomes.clear();
+                    }
+                }
+            }
+            // Late outcomes of requests sent before the circuit opened
+            CircuitState::Open => {}
+        }
+    }
+
+    fn open(&self, inner: &mut Inner) {
+        inner.state = CircuitState::Open;
+        inner.opened_at = Instant::now();
+        inner.probes_in_flight = 0;
+        inner.probe_successes = 0;
+    }
+
+    fn probe_available(&self, inner: &Inner) -> bool {
+        inner.probes_in_flight < self.half_open_probes || inner.last_probe.elapsed() >= self.cool_down
+    }
+
+    fn prune(&self, inner: &mut Inner) {
+        let now = Instant::now();
+        while let Some(&(at, _)) = inner.outcomes.front() {
+            if now.duration_since(at) < self.window {
+                break;
+            }
+            inner.outcomes.pop_front();
+        }
+    }
+}
+
+fn failure_rate(outcomes: &VecDeque<(Instant, bool)>) -> f64 {
+    if outcomes.is_empty() {
+        return 0.0;
+    }
+    outcomes.iter().filter(|(_, failed)| *failed).count() as f64 / outcomes.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(open_secs: u64, half_open_probes: u32) -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_rate_threshold: 0.5,
+            slow_call_ms: Some(100),
+            minimum_requests: 4,
+            window_secs: 60,
+            open_secs,
+            half_open_probes,
+        }
+    }
+
+    #[test]
+    fn test_opens_on_failure_rate() {
+        let breaker = CircuitBreaker::new("http://sd:7860", &config(30, 1));
+        breaker.record_failure();
+        breaker.record_failure();
+        breaker.record_failure();
+        // Below the minimum number of requests
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.record_success(None);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allows_request());
+        assert!(!breaker.try_acquire());
+    }
+
+    #[test]
+    fn test_slow_calls_count_as_failures() {
+        let breaker = CircuitBreaker::new("http://sd:7860", &config(30, 1));
+        breaker.record_success(Some(Duration::from_millis(10)));
+        breaker.record_success(Some(Duration::from_millis(10)));
+        breaker.record_success(Some(Duration::from_millis(500)));
+        assert_eq!(breaker.snapshot().requests, 3);
+        breaker.record_success(Some(Duration::from_millis(500)));
+
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn test_half_open_closes_after_probes() {
+        let breaker = CircuitBreaker::new("http://sd:7860", &config(0, 2));
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+        // A zero cool-down lets probes through straight away
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.try_acquire());
+        assert!(breaker.try_acquire());
+        breaker.record_success(None);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.record_success(None);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.snapshot().requests, 0);
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let breaker = CircuitBreaker::new("http://sd:7860", &config(30, 1));
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+        {
+            // Skip the cool-down
+            let mut inner = breaker.inner.lock();
+            inner.opened_at = Instant::now() - Duration::from_secs(31);
+        }
+
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allows_request());
+    }
+
+    #[tokio::test]
+    async fn test_backend_circuit_opens_after_failures() {
+        use crate::backend::http_backend::HttpBackend;
+        use crate::backend::traits::{GenerateRequest, ImageBackend};
+        use crate::config::BackendConfig;
+        use crate::error::AppError;
+
+        // Nothing listens on port 9, so every request fails to connect
+        let backend = HttpBackend::new(&BackendConfig {
+            name: "sd".to_string(),
+            endpoints: vec!["http://127.0.0.1:9".to_string()],
+            retry_count: Some(0),
+            circuit_breaker: CircuitBreakerConfig {
+                minimum_requests: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        let request = GenerateRequest {
+            prompt: "a cat".to_string(),
+            n: 1,
+            response_format: "b64_json".to_string(),
+            ..Default::default()
+        };
+        for _ in 0..2 {
+            assert!(matches!(backend.generate(request.clone()).await, Err(AppError::BackendError(_))));
+        }
+
+        let circuits = backend.circuits();
+        assert_eq!(circuits[0].circuit.state, CircuitState::Open);
+        assert_eq!(circuits[0].circuit.failure_rate, 1.0);
+        // The open circuit rejects requests without contacting the endpoint
+        assert!(matches!(backend.generate(request).await, Err(AppError::NoHealthyBackends(_))));
+    }
+}
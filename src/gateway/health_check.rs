@@ -1,13 +1,58 @@
 //! Health check manager for monitoring backend health
+//!
+//! A single scheduler covers both the image and the text registry. Each
+//! backend is checked on its own interval, concurrently with the others, and
+//! a check that outlives the backend's timeout counts as failed. A backend
+//! whose previous check is still running is not checked again until it ends,
+//! so results are recorded in order. A multi backend, registered in both
+//! registries, is checked once for both.
 
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
 use crate::backend::registry::BackendRegistry;
+use crate::backend::TextBackendRegistry;
+use crate::config::BackendConfig;
+
+/// How often the scheduler looks for backends that are due a check
+const SCHEDULER_TICK: Duration = Duration::from_secs(1);
+
+/// How often a backend is checked and how long a check may take
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthCheckSchedule {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl HealthCheckSchedule {
+    /// Read the schedule from a backend's `health_check` section
+    pub fn from_config(config: &BackendConfig) -> Self {
+        Self {
+            interval: config.health_check_interval(),
+            timeout: config.health_check_timeout(),
+        }
+    }
+}
+
+impl Default for HealthCheckSchedule {
+    fn default() -> Self {
+        Self::from_config(&BackendConfig::default())
+    }
+}
+
+/// Registry a checked backend belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Pool {
+    Image,
+    Text,
+}
 
 /// Health status of a backend
 #[derive(Debug, Clone)]
@@ -29,11 +74,38 @@ impl Default for HealthStatus {
     }
 }
 
+/// Marks a scheduled check as in progress until dropped
+struct RunningCheck {
+    manager: Arc<HealthCheckManager>,
+    key: (Pool, String),
+}
+
+impl RunningCheck {
+    fn start(manager: &Arc<HealthCheckManager>, pool: Pool, name: &str) -> Self {
+        let key = (pool, name.to_string());
+        manager.running.insert(key.clone());
+        Self {
+            manager: manager.clone(),
+            key,
+        }
+    }
+}
+
+impl Drop for RunningCheck {
+    fn drop(&mut self) {
+        self.manager.running.remove(&self.key);
+    }
+}
+
 /// Health check manager
 pub struct HealthCheckManager {
     registry: Arc<BackendRegistry>,
+    text_registry: Option<Arc<TextBackendRegistry>>,
     health_status: DashMap<String, HealthStatus>,
+    text_health_status: DashMap<String, HealthStatus>,
     check_task: RwLock<Option<JoinHandle<()>>>,
+    /// Backends with a scheduled check still in progress
+    running: DashSet<(Pool, String)>,
     /// Number of consecutive failures before marking unhealthy
     failure_threshold: u32,
     /// Number of consecutive successes before marking healthy again
@@ -45,72 +117,175 @@ impl HealthCheckManager {
     pub fn new(registry: Arc<BackendRegistry>) -> Self {
         Self {
             registry,
+            text_registry: None,
             health_status: DashMap::new(),
+            text_health_status: DashMap::new(),
             check_task: RwLock::new(None),
+            running: DashSet::new(),
             failure_threshold: 3,
             recovery_threshold: 2,
         }
     }
 
+    /// Also check the backends of a text registry
+    pub fn with_text_registry(mut self, text_registry: Arc<TextBackendRegistry>) -> Self {
+        self.text_registry = Some(text_registry);
+        self
+    }
+
     /// Start the health check background task
-    pub async fn start(&self, interval_secs: u64) {
-        let registry = self.registry.clone();
-        let health_status = self.health_status.clone();
-        let failure_threshold = self.failure_threshold;
-        let recovery_threshold = self.recovery_threshold;
+    ///
+    /// Every backend is checked straight away and then on its own
+    /// `health_check.interval_secs`. The task stops when the manager is dropped.
+    pub async fn start(self: &Arc<Self>) {
+        let manager = Arc::downgrade(self);
 
         let handle = tokio::spawn(async move {
-            let interval = Duration::from_secs(interval_secs);
-            
+            let mut next_checks = HashMap::new();
+            let mut tick = tokio::time::interval(SCHEDULER_TICK);
+
             loop {
-                // Check all backends
-                for backend in registry.get_all() {
-                    let name = backend.name().to_string();
-                    let is_healthy = backend.health_check().await;
-
-                    let mut status = health_status
-                        .entry(name.clone())
-                        .or_insert_with(HealthStatus::default);
-
-                    status.last_check = std::time::Instant::now();
-
-                    if is_healthy {
-                        status.consecutive_failures = 0;
-                        status.consecutive_successes += 1;
-
-                        if !status.healthy && status.consecutive_successes >= recovery_threshold {
-                            status.healthy = true;
-                            info!(backend = %name, "Backend recovered and marked healthy");
-                        }
-                    } else {
-                        status.consecutive_successes = 0;
-                        status.consecutive_failures += 1;
-
-                        if status.healthy && status.consecutive_failures >= failure_threshold {
-                            status.healthy = false;
-                            warn!(
-                                backend = %name,
-                                failures = status.consecutive_failures,
-                                "Backend marked unhealthy after consecutive failures"
-                            );
-                        }
-                    }
-
-                    debug!(
-                        backend = %name,
-                        healthy = status.healthy,
-                        consecutive_failures = status.consecutive_failures,
-                        consecutive_successes = status.consecutive_successes,
-                        "Health check completed"
-                    );
-                }
-
-                tokio::time::sleep(interval).await;
+                tick.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                next_checks = manager.spawn_due_checks(next_checks);
             }
         });
 
         *self.check_task.write().await = Some(handle);
-        info!(interval_secs = interval_secs, "Started health check background task");
+        info!("Started health check background task");
+    }
+
+    /// Spawn a check for every backend that is due one, returning the updated
+    /// schedule; backends that have been removed drop out of it
+    fn spawn_due_checks(
+        self: &Arc<Self>,
+        mut previous: HashMap<(Pool, String), Instant>,
+    ) -> HashMap<(Pool, String), Instant> {
+        // Catch statuses recorded by checks that finished after a removal
+        self.prune();
+
+        let now = Instant::now();
+        let mut schedule = HashMap::new();
+        let mut due = |pool: Pool, name: &str, interval: Duration| {
+            let key = (pool, name.to_string());
+            let next_check = previous.remove(&key).unwrap_or(now);
+            if self.running.contains(&key) {
+                // Picked up again on the first tick after the running check ends
+                schedule.insert(key, next_check);
+                return false;
+            }
+            let is_due = next_check <= now;
+            schedule.insert(key, if is_due { now + interval } else { next_check });
+            is_due
+        };
+
+        for backend in self.registry.get_all() {
+            let schedule = backend.health_check_schedule();
+            if due(Pool::Image, backend.name(), schedule.interval) {
+                let manager = self.clone();
+                let running = RunningCheck::start(self, Pool::Image, backend.name());
+                tokio::spawn(async move {
+                    let _running = running;
+                    let name = backend.name().to_string();
+                    manager.run_check(Pool::Image, &name, schedule.timeout, backend.health_check()).await;
+                });
+            }
+        }
+
+        for backend in self.text_backends() {
+            if self.registry.contains(backend.name()) {
+                // Multi backends are checked with the image backends
+                continue;
+            }
+            let schedule = backend.health_check_schedule();
+            if due(Pool::Text, backend.name(), schedule.interval) {
+                let manager = self.clone();
+                let running = RunningCheck::start(self, Pool::Text, backend.name());
+                tokio::spawn(async move {
+                    let _running = running;
+                    let name = backend.name().to_string();
+                    manager.run_check(Pool::Text, &name, schedule.timeout, backend.health_check()).await;
+                });
+            }
+        }
+
+        schedule
+    }
+
+    /// Check every backend in both registries once, concurrently
+    pub async fn check_all(&self) {
+        let image_checks = self.registry.get_all().into_iter().map(|backend| async move {
+            let timeout = backend.health_check_schedule().timeout;
+            self.run_check(Pool::Image, backend.name(), timeout, backend.health_check()).await;
+        });
+        let text_checks = self
+            .text_backends()
+            .into_iter()
+            .filter(|backend| !self.registry.contains(backend.name()))
+            .map(|backend| async move {
+                let timeout = backend.health_check_schedule().timeout;
+                self.run_check(Pool::Text, backend.name(), timeout, backend.health_check()).await;
+            });
+
+        tokio::join!(join_all(image_checks), join_all(text_checks));
+    }
+
+    /// Run one health check within its timeout and record the result
+    async fn run_check(&self, pool: Pool, name: &str, timeout: Duration, check: impl Future<Output = bool>) {
+        let is_healthy = match tokio::time::timeout(timeout, check).await {
+            Ok(is_healthy) => is_healthy,
+            Err(_) => {
+                debug!(backend = %name, timeout_ms = timeout.as_millis() as u64, "Health check timed out");
+                false
+            }
+        };
+
+        let status = record_result(
+            self.statuses(pool),
+            name,
+            is_healthy,
+            self.failure_threshold,
+            self.recovery_threshold,
+        );
+        if pool == Pool::Image && self.in_text_registry(name) {
+            record_result(
+                &self.text_health_status,
+                name,
+                is_healthy,
+                self.failure_threshold,
+                self.recovery_threshold,
+            );
+        }
+
+        debug!(
+            backend = %name,
+            healthy = status.healthy,
+            consecutive_failures = status.consecutive_failures,
+            consecutive_successes = status.consecutive_successes,
+            "Health check completed"
+        );
+    }
+
+    fn statuses(&self, pool: Pool) -> &DashMap<String, HealthStatus> {
+        match pool {
+            Pool::Image => &self.health_status,
+            Pool::Text => &self.text_health_status,
+        }
+    }
+
+    fn in_text_registry(&self, name: &str) -> bool {
+        self.text_registry
+            .as_ref()
+            .is_some_and(|registry| registry.contains(name))
+    }
+
+    fn text_backends(&self) -> Vec<Arc<dyn crate::backend::TextBackend>> {
+        self.text_registry
+            .as_ref()
+            .map(|registry| registry.get_all_backends())
+            .unwrap_or_default()
     }
 
     /// Stop the health check background task
@@ -129,11 +304,34 @@ impl HealthCheckManager {
             .unwrap_or(true) // Assume healthy if not checked yet
     }
 
+    /// Record the outcome of a request served by a backend
+    ///
+    /// Request outcomes count towards the same failure and recovery thresholds
+    /// as background checks, so a failing backend is taken out of rotation
+    /// without waiting for the next check interval.
+    pub fn record_outcome(&self, name: &str, success: bool) {
+        record_result(
+            &self.health_status,
+            name,
+            success,
+            self.failure_threshold,
+            self.recovery_threshold,
+        );
+    }
+
     /// Get health status for a backend
     pub fn get_status(&self, name: &str) -> Option<HealthStatus> {
         self.health_status.get(name).map(|s| s.clone())
     }
 
+    /// Check if a specific text backend is healthy
+    pub fn is_text_healthy(&self, name: &str) -> bool {
+        self.text_health_status
+            .get(name)
+            .map(|s| s.healthy)
+            .unwrap_or(true) // Assume healthy if not checked yet
+    }
+
     /// Get health summary (total, healthy, unhealthy)
     pub async fn get_health_summary(&self) -> (usize, usize, usize) {
         let backends = self.registry.get_all();
@@ -153,6 +351,32 @@ impl HealthCheckManager {
         (total, healthy, unhealthy)
     }
 
+    /// Get text backend health summary (total, healthy, unhealthy)
+    pub fn get_text_health_summary(&self) -> (usize, usize, usize) {
+        let backends = self.text_backends();
+        let healthy = backends
+            .iter()
+            .filter(|backend| self.is_text_healthy(backend.name()))
+            .count();
+
+        (backends.len(), healthy, backends.len() - healthy)
+    }
+
+    /// Get the health of every registered image and text backend as
+    /// `(name, healthy)` pairs
+    pub fn backend_health(&self) -> Vec<(String, bool)> {
+        let image = self
+            .registry
+            .get_all()
+            .into_iter()
+            .map(|backend| (backend.name().to_string(), self.is_healthy(backend.name())));
+        let text = self
+            .text_backends()
+            .into_iter()
+            .map(|backend| (backend.name().to_string(), self.is_text_healthy(backend.name())));
+        image.chain(text).collect()
+    }
+
     /// Force a health check for a specific backend
     pub async fn check_now(&self, name: &str) -> Option<bool> {
         let backend = self.registry.get(name)?;
@@ -160,7 +384,7 @@ impl HealthCheckManager {
 
         let mut status = self.health_status
             .entry(name.to_string())
-            .or_insert_with(HealthStatus::default);
+            .or_default();
 
         status.last_check = std::time::Instant::now();
         status.healthy = is_healthy;
@@ -176,6 +400,20 @@ impl HealthCheckManager {
         Some(is_healthy)
     }
 
+    /// Drop the health status of a backend that was unregistered
+    ///
+    /// A backend registered again under the same name starts out healthy.
+    pub fn forget(&self, name: &str) {
+        self.health_status.remove(name);
+        self.text_health_status.remove(name);
+    }
+
+    /// Drop statuses of backends no longer in their registry
+    fn prune(&self) {
+        self.health_status.retain(|name, _| self.registry.contains(name));
+        self.text_health_status.retain(|name, _| self.in_text_registry(name));
+    }
+
     /// Get all unhealthy backends
     pub fn get_unhealthy_backends(&self) -> Vec<String> {
         self.health_status
@@ -186,3 +424,165 @@ impl HealthCheckManager {
     }
 }
 
+/// Apply a health result to a backend's status, flipping it healthy or
+/// unhealthy once the corresponding threshold is reached
+fn record_result(
+    health_status: &DashMap<String, HealthStatus>,
+    name: &str,
+    is_healthy: bool,
+    failure_threshold: u32,
+    recovery_threshold: u32,
+) -> HealthStatus {
+    let mut status = health_status.entry(name.to_string()).or_default();
+
+    status.last_check = std::time::Instant::now();
+
+    if is_healthy {
+        status.consecutive_failures = 0;
+        status.consecutive_successes += 1;
+
+        if !status.healthy && status.consecutive_successes >= recovery_threshold {
+            status.healthy = true;
+            info!(backend = %name, "Backend recovered and marked healthy");
+        }
+    } else {
+        status.consecutive_successes = 0;
+        status.consecutive_failures += 1;
+
+        if status.healthy && status.consecutive_failures >= failure_threshold {
+            status.healthy = false;
+            warn!(
+                backend = %name,
+                failures = status.consecutive_failures,
+                "Backend marked unhealthy after consecutive failures"
+            );
+        }
+    }
+
+    status.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BackendHealthCheck, BackendType, ProtocolType};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_check_all_covers_both_registries() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+            .mount(&server)
+            .await;
+
+        let registry = Arc::new(BackendRegistry::new());
+        registry
+            .add_backend(BackendConfig {
+                name: "sd".to_string(),
+                endpoints: vec![server.uri()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let text_registry = Arc::new(TextBackendRegistry::new());
+        text_registry
+            .add_backend(BackendConfig {
+                name: "vllm".to_string(),
+                backend_type: BackendType::Text,
+                protocol: ProtocolType::OpenAI,
+                endpoints: vec![server.uri()],
+                health_check: BackendHealthCheck {
+                    path: "/slow".to_string(),
+                    timeout_secs: Some(1),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let manager = HealthCheckManager::new(registry).with_text_registry(text_registry);
+        manager.check_all().await;
+
+        assert_eq!(manager.get_status("sd").unwrap().consecutive_successes, 1);
+        // The slow text backend timed out, but stays healthy until the failure threshold
+        assert_eq!(manager.text_health_status.get("vllm").unwrap().consecutive_failures, 1);
+        assert_eq!(manager.get_text_health_summary(), (1, 1, 0));
+        assert_eq!(manager.backend_health().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_running_checks_do_not_overlap() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let registry = Arc::new(BackendRegistry::new());
+        registry
+            .add_backend(BackendConfig {
+                name: "sd".to_string(),
+                endpoints: vec![server.uri()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let manager = Arc::new(HealthCheckManager::new(registry));
+
+        // Due again straight away, but the first check has not answered yet
+        let schedule = manager.spawn_due_checks(HashMap::new());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let schedule = manager.spawn_due_checks(schedule.into_keys().map(|key| (key, Instant::now())).collect());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(manager.running.is_empty());
+        assert_eq!(manager.get_status("sd").unwrap().consecutive_successes, 1);
+        manager.spawn_due_checks(schedule);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_removed_backends_are_forgotten() {
+        let registry = Arc::new(BackendRegistry::new());
+        for name in ["sd", "flux"] {
+            registry
+                .add_backend(BackendConfig {
+                    name: name.to_string(),
+                    endpoints: vec!["http://127.0.0.1:9".to_string()],
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        let manager = HealthCheckManager::new(registry.clone());
+        for _ in 0..3 {
+            manager.record_outcome("sd", false);
+            manager.record_outcome("flux", false);
+        }
+        assert_eq!(manager.get_unhealthy_backends().len(), 2);
+
+        registry.remove_backend("sd").await.unwrap();
+        manager.forget("sd");
+        assert!(manager.get_status("sd").is_none());
+        assert_eq!(manager.get_unhealthy_backends(), vec!["flux".to_string()]);
+
+        // A status recorded after the removal is pruned as well
+        registry.remove_backend("flux").await.unwrap();
+        manager.prune();
+        assert!(manager.get_unhealthy_backends().is_empty());
+        assert!(manager.backend_health().is_empty());
+    }
+}
@@ -1,37 +1,81 @@
 //! Load balancer implementation with multiple strategies
 
 use parking_lot::RwLock;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, warn};
 
 use crate::backend::registry::BackendRegistry;
 use crate::backend::traits::ImageBackend;
 use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckManager;
+use crate::gateway::load_tracker::{least_cost, power_of_two_choices, InFlightGuard, LoadTracker};
+use crate::gateway::router::RoutingRules;
 
 /// Load balancing strategy
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum LoadBalancingStrategy {
     /// Round-robin distribution
+    #[default]
     RoundRobin,
     /// Weighted round-robin based on backend weights
     WeightedRoundRobin,
     /// Random selection
     Random,
-    /// Least connections (placeholder - needs connection tracking)
+    /// Fewest in-flight requests
     LeastConnections,
+    /// Lowest peak-EWMA latency, scaled by in-flight requests
+    LeastLatency,
+    /// Lower-cost of two randomly chosen backends (peak-EWMA cost)
+    PowerOfTwoChoices,
 }
 
-impl Default for LoadBalancingStrategy {
-    fn default() -> Self {
-        Self::RoundRobin
+impl LoadBalancingStrategy {
+    /// Configuration name of the strategy
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoadBalancingStrategy::RoundRobin => "round_robin",
+            LoadBalancingStrategy::WeightedRoundRobin => "weighted_round_robin",
+            LoadBalancingStrategy::Random => "random",
+            LoadBalancingStrategy::LeastConnections => "least_connections",
+            LoadBalancingStrategy::LeastLatency => "least_latency",
+            LoadBalancingStrategy::PowerOfTwoChoices => "power_of_two_choices",
+        }
+    }
+}
+
+impl FromStr for LoadBalancingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "round_robin" => Ok(LoadBalancingStrategy::RoundRobin),
+            "weighted_round_robin" | "weighted" => Ok(LoadBalancingStrategy::WeightedRoundRobin),
+            "random" => Ok(LoadBalancingStrategy::Random),
+            "least_connections" => Ok(LoadBalancingStrategy::LeastConnections),
+            "least_latency" | "peak_ewma" => Ok(LoadBalancingStrategy::LeastLatency),
+            "power_of_two_choices" | "p2c" => Ok(LoadBalancingStrategy::PowerOfTwoChoices),
+            other => Err(format!("Unknown load balancing strategy '{}'", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for LoadBalancingStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
 /// Load balancer for distributing requests across backends
 pub struct LoadBalancer {
     registry: Arc<BackendRegistry>,
+    health_manager: Option<Arc<HealthCheckManager>>,
+    /// Route to unhealthy backends when none are healthy
+    fail_open: AtomicBool,
     strategy: RwLock<LoadBalancingStrategy>,
+    routing: RwLock<RoutingRules>,
+    load: LoadTracker,
     round_robin_index: AtomicUsize,
     weighted_state: RwLock<WeightedRoundRobinState>,
 }
@@ -47,7 +91,11 @@ impl LoadBalancer {
     pub fn new(registry: Arc<BackendRegistry>) -> Self {
         Self {
             registry,
+            health_manager: None,
+            fail_open: AtomicBool::new(false),
             strategy: RwLock::new(LoadBalancingStrategy::default()),
+            routing: RwLock::new(RoutingRules::default()),
+            load: LoadTracker::new(),
             round_robin_index: AtomicUsize::new(0),
             weighted_state: RwLock::new(WeightedRoundRobinState {
                 current_index: 0,
@@ -56,6 +104,47 @@ impl LoadBalancer {
         }
     }
 
+    /// Skip backends that the health check manager reports as unhealthy
+    pub fn with_health_manager(mut self, health_manager: Arc<HealthCheckManager>) -> Self {
+        self.health_manager = Some(health_manager);
+        self
+    }
+
+    /// Set whether to fall back to unhealthy backends when none are healthy
+    pub fn set_fail_open(&self, fail_open: bool) {
+        self.fail_open.store(fail_open, Ordering::Relaxed);
+    }
+
+    /// Whether requests fall back to unhealthy backends when none are healthy
+    pub fn fail_open(&self) -> bool {
+        self.fail_open.load(Ordering::Relaxed)
+    }
+
+    /// Report the outcome of a request to the health check manager, if any
+    pub fn record_outcome(&self, backend_name: &str, success: bool) {
+        if let Some(health_manager) = &self.health_manager {
+            health_manager.record_outcome(backend_name, success);
+        }
+    }
+
+    /// Count a request to a backend as in flight until the guard is dropped
+    ///
+    /// Call `finish` on the guard after a successful response to feed the
+    /// backend's latency EWMA.
+    pub fn start_request(&self, backend_name: &str) -> InFlightGuard {
+        self.load.get(backend_name).start()
+    }
+
+    /// Number of requests in flight to a backend
+    pub fn in_flight(&self, backend_name: &str) -> usize {
+        self.load.get(backend_name).in_flight()
+    }
+
+    /// Peak-EWMA latency of a backend in milliseconds, if it has served a request
+    pub fn latency_ms(&self, backend_name: &str) -> Option<f64> {
+        self.load.get(backend_name).latency_ms()
+    }
+
     /// Set the load balancing strategy
     pub fn set_strategy(&self, strategy: LoadBalancingStrategy) {
         *self.strategy.write() = strategy;
@@ -66,23 +155,118 @@ impl LoadBalancer {
         *self.strategy.read()
     }
 
+    /// Set the model mappings and fallback chains used to route requests
+    pub fn set_routing(&self, rules: RoutingRules) {
+        *self.routing.write() = rules;
+    }
+
+    /// Whether a model name appears in the routing configuration
+    pub fn is_configured_model(&self, model: &str) -> bool {
+        self.routing.read().has_model(model)
+    }
+
+    /// Backends to try for a request, in order
+    ///
+    /// Starts with the requested backend, or the backend mapped to the model,
+    /// followed by its fallbacks; an unhealthy backend moves behind healthy
+    /// ones. Mappings to backends that are not image backends are ignored.
+    /// Empty when any backend may be selected.
+    pub fn candidates(&self, backend_name: Option<&str>, model: Option<&str>) -> Vec<String> {
+        let primary = match backend_name {
+            Some(name) => name.to_string(),
+            None => {
+                let routing = self.routing.read();
+                let is_backend = |name: &str| self.registry.contains(name);
+                match model.and_then(|m| routing.backend_for_model(m, is_backend)) {
+                    Some(name) if self.registry.contains(name) => name.to_string(),
+                    _ => return Vec::new(),
+                }
+            }
+        };
+
+        let mut candidates = vec![primary.clone()];
+        candidates.extend(self.fallbacks(&primary));
+        candidates.sort_by_key(|name| !self.is_healthy(name));
+        candidates
+    }
+
+    /// Fallback backends for a backend, healthy ones first
+    pub fn fallbacks(&self, backend_name: &str) -> Vec<String> {
+        let mut fallbacks: Vec<String> = self
+            .routing
+            .read()
+            .fallbacks(backend_name)
+            .into_iter()
+            .filter(|name| self.registry.contains(name))
+            .collect();
+        fallbacks.sort_by_key(|name| !self.is_healthy(name));
+        fallbacks
+    }
+
     /// Select a backend for a request
     pub async fn select_backend(
         &self,
         backend_name: Option<&str>,
+    ) -> Result<Arc<dyn ImageBackend>> {
+        self.select_backend_for(backend_name, &[]).await
+    }
+
+    /// Whether a backend advertises every given capability
+    pub fn supports(&self, backend_name: &str, capabilities: &[&str]) -> bool {
+        self.registry
+            .get(backend_name)
+            .map(|backend| capabilities.iter().all(|c| backend.supports(c)))
+            .unwrap_or(false)
+    }
+
+    /// Select a backend that advertises every given capability
+    pub async fn select_backend_for(
+        &self,
+        backend_name: Option<&str>,
+        capabilities: &[&str],
     ) -> Result<Arc<dyn ImageBackend>> {
         // If a specific backend is requested, use that
         if let Some(name) = backend_name {
-            return self
+            let backend = self
                 .registry
                 .get(name)
-                .ok_or_else(|| AppError::BackendNotFound(name.to_string()));
+                .ok_or_else(|| AppError::BackendNotFound(name.to_string()))?;
+
+            if let Some(missing) = capabilities.iter().find(|c| !backend.supports(c)) {
+                return Err(AppError::InvalidRequest(format!(
+                    "Backend '{}' does not support '{}' requests",
+                    name, missing
+                )));
+            }
+
+            if !backend.is_enabled() {
+                return Err(AppError::NoHealthyBackends(name.to_string()));
+            }
+
+            if !self.is_healthy(name) {
+                if !self.fail_open() {
+                    return Err(AppError::NoHealthyBackends(name.to_string()));
+                }
+                warn!(backend = %name, "Requested backend is unhealthy, routing anyway (fail-open)");
+            }
+
+            return Ok(backend);
         }
 
         // Get all healthy backends
-        let healthy_backends = self.get_healthy_backends().await;
+        let healthy_backends = self.get_healthy_backends(capabilities).await;
         
         if healthy_backends.is_empty() {
+            // Unhealthy capable backends may recover; no capable backend is a client error
+            let capable = self.registry.get_all().iter().any(|backend| {
+                backend.is_enabled() && capabilities.iter().all(|c| backend.supports(c))
+            });
+            if !capable && !capabilities.is_empty() {
+                return Err(AppError::InvalidRequest(format!(
+                    "No backend supports '{}' requests",
+                    capabilities.join(", ")
+                )));
+            }
             return Err(AppError::NoHealthyBackends("all".to_string()));
         }
 
@@ -99,8 +283,13 @@ impl LoadBalancer {
                 self.select_random(&healthy_backends)
             }
             LoadBalancingStrategy::LeastConnections => {
-                // Fall back to round-robin for now
-                self.select_round_robin(&healthy_backends)
+                self.select_least_cost(&healthy_backends, |name| self.in_flight(name) as f64)
+            }
+            LoadBalancingStrategy::LeastLatency => {
+                self.select_least_cost(&healthy_backends, |name| self.load.get(name).cost())
+            }
+            LoadBalancingStrategy::PowerOfTwoChoices => {
+                self.select_power_of_two(&healthy_backends)
             }
         };
 
@@ -113,22 +302,46 @@ impl LoadBalancer {
         Ok(selected)
     }
 
-    /// Get all healthy backends
-    async fn get_healthy_backends(&self) -> Vec<Arc<dyn ImageBackend>> {
-        let all_backends = self.registry.get_all();
-        let mut healthy = Vec::new();
+    /// Get all enabled backends with the given capabilities that the health
+    /// check manager reports as healthy
+    ///
+    /// Uses the cached status from the background checker rather than probing
+    /// each backend. When every enabled backend is unhealthy, all of them are
+    /// returned if fail-open is set.
+    async fn get_healthy_backends(&self, capabilities: &[&str]) -> Vec<Arc<dyn ImageBackend>> {
+        let enabled: Vec<_> = self
+            .registry
+            .get_all()
+            .into_iter()
+            .filter(|backend| backend.is_enabled())
+            .filter(|backend| capabilities.iter().all(|c| backend.supports(c)))
+            .collect();
 
-        for backend in all_backends {
-            if backend.is_enabled() {
-                // Use cached health status instead of checking each time
-                // The health check manager will update the status periodically
-                healthy.push(backend);
-            }
+        let healthy: Vec<_> = enabled
+            .iter()
+            .filter(|backend| self.is_healthy(backend.name()))
+            .cloned()
+            .collect();
+
+        if healthy.is_empty() && !enabled.is_empty() && self.fail_open() {
+            warn!(
+                backends = enabled.len(),
+                "All backends are unhealthy, routing to all enabled backends (fail-open)"
+            );
+            return enabled;
         }
 
         healthy
     }
 
+    /// Check a backend's cached health, treating it as healthy without a health manager
+    fn is_healthy(&self, name: &str) -> bool {
+        self.health_manager
+            .as_ref()
+            .map(|health_manager| health_manager.is_healthy(name))
+            .unwrap_or(true)
+    }
+
     /// Round-robin selection
     fn select_round_robin(
         &self,
@@ -138,6 +351,23 @@ impl LoadBalancer {
         backends[index % backends.len()].clone()
     }
 
+    /// Lowest-cost selection, rotating between backends with equal cost
+    fn select_least_cost(
+        &self,
+        backends: &[Arc<dyn ImageBackend>],
+        cost: impl Fn(&str) -> f64,
+    ) -> Arc<dyn ImageBackend> {
+        let start = self.round_robin_index.fetch_add(1, Ordering::Relaxed);
+        let index = least_cost(backends, start, |b| cost(b.name())).unwrap_or(0);
+        backends[index].clone()
+    }
+
+    /// Power-of-two-choices selection on peak-EWMA cost
+    fn select_power_of_two(&self, backends: &[Arc<dyn ImageBackend>]) -> Arc<dyn ImageBackend> {
+        let index = power_of_two_choices(backends, |b| self.load.get(b.name()).cost()).unwrap_or(0);
+        backends[index].clone()
+    }
+
     /// Weighted round-robin selection
     fn select_weighted_round_robin(
         &self,
@@ -199,5 +429,16 @@ mod tests {
         assert_eq!(gcd(100, 25), 25);
         assert_eq!(gcd(7, 3), 1);
     }
+
+    #[test]
+    fn test_strategy_from_str() {
+        assert_eq!("round_robin".parse(), Ok(LoadBalancingStrategy::RoundRobin));
+        assert_eq!("Least_Connections".parse(), Ok(LoadBalancingStrategy::LeastConnections));
+        assert_eq!("p2c".parse(), Ok(LoadBalancingStrategy::PowerOfTwoChoices));
+        assert!("fastest".parse::<LoadBalancingStrategy>().is_err());
+
+        let strategy = LoadBalancingStrategy::LeastLatency;
+        assert_eq!(strategy.to_string().parse(), Ok(strategy));
+    }
 }
 
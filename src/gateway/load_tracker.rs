@@ -0,0 +1,238 @@
+//! In-flight request and latency tracking for load-aware balancing
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use rand::seq::index::sample;
+use rand::Rng;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::gateway::load_balancer::LoadBalancingStrategy;
+
+/// Time constant for decaying the latency EWMA towards faster samples
+const DECAY_SECS: f64 = 10.0;
+
+/// Latency assumed for a busy target that has not completed a request yet,
+/// so untested targets are not flooded while their first requests run
+const UNMEASURED_PENALTY_MS: f64 = 1_000_000.0;
+
+/// In-flight count and peak-EWMA latency for one backend or endpoint
+#[derive(Debug, Default)]
+pub struct LoadStats {
+    in_flight: AtomicUsize,
+    latency: Mutex<LatencyEwma>,
+}
+
+#[derive(Debug, Default)]
+struct LatencyEwma {
+    value_ms: f64,
+    last_update: Option<Instant>,
+}
+
+impl LoadStats {
+    /// Create empty stats
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a request as in flight until the returned guard is dropped
+    pub fn start(self: &Arc<Self>) -> InFlightGuard {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard {
+            stats: self.clone(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Number of requests currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Latency EWMA in milliseconds, or `None` before the first sample
+    pub fn latency_ms(&self) -> Option<f64> {
+        let latency = self.latency.lock();
+        latency.last_update.map(|_| latency.value_ms)
+    }
+
+    /// Record a completed request's latency
+    ///
+    /// Peak-EWMA: a slower sample replaces the average immediately, while
+    /// faster samples pull it down with a decay weighted by the time since the
+    /// last update. This reacts quickly when a backend slows down.
+    pub fn observe(&self, latency_ms: f64) {
+        let mut latency = self.latency.lock();
+        let now = Instant::now();
+
+        latency.value_ms = match latency.last_update {
+            Some(last) if latency_ms < latency.value_ms => {
+                let elapsed = now.duration_since(last).as_secs_f64();
+                let weight = (-elapsed / DECAY_SECS).exp();
+                latency.value_ms * weight + latency_ms * (1.0 - weight)
+            }
+            _ => latency_ms,
+        };
+        latency.last_update = Some(now);
+    }
+
+    /// Expected cost of sending one more request: latency scaled by queued work
+    pub fn cost(&self) -> f64 {
+        let in_flight = self.in_flight();
+        let latency_ms = match self.latency_ms() {
+            Some(latency_ms) => latency_ms,
+            None if in_flight == 0 => 0.0,
+            None => UNMEASURED_PENALTY_MS,
+        };
+        latency_ms * (in_flight + 1) as f64
+    }
+}
+
+/// Guard for an in-flight request; the count is released on drop
+#[derive(Debug)]
+pub struct InFlightGuard {
+    stats: Arc<LoadStats>,
+    started: Instant,
+}
+
+impl InFlightGuard {
+    /// Record the request's latency and release it
+    ///
+    /// Only call this for successful requests; fast failures would otherwise
+    /// make a broken target look fast.
+    pub fn finish(self) {
+        self.stats.observe(self.started.elapsed().as_secs_f64() * 1000.0);
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.stats.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Load stats keyed by backend name
+#[derive(Debug, Default)]
+pub struct LoadTracker {
+    stats: DashMap<String, Arc<LoadStats>>,
+}
+
+impl LoadTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the stats for a backend, creating them on first use
+    pub fn get(&self, name: &str) -> Arc<LoadStats> {
+        if let Some(stats) = self.stats.get(name) {
+            return stats.clone();
+        }
+        self.stats.entry(name.to_string()).or_default().clone()
+    }
+}
+
+/// Pick the candidate with the lowest cost, scanning from `start` so that
+/// ties rotate round-robin
+pub fn least_cost<T>(candidates: &[T], start: usize, cost: impl Fn(&T) -> f64) -> Option<usize> {
+    let len = candidates.len();
+    (0..len)
+        .map(|offset| (start + offset) % len)
+        .min_by(|&a, &b| cost(&candidates[a]).total_cmp(&cost(&candidates[b])))
+}
+
+/// Pick the lower-cost of two randomly chosen candidates
+pub fn power_of_two_choices<T>(candidates: &[T], cost: impl Fn(&T) -> f64) -> Option<usize> {
+    match candidates.len() {
+        0 => None,
+        1 => Some(0),
+        len => {
+            let picks = sample(&mut rand::thread_rng(), len, 2);
+            let (a, b) = (picks.index(0), picks.index(1));
+            Some(if cost(&candidates[b]) < cost(&candidates[a]) { b } else { a })
+        }
+    }
+}
+
+/// Pick one of a backend's endpoints according to its strategy
+///
+/// `next` is the backend's round-robin counter. Endpoints carry no weights,
+/// so weighted round-robin behaves like plain round-robin.
+pub fn select_endpoint<T>(
+    strategy: LoadBalancingStrategy,
+    candidates: &[T],
+    next: usize,
+    load: impl Fn(&T) -> &LoadStats,
+) -> Option<usize> {
+    if candidates.is_empty() {
+        return None;
+    }
+
+    match strategy {
+        LoadBalancingStrategy::RoundRobin | LoadBalancingStrategy::WeightedRoundRobin => {
+            Some(next % candidates.len())
+        }
+        LoadBalancingStrategy::Random => Some(rand::thread_rng().gen_range(0..candidates.len())),
+        LoadBalancingStrategy::LeastConnections => {
+            least_cost(candidates, next, |c| load(c).in_flight() as f64)
+        }
+        LoadBalancingStrategy::LeastLatency => least_cost(candidates, next, |c| load(c).cost()),
+        LoadBalancingStrategy::PowerOfTwoChoices => power_of_two_choices(candidates, |c| load(c).cost()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_flight_guard() {
+        let stats = Arc::new(LoadStats::new());
+        let first = stats.start();
+        let second = stats.start();
+        assert_eq!(stats.in_flight(), 2);
+
+        first.finish();
+        drop(second);
+        assert_eq!(stats.in_flight(), 0);
+        assert!(stats.latency_ms().is_some());
+    }
+
+    #[test]
+    fn test_peak_ewma() {
+        let stats = LoadStats::new();
+        stats.observe(100.0);
+        stats.observe(500.0);
+        // Slower samples take effect immediately
+        assert_eq!(stats.latency_ms(), Some(500.0));
+
+        // Faster samples decay towards the new value
+        stats.observe(100.0);
+        let latency = stats.latency_ms().unwrap();
+        assert!(latency > 100.0 && latency <= 500.0);
+    }
+
+    #[test]
+    fn test_cost() {
+        let stats = Arc::new(LoadStats::new());
+        assert_eq!(stats.cost(), 0.0);
+
+        let _guard = stats.start();
+        assert_eq!(stats.cost(), UNMEASURED_PENALTY_MS * 2.0);
+
+        stats.observe(50.0);
+        assert_eq!(stats.cost(), 100.0);
+    }
+
+    #[test]
+    fn test_least_cost_rotates_ties() {
+        let costs = [1.0, 1.0, 1.0];
+        assert_eq!(least_cost(&costs, 0, |c| *c), Some(0));
+        assert_eq!(least_cost(&costs, 1, |c| *c), Some(1));
+        assert_eq!(least_cost(&costs, 5, |c| *c), Some(2));
+
+        let costs = [3.0, 1.0, 2.0];
+        assert_eq!(least_cost(&costs, 2, |c| *c), Some(1));
+        assert_eq!(least_cost::<f64>(&[], 0, |c| *c), None);
+    }
+}
@@ -1,6 +1,9 @@
 //! Gateway module - Load balancing, health checking, and routing
 
+pub mod circuit_breaker;
 pub mod health_check;
 pub mod load_balancer;
+pub mod load_tracker;
+pub mod retry;
 pub mod router;
 
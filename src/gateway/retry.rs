@@ -0,0 +1,281 @@
+//! Retry policy with exponential backoff for transient backend failures
+//!
+//! Backends describe each attempt as an [`Attempt`]; [`RetryPolicy::run`]
+//! retries transient failures with jittered exponential backoff, honouring
+//! `Retry-After` and never sleeping past the request deadline. Each attempt
+//! runs in its own `backend_attempt` tracing span.
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, info_span, warn, Instrument};
+
+use crate::config::{BackendConfig, ConnectionDefaults};
+use crate::error::{AppError, Result};
+
+/// Delay before the first retry; doubled for each further retry
+const BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Upper bound for a single backoff delay
+const MAX_DELAY: Duration = Duration::from_secs(5);
+
+tokio::task_local! {
+    static REQUEST_DEADLINE: Instant;
+}
+
+/// Run a future with an overall deadline that retries will not sleep past
+pub async fn with_deadline<F: Future>(deadline: Instant, future: F) -> F::Output {
+    REQUEST_DEADLINE.scope(deadline, future).await
+}
+
+/// Outcome of a single attempt
+pub enum Attempt<T> {
+    /// Finished, successfully or with a failure retrying would not fix
+    Done(Result<T>),
+    /// Failed transiently; may be retried, optionally after a server-requested
+    /// delay. The outcome is returned as-is once retries are spent.
+    Retry(Result<T>, Option<Duration>),
+}
+
+/// Retry policy for idempotent backend requests
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Create a policy allowing up to `max_retries` retries after the first attempt
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base_delay: BASE_DELAY,
+            max_delay: MAX_DELAY,
+        }
+    }
+
+    /// Create the policy for a backend, falling back to the connection defaults
+    pub fn from_config(config: &BackendConfig) -> Self {
+        Self::new(
+            config
+                .retry_count
+                .unwrap_or_else(|| ConnectionDefaults::default().retry_count),
+        )
+    }
+
+    /// Override the backoff delays
+    pub fn with_delays(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Maximum number of retries after the first attempt
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Backoff before retry number `retry` (starting at 0), with full jitter
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+
+    /// Run `attempt` until it is done, retries are spent, or the next delay
+    /// would pass the deadline
+    ///
+    /// `attempt` receives the zero-based attempt number. The effective
+    /// deadline is the earlier of `deadline` and the one set by
+    /// [`with_deadline`]. If an attempt after a retry finds no healthy
+    /// target, the previous failure is returned instead, as it is the more
+    /// useful error.
+    pub async fn run<T, F, Fut>(&self, deadline: Instant, mut attempt: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Attempt<T>>,
+    {
+        let deadline = REQUEST_DEADLINE
+            .try_with(|request_deadline| deadline.min(*request_deadline))
+            .unwrap_or(deadline);
+        let mut previous: Option<Result<T>> = None;
+        let mut number = 0;
+
+        loop {
+            let span = info_span!("backend_attempt", attempt = number + 1, max_attempts = self.max_retries + 1);
+            let (outcome, retry_after) = match attempt(number).instrument(span).await {
+                Attempt::Done(Err(AppError::NoHealthyBackends(name))) => {
+                    return previous.unwrap_or(Err(AppError::NoHealthyBackends(name)));
+                }
+                Attempt::Done(outcome) => return outcome,
+                Attempt::Retry(outcome, retry_after) => (outcome, retry_after),
+            };
+
+            if number >= self.max_retries {
+                debug!(attempts = number + 1, "Retries exhausted");
+                return outcome;
+            }
+
+            let delay = retry_after.unwrap_or_else(|| self.backoff(number));
+            if Instant::now() + delay >= deadline {
+                debug!(attempts = number + 1, delay_ms = delay.as_millis() as u64, "Retry would pass the deadline");
+                return outcome;
+            }
+
+            warn!(
+                attempt = number + 1,
+                delay_ms = delay.as_millis() as u64,
+                error = %describe(&outcome),
+                "Transient backend failure, retrying"
+            );
+            tokio::time::sleep(delay).await;
+            previous = Some(outcome);
+            number += 1;
+        }
+    }
+}
+
+fn describe<T>(outcome: &Result<T>) -> String {
+    match outcome {
+        Ok(_) => "retryable response".to_string(),
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Whether an upstream HTTP status is a transient failure worth retrying
+///
+/// 529 is Anthropic's "overloaded" status, equivalent to 503.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 502 | 503 | 529)
+}
+
+/// Delay requested by a `Retry-After` header, as seconds or an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy::new(max_retries).with_delays(Duration::from_millis(1), Duration::from_millis(2))
+    }
+
+    fn far_deadline() -> Instant {
+        Instant::now() + Duration::from_secs(10)
+    }
+
+    #[test]
+    fn test_backoff_bounds() {
+        let policy = RetryPolicy::new(5);
+        for retry in 0..10 {
+            assert!(policy.backoff(retry) <= MAX_DELAY);
+        }
+        assert!(policy.backoff(0) <= BASE_DELAY);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        headers.insert(RETRY_AFTER, date.parse().unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = fast_policy(3)
+            .run(far_deadline(), |attempt| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                async move {
+                    if attempt < 2 {
+                        Attempt::Retry(Err(AppError::BackendError("unavailable".to_string())), None)
+                    } else {
+                        Attempt::Done(Ok(attempt))
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = fast_policy(2)
+            .run(far_deadline(), |_| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                async { Attempt::Retry(Err(AppError::BackendError("unavailable".to_string())), None) }
+            })
+            .await;
+
+        assert!(matches!(result, Err(AppError::BackendError(_))));
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_respects_deadline() {
+        let calls = AtomicU32::new(0);
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let result: Result<()> = fast_policy(5)
+            .run(deadline, |_| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                async { Attempt::Retry(Err(AppError::RateLimitExceeded), Some(Duration::from_secs(60))) }
+            })
+            .await;
+
+        assert!(matches!(result, Err(AppError::RateLimitExceeded)));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        // The request deadline applies even when the backend's is later
+        calls.store(0, Ordering::Relaxed);
+        let result: Result<()> = with_deadline(Instant::now(), async {
+            fast_policy(5)
+                .run(far_deadline(), |_| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    async { Attempt::Retry(Err(AppError::RateLimitExceeded), None) }
+                })
+                .await
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_keeps_previous_error_when_no_target_left() {
+        let result: Result<()> = fast_policy(3)
+            .run(far_deadline(), |attempt| async move {
+                if attempt == 0 {
+                    Attempt::Retry(Err(AppError::BackendError("connection refused".to_string())), None)
+                } else {
+                    Attempt::Done(Err(AppError::NoHealthyBackends("sd".to_string())))
+                }
+            })
+            .await;
+
+        assert!(matches!(result, Err(AppError::BackendError(_))));
+    }
+}
@@ -1,13 +1,153 @@
 //! Dynamic router for routing requests to appropriate backends
+//!
+//! [`RoutingRules`] applies the `routing` section of backends.yaml: model
+//! mappings pick a backend for a model name, and fallback chains list the
+//! backends to try next when that backend fails or is unhealthy.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::debug;
 
 use crate::backend::registry::BackendRegistry;
 use crate::backend::traits::ImageBackend;
+use crate::config::RoutingConfig;
 use crate::error::{AppError, Result};
 use crate::gateway::health_check::HealthCheckManager;
 
+/// Maximum number of alias hops followed when resolving a model mapping
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// Model-to-backend mappings and fallback chains
+///
+/// Mapping keys are exact model names or glob patterns (`*` matches any run
+/// of characters, `?` a single character). A mapping whose target is itself
+/// a mapped model name is an alias and is followed to the final backend,
+/// unless the target is already a registered backend.
+#[derive(Debug, Clone, Default)]
+pub struct RoutingRules {
+    exact: HashMap<String, String>,
+    /// Glob patterns, most specific (longest literal part) first
+    patterns: Vec<(String, String)>,
+    fallbacks: HashMap<String, Vec<String>>,
+}
+
+impl RoutingRules {
+    /// Build routing rules from the `routing` config section
+    pub fn from_config(config: &RoutingConfig) -> Self {
+        let (patterns, exact): (Vec<_>, Vec<_>) = config
+            .model_mappings
+            .iter()
+            .map(|(model, backend)| (model.clone(), backend.clone()))
+            .partition(|(model, _)| model.contains(['*', '?']));
+
+        let mut patterns = patterns;
+        patterns.sort_by(|(a, _), (b, _)| {
+            let literal = |p: &str| p.chars().filter(|c| !matches!(c, '*' | '?')).count();
+            literal(b).cmp(&literal(a)).then_with(|| a.cmp(b))
+        });
+
+        Self {
+            exact: exact.into_iter().collect(),
+            patterns,
+            fallbacks: config.fallbacks.clone(),
+        }
+    }
+
+    /// Whether a model name is mapped exactly, not just matched by a pattern
+    pub fn has_model(&self, model: &str) -> bool {
+        self.exact.contains_key(model)
+    }
+
+    /// Backend mapped to a model, following aliases
+    ///
+    /// `is_backend` tells whether a name is a registered backend; aliases are
+    /// not followed past one, so a backend stays reachable even when a model
+    /// of the same name is mapped elsewhere.
+    pub fn backend_for_model(&self, model: &str, is_backend: impl Fn(&str) -> bool) -> Option<&str> {
+        let mut target = self.lookup(model)?;
+        for _ in 0..MAX_ALIAS_DEPTH {
+            if is_backend(target) {
+                break;
+            }
+            match self.exact.get(target) {
+                Some(next) if next != target => target = next,
+                _ => break,
+            }
+        }
+        Some(target)
+    }
+
+    fn lookup(&self, model: &str) -> Option<&str> {
+        if let Some(backend) = self.exact.get(model) {
+            return Some(backend);
+        }
+        self.patterns
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, model))
+            .map(|(_, backend)| backend.as_str())
+    }
+
+    /// Fallback backends for a backend, in the order they should be tried
+    pub fn fallbacks(&self, backend: &str) -> Vec<String> {
+        let mut chain: Vec<String> = Vec::new();
+        for fallback in self.fallbacks.get(backend).into_iter().flatten() {
+            if fallback != backend && !chain.contains(fallback) {
+                chain.push(fallback.clone());
+            }
+        }
+        chain
+    }
+}
+
+/// Match a model name against a glob pattern with `*` and `?` wildcards
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether an error from one backend warrants trying a fallback backend
+///
+/// Client errors would fail the same way on any backend, so only upstream
+/// failures, timeouts and unavailable backends fail over.
+pub fn should_fail_over(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::BackendError(_)
+            | AppError::HttpClient(_)
+            | AppError::Timeout(_)
+            | AppError::RateLimitExceeded
+            | AppError::NoHealthyBackends(_)
+            | AppError::BackendNotFound(_)
+            | AppError::Grpc(_)
+    )
+}
+
 /// Router configuration
 #[derive(Debug, Clone)]
 pub struct RouterConfig {
@@ -140,16 +280,16 @@ impl Router {
             }
 
             // Check for common model patterns
-            if model_lower.contains("stable") || model_lower.contains("sd") {
-                if backend_name.contains("stable") || backend_name.contains("sd") {
-                    return Some(backend);
-                }
+            if (model_lower.contains("stable") || model_lower.contains("sd"))
+                && (backend_name.contains("stable") || backend_name.contains("sd"))
+            {
+                return Some(backend);
             }
 
-            if model_lower.contains("dall") {
-                if backend_name.contains("dall") || backend_name.contains("openai") {
-                    return Some(backend);
-                }
+            if model_lower.contains("dall")
+                && (backend_name.contains("dall") || backend_name.contains("openai"))
+            {
+                return Some(backend);
             }
         }
 
@@ -179,3 +319,112 @@ impl Router {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BackendConfig;
+    use crate::gateway::load_balancer::LoadBalancer;
+
+    fn rules(mappings: &[(&str, &str)], fallbacks: &[(&str, &[&str])]) -> RoutingRules {
+        RoutingRules::from_config(&RoutingConfig {
+            model_mappings: mappings
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            fallbacks: fallbacks
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect()))
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("gpt-4*", "gpt-4o-mini"));
+        assert!(glob_match("*llama*", "meta-llama/Llama-3-8B"));
+        assert!(glob_match("sd-?l", "sd-xl"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("gpt-4*", "gpt-3.5-turbo"));
+        assert!(!glob_match("sd-?l", "sd-xxl"));
+    }
+
+    #[test]
+    fn test_backend_for_model() {
+        let rules = rules(
+            &[
+                ("gpt-4o", "openai"),
+                ("gpt-*", "azure"),
+                ("gpt-4*", "openai-gpt4"),
+                ("sdxl", "sd-xl"),
+                ("sd-xl", "stable-diffusion"),
+            ],
+            &[],
+        );
+
+        let no_backends = |_: &str| false;
+        assert_eq!(rules.backend_for_model("gpt-4o", no_backends), Some("openai"));
+        assert_eq!(rules.backend_for_model("gpt-4-turbo", no_backends), Some("openai-gpt4"));
+        assert_eq!(rules.backend_for_model("gpt-3.5-turbo", no_backends), Some("azure"));
+        // Aliases resolve through other mappings
+        assert_eq!(rules.backend_for_model("sdxl", no_backends), Some("stable-diffusion"));
+        assert_eq!(rules.backend_for_model("claude-3", no_backends), None);
+    }
+
+    #[test]
+    fn test_aliases_stop_at_registered_backends() {
+        let rules = rules(&[("sdxl", "sd-xl"), ("sd-xl", "stable-diffusion-local")], &[]);
+        let is_backend = |name: &str| matches!(name, "sd-xl" | "stable-diffusion-local");
+
+        assert_eq!(rules.backend_for_model("sdxl", is_backend), Some("sd-xl"));
+        assert_eq!(rules.backend_for_model("sd-xl", is_backend), Some("stable-diffusion-local"));
+    }
+
+    #[test]
+    fn test_alias_cycle_terminates() {
+        let rules = rules(&[("a", "b"), ("b", "a")], &[]);
+        assert!(rules.backend_for_model("a", |_| false).is_some());
+    }
+
+    #[test]
+    fn test_fallbacks() {
+        let rules = rules(&[], &[("primary", &["second", "primary", "third", "second"])]);
+        assert_eq!(rules.fallbacks("primary"), vec!["second", "third"]);
+        assert!(rules.fallbacks("second").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_candidates_from_model_mappings() {
+        let registry = Arc::new(BackendRegistry::new());
+        for name in ["backend-1", "backend-2", "backend-3"] {
+            registry
+                .add_backend(BackendConfig {
+                    name: name.to_string(),
+                    endpoints: vec!["http://localhost:8001".to_string()],
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        let health = Arc::new(HealthCheckManager::new(registry.clone()));
+        let lb = LoadBalancer::new(registry).with_health_manager(health.clone());
+        lb.set_routing(rules(
+            &[("sd-*", "backend-1"), ("gpt-*", "openai-text")],
+            &[("backend-1", &["backend-2", "missing", "backend-3"])],
+        ));
+
+        assert_eq!(lb.candidates(None, Some("sd-xl")), vec!["backend-1", "backend-2", "backend-3"]);
+        // Explicit backends take precedence over mappings
+        assert_eq!(lb.candidates(Some("backend-3"), Some("sd-xl")), vec!["backend-3"]);
+        // Mappings to backends that are not image backends are ignored
+        assert!(lb.candidates(None, Some("gpt-4")).is_empty());
+        assert!(lb.candidates(None, None).is_empty());
+
+        // Unhealthy backends are tried last
+        for _ in 0..3 {
+            health.record_outcome("backend-1", false);
+        }
+        assert_eq!(lb.candidates(None, Some("sd-xl")), vec!["backend-2", "backend-3", "backend-1"]);
+    }
+}
@@ -8,6 +8,7 @@ pub mod backend;
 pub mod config;
 pub mod error;
 pub mod gateway;
+pub mod metrics;
 pub mod middleware;
 pub mod queue;
 pub mod response;
@@ -20,6 +21,8 @@ use tokio::sync::RwLock;
 use backend::registry::BackendRegistry;
 use backend::TextBackendRegistry;
 use gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer};
+use metrics::Metrics;
+use middleware::{auth::AuthLayer, rate_limit::RateLimitLayer};
 use queue::request_queue::RequestQueue;
 
 /// Application state shared across all handlers
@@ -30,5 +33,10 @@ pub struct AppState {
     pub load_balancer: Arc<LoadBalancer>,
     pub health_manager: Arc<HealthCheckManager>,
     pub request_queue: Arc<RequestQueue>,
+    pub metrics: Arc<Metrics>,
+    /// API key check for `/v1` routes, refreshed on config reload
+    pub auth: AuthLayer,
+    /// Rate limit for `/v1` routes, refreshed on config reload
+    pub rate_limit: RateLimitLayer,
 }
 
@@ -2,15 +2,19 @@
 
 use gen_serving_gateway::{
     api,
+    backend::multi_backend::register_multi_backend,
     backend::registry::BackendRegistry,
     backend::TextBackendRegistry,
-    config::{Settings, BackendType},
-    gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer},
-    queue::request_queue::RequestQueue,
+    config::{reload::ConfigReloader, Settings, BackendType, BACKENDS_CONFIG_PATH, GATEWAY_CONFIG_PATH},
+    gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer, router::RoutingRules},
+    metrics::Metrics,
+    middleware::{auth::AuthLayer, rate_limit::RateLimitLayer},
+    queue::request_queue::{QueueConfig, RequestQueue},
     AppState,
 };
 use rand::Rng;
 use std::sync::Arc;
+use std::time::Duration;
 use std::path::Path;
 use std::io::Write;
 use tokio::sync::RwLock;
@@ -109,18 +113,14 @@ async fn main() -> anyhow::Result<()> {
     
     // Load configuration
     let mut settings = Settings::load()?;
+    settings.validate()?;
     
-    // If API key was loaded/generated and auth is enabled but no keys configured, add it
+    // If API key was loaded/generated, use it alongside (or instead of) configured keys
     if let Some(key) = api_key {
         if settings.auth.enabled && settings.auth.api_keys.is_empty() {
-            settings.auth.api_keys.push(key.clone());
             info!("Using auto-configured API key for authentication");
-        } else if !settings.auth.api_keys.is_empty() {
-            // Add the env key to existing keys if not already present
-            if !settings.auth.api_keys.contains(&key) {
-                settings.auth.api_keys.push(key);
-            }
         }
+        settings.merge_api_key(key);
     }
     
     info!(
@@ -159,28 +159,61 @@ async fn main() -> anyhow::Result<()> {
                 );
             }
         }
+        
+        // Register multi-modal backends in both registries
+        for backend_config in config.backends.iter()
+            .filter(|b| b.backend_type == BackendType::Multi)
+        {
+            if let Err(e) = register_multi_backend(backend_config, &backend_registry, &text_registry) {
+                warn!(
+                    backend = %backend_config.name,
+                    error = %e,
+                    "Failed to register multi backend"
+                );
+            }
+        }
         info!("Registered {} text backends", text_registry.list_backends().await.len());
     }
     
-    // Initialize load balancer
-    let load_balancer = Arc::new(LoadBalancer::new(backend_registry.clone()));
-    
-    // Initialize health check manager
-    let health_manager = Arc::new(HealthCheckManager::new(backend_registry.clone()));
+    // Initialize health check manager covering both registries
+    let health_manager = Arc::new(
+        HealthCheckManager::new(backend_registry.clone()).with_text_registry(text_registry.clone()),
+    );
     
-    // Start health check background task
+    // Initialize load balancer, skipping backends the health manager reports as unhealthy
+    let load_balancer = Arc::new(
+        LoadBalancer::new(backend_registry.clone()).with_health_manager(health_manager.clone()),
+    );
     {
         let config = settings.read().await;
-        health_manager.start(config.backends.iter()
-            .map(|b| b.health_check_interval_secs)
-            .min()
-            .unwrap_or(30))
-            .await;
+        load_balancer.set_strategy(config.routing.strategy()?);
+        load_balancer.set_routing(RoutingRules::from_config(&config.routing));
+        text_registry.set_routing(RoutingRules::from_config(&config.routing));
+        load_balancer.set_fail_open(config.routing.fail_open);
     }
     
+    // Start health check background task; each backend runs on its own interval
+    health_manager.start().await;
+    
     // Initialize request queue
-    let request_queue = Arc::new(RequestQueue::new(load_balancer.clone()));
+    let queue_settings = settings.read().await.queue.clone();
+    let queue_config = QueueConfig {
+        job_retention: Duration::from_secs(queue_settings.job_retention_secs),
+        job_timeout: Duration::from_secs(queue_settings.job_timeout_secs),
+        ..QueueConfig::default()
+    };
+    let request_queue = Arc::new(RequestQueue::with_config(load_balancer.clone(), queue_config));
     
+    // Auth and rate limits are shared with the router so reloads can update them
+    let metrics = Arc::new(Metrics::new());
+    let (auth, rate_limit) = {
+        let config = settings.read().await;
+        (
+            AuthLayer::from_config(&config.auth),
+            RateLimitLayer::from_config(&config.rate_limit).with_metrics(metrics.clone()),
+        )
+    };
+
     // Create application state
     let app_state = Arc::new(AppState {
         settings: settings.clone(),
@@ -189,8 +222,14 @@ async fn main() -> anyhow::Result<()> {
         load_balancer,
         health_manager,
         request_queue,
+        metrics,
+        auth,
+        rate_limit,
     });
 
+    // Reload configuration when the files change or on SIGHUP
+    Arc::new(ConfigReloader::new(app_state.clone(), GATEWAY_CONFIG_PATH, BACKENDS_CONFIG_PATH)).spawn();
+
     // Build the router
     let app = api::routes::create_router(app_state.clone()).await;
 
@@ -0,0 +1,317 @@
+//! Prometheus metrics for the gateway
+//!
+//! Request counters and latency histograms are recorded by [`track_requests`],
+//! which handlers feed with backend and model labels through [`RequestLabels`].
+//! Queue and health gauges are refreshed from `AppState` when `/metrics` is
+//! scraped.
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, State},
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use parking_lot::Mutex;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::backend::Usage;
+use crate::queue::request_queue::QueueStats;
+
+/// Metric name prefix
+const NAMESPACE: &str = "img_serving";
+
+/// Latency buckets in seconds, sized for both chat requests and slow image generation
+const LATENCY_BUCKETS: &[f64] = &[
+    0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+/// Gateway metrics registry
+pub struct Metrics {
+    registry: Registry,
+    requests_total: IntCounterVec,
+    request_duration_seconds: HistogramVec,
+    queue_depth: IntGauge,
+    queue_in_flight: IntGauge,
+    backend_healthy: IntGaugeVec,
+    rate_limit_rejections_total: IntCounter,
+    tokens_total: IntCounterVec,
+}
+
+impl Metrics {
+    /// Create a new metrics registry with all gateway metrics registered
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let requests_total = IntCounterVec::new(
+            Opts::new("requests_total", "Total number of API requests").namespace(NAMESPACE),
+            &["route", "backend", "model", "status"],
+        )
+        .expect("valid metric");
+        let request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new("request_duration_seconds", "API request latency in seconds")
+                .namespace(NAMESPACE)
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["route", "backend", "model", "status"],
+        )
+        .expect("valid metric");
+        let queue_depth = IntGauge::with_opts(
+            Opts::new("queue_depth", "Image requests waiting for a processing slot").namespace(NAMESPACE),
+        )
+        .expect("valid metric");
+        let queue_in_flight = IntGauge::with_opts(
+            Opts::new("queue_in_flight", "Image requests currently being processed").namespace(NAMESPACE),
+        )
+        .expect("valid metric");
+        let backend_healthy = IntGaugeVec::new(
+            Opts::new("backend_healthy", "Backend health (1 = healthy, 0 = unhealthy)").namespace(NAMESPACE),
+            &["backend"],
+        )
+        .expect("valid metric");
+        let rate_limit_rejections_total = IntCounter::with_opts(
+            Opts::new("rate_limit_rejections_total", "Requests rejected by the rate limiter").namespace(NAMESPACE),
+        )
+        .expect("valid metric");
+        let tokens_total = IntCounterVec::new(
+            Opts::new("tokens_total", "Tokens processed by text backends").namespace(NAMESPACE),
+            &["backend", "model", "type"],
+        )
+        .expect("valid metric");
+
+        registry.register(Box::new(requests_total.clone())).expect("unique metric");
+        registry.register(Box::new(request_duration_seconds.clone())).expect("unique metric");
+        registry.register(Box::new(queue_depth.clone())).expect("unique metric");
+        registry.register(Box::new(queue_in_flight.clone())).expect("unique metric");
+        registry.register(Box::new(backend_healthy.clone())).expect("unique metric");
+        registry.register(Box::new(rate_limit_rejections_total.clone())).expect("unique metric");
+        registry.register(Box::new(tokens_total.clone())).expect("unique metric");
+
+        Self {
+            registry,
+            requests_total,
+            request_duration_seconds,
+            queue_depth,
+            queue_in_flight,
+            backend_healthy,
+            rate_limit_rejections_total,
+            tokens_total,
+        }
+    }
+
+    /// Record a completed API request
+    pub fn observe_request(&self, route: &str, backend: &str, model: &str, status: u16, seconds: f64) {
+        let status = status.to_string();
+        let labels = [route, backend, model, status.as_str()];
+        self.requests_total.with_label_values(&labels).inc();
+        self.request_duration_seconds.with_label_values(&labels).observe(seconds);
+    }
+
+    /// Record a request rejected by the rate limiter
+    pub fn record_rate_limited(&self) {
+        self.rate_limit_rejections_total.inc();
+    }
+
+    /// Record token usage reported by a text backend
+    pub fn record_usage(&self, backend: &str, model: &str, usage: &Usage) {
+        self.tokens_total
+            .with_label_values(&[backend, model, "prompt"])
+            .inc_by(u64::from(usage.prompt_tokens));
+        self.tokens_total
+            .with_label_values(&[backend, model, "completion"])
+            .inc_by(u64::from(usage.completion_tokens));
+    }
+
+    /// Update queue gauges from a queue snapshot
+    pub fn set_queue_stats(&self, stats: &QueueStats) {
+        self.queue_depth.set(stats.pending.saturating_sub(stats.in_flight) as i64);
+        self.queue_in_flight.set(stats.in_flight as i64);
+    }
+
+    /// Replace the backend health gauges
+    pub fn set_backend_health(&self, health: &[(String, bool)]) {
+        // Reset so removed backends stop being reported
+        self.backend_healthy.reset();
+        for (backend, healthy) in health {
+            self.backend_healthy.with_label_values(&[backend]).set(i64::from(*healthy));
+        }
+    }
+
+    /// Encode all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!(error = %e, "Failed to encode metrics");
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Label value standing in for model names the gateway does not know
+pub const OTHER_LABEL: &str = "other";
+
+/// Label value for a client-requested model
+///
+/// Only configured model names are used as labels; anything else is recorded
+/// as [`OTHER_LABEL`] so clients cannot create unbounded series.
+pub fn model_label(model: &str, configured: bool) -> &str {
+    if configured {
+        model
+    } else {
+        OTHER_LABEL
+    }
+}
+
+/// Backend and model labels for the current request, filled in by handlers
+///
+/// Handlers set only resolved backend names and labels from [`model_label`].
+#[derive(Debug, Clone, Default)]
+pub struct RequestLabels(Arc<Mutex<(Option<String>, Option<String>)>>);
+
+impl RequestLabels {
+    /// Set the backend that served the request
+    pub fn set_backend(&self, backend: impl Into<String>) {
+        self.0.lock().0 = Some(backend.into());
+    }
+
+    /// Set the model requested by the client
+    pub fn set_model(&self, model: impl Into<String>) {
+        self.0.lock().1 = Some(model.into());
+    }
+
+    pub(crate) fn get(&self) -> (String, String) {
+        let labels = self.0.lock();
+        (
+            labels.0.clone().unwrap_or_default(),
+            labels.1.clone().unwrap_or_default(),
+        )
+    }
+}
+
+/// Middleware recording request count and latency per matched route
+///
+/// Installed as a route layer so only matched routes are labelled, keeping
+/// label cardinality bounded. Streaming responses are timed to the first byte.
+pub async fn track_requests(
+    State(state): State<Arc<crate::AppState>>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    let labels = RequestLabels::default();
+    request.extensions_mut().insert(labels.clone());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let (backend, model) = labels.get();
+    state
+        .metrics
+        .observe_request(&route, &backend, &model, response.status().as_u16(), elapsed);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_text_exposition() {
+        let metrics = Metrics::new();
+        metrics.observe_request("/v1/chat/completions", "vllm", "llama3", 200, 0.3);
+        metrics.record_rate_limited();
+        metrics.record_usage("vllm", "llama3", &Usage {
+            prompt_tokens: 5,
+            completion_tokens: 7,
+            total_tokens: 12,
+        });
+        metrics.set_queue_stats(&QueueStats {
+            pending: 5,
+            in_flight: 2,
+            processed: 0,
+            max_queue_size: 10,
+            max_concurrent: 2,
+        });
+        metrics.set_backend_health(&[("sd".to_string(), false)]);
+
+        let output = metrics.render();
+
+        assert!(output.contains("# TYPE img_serving_requests_total counter"));
+        assert!(output.contains(
+            "img_serving_requests_total{backend=\"vllm\",model=\"llama3\",route=\"/v1/chat/completions\",status=\"200\"} 1"
+        ));
+        assert!(output.contains("# TYPE img_serving_request_duration_seconds histogram"));
+        assert!(output.contains("img_serving_rate_limit_rejections_total 1"));
+        assert!(output.contains("img_serving_tokens_total{backend=\"vllm\",model=\"llama3\",type=\"completion\"} 7"));
+        assert!(output.contains("img_serving_queue_depth 3"));
+        assert!(output.contains("img_serving_queue_in_flight 2"));
+        assert!(output.contains("img_serving_backend_healthy{backend=\"sd\"} 0"));
+    }
+
+    #[test]
+    fn test_backend_health_reset() {
+        let metrics = Metrics::new();
+        metrics.set_backend_health(&[("a".to_string(), true), ("b".to_string(), true)]);
+        metrics.set_backend_health(&[("a".to_string(), true)]);
+
+        let output = metrics.render();
+        assert!(output.contains("img_serving_backend_healthy{backend=\"a\"} 1"));
+        assert!(!output.contains("backend=\"b\""));
+    }
+    #[tokio::test]
+    async fn test_track_requests_uses_matched_route() {
+        use crate::backend::{registry::BackendRegistry, TextBackendRegistry};
+        use crate::gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer};
+        use crate::queue::request_queue::RequestQueue;
+        use axum::{middleware::from_fn_with_state, routing::get, Router};
+        use tower::ServiceExt;
+
+        let backend_registry = Arc::new(BackendRegistry::new());
+        let load_balancer = Arc::new(LoadBalancer::new(backend_registry.clone()));
+        let state = Arc::new(crate::AppState {
+            settings: Arc::new(tokio::sync::RwLock::new(crate::config::Settings::default())),
+            backend_registry: backend_registry.clone(),
+            text_registry: Arc::new(TextBackendRegistry::new()),
+            load_balancer: load_balancer.clone(),
+            health_manager: Arc::new(HealthCheckManager::new(backend_registry)),
+            request_queue: Arc::new(RequestQueue::new(load_balancer)),
+            metrics: Arc::new(Metrics::new()),
+            auth: crate::middleware::auth::AuthLayer::new(vec![]),
+            rate_limit: crate::middleware::rate_limit::RateLimitLayer::new(100, 200),
+        });
+
+        let handler = |axum::Extension(labels): axum::Extension<RequestLabels>| async move {
+            labels.set_backend("sd");
+            labels.set_model("sdxl");
+            "ok"
+        };
+        let app = Router::new()
+            .nest("/v1", Router::new().route("/items/:id", get(handler)))
+            .route_layer(from_fn_with_state(state.clone(), track_requests))
+            .with_state(state.clone());
+
+        let request = Request::builder().uri("/v1/items/42").body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap();
+
+        assert!(state.metrics.render().contains(
+            "img_serving_requests_total{backend=\"sd\",model=\"sdxl\",route=\"/v1/items/:id\",status=\"200\"} 1"
+        ));
+    }
+}
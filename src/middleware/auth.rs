@@ -7,6 +7,7 @@ use axum::{
     Json,
 };
 use futures::future::BoxFuture;
+use parking_lot::RwLock;
 use serde::Serialize;
 use std::{
     collections::HashSet,
@@ -16,6 +17,8 @@ use std::{
 use tower::{Layer, Service};
 use tracing::warn;
 
+use crate::config::AuthConfig;
+
 /// Authentication error response
 #[derive(Serialize)]
 struct AuthError {
@@ -30,17 +33,38 @@ struct AuthErrorDetail {
 }
 
 /// Authentication layer
+///
+/// The key set is shared with every service the layer creates, so
+/// [`set_api_keys`](Self::set_api_keys) takes effect on live routes.
 #[derive(Clone)]
 pub struct AuthLayer {
-    api_keys: Arc<HashSet<String>>,
+    api_keys: Arc<RwLock<HashSet<String>>>,
 }
 
 impl AuthLayer {
     pub fn new(api_keys: Vec<String>) -> Self {
         Self {
-            api_keys: Arc::new(api_keys.into_iter().collect()),
+            api_keys: Arc::new(RwLock::new(api_keys.into_iter().collect())),
         }
     }
+
+    /// Create a layer from configuration; disabled auth lets every request through
+    pub fn from_config(config: &AuthConfig) -> Self {
+        let layer = Self::new(Vec::new());
+        layer.update(config);
+        layer
+    }
+
+    /// Replace the accepted API keys
+    pub fn set_api_keys(&self, api_keys: Vec<String>) {
+        *self.api_keys.write() = api_keys.into_iter().collect();
+    }
+
+    /// Apply an auth configuration; an empty key set disables authentication
+    pub fn update(&self, config: &AuthConfig) {
+        let api_keys = if config.enabled { config.api_keys.clone() } else { Vec::new() };
+        self.set_api_keys(api_keys);
+    }
 }
 
 impl<S> Layer<S> for AuthLayer {
@@ -58,7 +82,7 @@ impl<S> Layer<S> for AuthLayer {
 #[derive(Clone)]
 pub struct AuthMiddleware<S> {
     inner: S,
-    api_keys: Arc<HashSet<String>>,
+    api_keys: Arc<RwLock<HashSet<String>>>,
 }
 
 impl<S> Service<Request<Body>> for AuthMiddleware<S>
@@ -79,7 +103,7 @@ where
         let path = request.uri().path();
         if path == "/health" || path == "/metrics" {
             let future = self.inner.call(request);
-            return Box::pin(async move { future.await });
+            return Box::pin(future);
         }
 
         // Extract API key from Authorization header
@@ -88,25 +112,30 @@ where
             .get(AUTHORIZATION)
             .and_then(|h| h.to_str().ok());
 
-        let api_key = auth_header.and_then(|h| {
+        let api_key = auth_header.map(|h| {
             if h.starts_with("Bearer ") {
-                Some(h.trim_start_matches("Bearer ").to_string())
+                h.trim_start_matches("Bearer ").to_string()
             } else {
-                Some(h.to_string())
+                h.to_string()
             }
         });
 
+        let (no_keys, valid) = {
+            let api_keys = self.api_keys.read();
+            (api_keys.is_empty(), api_key.as_ref().is_some_and(|key| api_keys.contains(key)))
+        };
+
         // If no API keys are configured, allow all requests
-        if self.api_keys.is_empty() {
+        if no_keys {
             let future = self.inner.call(request);
-            return Box::pin(async move { future.await });
+            return Box::pin(future);
         }
 
         // Validate API key
         match api_key {
-            Some(key) if self.api_keys.contains(&key) => {
+            Some(_) if valid => {
                 let future = self.inner.call(request);
-                Box::pin(async move { future.await })
+                Box::pin(future)
             }
             Some(_) => {
                 warn!("Invalid API key provided");
@@ -145,7 +174,24 @@ mod tests {
     #[test]
     fn test_auth_layer_creation() {
         let layer = AuthLayer::new(vec!["test-key".to_string()]);
-        assert!(layer.api_keys.contains("test-key"));
+        assert!(layer.api_keys.read().contains("test-key"));
+    }
+
+    #[test]
+    fn test_auth_layer_update() {
+        let mut config = AuthConfig {
+            enabled: true,
+            api_keys: vec!["new-key".to_string()],
+            bypass_paths: vec![],
+        };
+        let layer = AuthLayer::new(vec!["test-key".to_string()]);
+        layer.update(&config);
+        assert!(layer.api_keys.read().contains("new-key"));
+        assert!(!layer.api_keys.read().contains("test-key"));
+
+        config.enabled = false;
+        layer.update(&config);
+        assert!(layer.api_keys.read().is_empty());
     }
 }
 
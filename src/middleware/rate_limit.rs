@@ -13,6 +13,7 @@ use governor::{
     state::{InMemoryState, NotKeyed},
     Quota, RateLimiter,
 };
+use parking_lot::RwLock;
 use serde::Serialize;
 use std::{
     num::NonZeroU32,
@@ -22,6 +23,9 @@ use std::{
 use tower::{Layer, Service};
 use tracing::warn;
 
+use crate::config::RateLimitConfig;
+use crate::metrics::Metrics;
+
 /// Rate limit error response
 #[derive(Serialize)]
 struct RateLimitError {
@@ -38,22 +42,56 @@ struct RateLimitErrorDetail {
 type SharedRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>>;
 
 /// Rate limiting layer
+///
+/// The limiter is shared with every service the layer creates, so
+/// [`update`](Self::update) takes effect on live routes.
 #[derive(Clone)]
 pub struct RateLimitLayer {
-    limiter: SharedRateLimiter,
+    limiter: Arc<RwLock<Option<SharedRateLimiter>>>,
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl RateLimitLayer {
     pub fn new(requests_per_second: u32, burst_size: u32) -> Self {
-        let quota = Quota::per_second(NonZeroU32::new(requests_per_second).unwrap_or(NonZeroU32::new(100).unwrap()))
-            .allow_burst(NonZeroU32::new(burst_size).unwrap_or(NonZeroU32::new(200).unwrap()));
-        
-        let limiter = Arc::new(RateLimiter::direct(quota));
-        
-        Self { limiter }
+        Self {
+            limiter: Arc::new(RwLock::new(Some(create_limiter(requests_per_second, burst_size)))),
+            metrics: None,
+        }
+    }
+
+    /// Create a layer from configuration; a disabled limit lets every request through
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self {
+            limiter: Arc::new(RwLock::new(
+                config
+                    .enabled
+                    .then(|| create_limiter(config.requests_per_second, config.burst_size)),
+            )),
+            metrics: None,
+        }
+    }
+
+    /// Count rejected requests in the given metrics registry
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Replace the quota, starting from a full burst
+    pub fn update(&self, config: &RateLimitConfig) {
+        *self.limiter.write() = config
+            .enabled
+            .then(|| create_limiter(config.requests_per_second, config.burst_size));
     }
 }
 
+fn create_limiter(requests_per_second: u32, burst_size: u32) -> SharedRateLimiter {
+    let quota = Quota::per_second(NonZeroU32::new(requests_per_second).unwrap_or(NonZeroU32::new(100).unwrap()))
+        .allow_burst(NonZeroU32::new(burst_size).unwrap_or(NonZeroU32::new(200).unwrap()));
+    
+    Arc::new(RateLimiter::direct(quota))
+}
+
 impl<S> Layer<S> for RateLimitLayer {
     type Service = RateLimitMiddleware<S>;
 
@@ -61,6 +99,7 @@ impl<S> Layer<S> for RateLimitLayer {
         RateLimitMiddleware {
             inner,
             limiter: self.limiter.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -69,7 +108,8 @@ impl<S> Layer<S> for RateLimitLayer {
 #[derive(Clone)]
 pub struct RateLimitMiddleware<S> {
     inner: S,
-    limiter: SharedRateLimiter,
+    limiter: Arc<RwLock<Option<SharedRateLimiter>>>,
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl<S> Service<Request<Body>> for RateLimitMiddleware<S>
@@ -90,17 +130,24 @@ where
         let path = request.uri().path();
         if path == "/health" || path == "/metrics" {
             let future = self.inner.call(request);
-            return Box::pin(async move { future.await });
+            return Box::pin(future);
         }
 
+        let Some(limiter) = self.limiter.read().clone() else {
+            return Box::pin(self.inner.call(request));
+        };
+
         // Check rate limit
-        match self.limiter.check() {
+        match limiter.check() {
             Ok(_) => {
                 let future = self.inner.call(request);
-                Box::pin(async move { future.await })
+                Box::pin(future)
             }
             Err(_) => {
                 warn!("Rate limit exceeded");
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_rate_limited();
+                }
                 Box::pin(async move {
                     Ok(create_rate_limit_error_response())
                 })
@@ -129,7 +176,24 @@ mod tests {
     fn test_rate_limit_layer_creation() {
         let layer = RateLimitLayer::new(100, 200);
         // Should not panic
-        assert!(layer.limiter.check().is_ok());
+        assert!(layer.limiter.read().as_ref().unwrap().check().is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_update() {
+        let mut config = RateLimitConfig {
+            enabled: true,
+            requests_per_second: 1,
+            burst_size: 1,
+        };
+        let layer = RateLimitLayer::from_config(&config);
+        let limiter = layer.limiter.read().clone().unwrap();
+        assert!(limiter.check().is_ok());
+        assert!(limiter.check().is_err());
+
+        config.enabled = false;
+        layer.update(&config);
+        assert!(layer.limiter.read().is_none());
     }
 }
 
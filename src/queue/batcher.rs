@@ -168,6 +168,7 @@ impl Batcher {
             results.push(GenerateResponse {
                 images,
                 model: response.model.clone(),
+                backend: response.backend.clone(),
             });
 
             image_index += n;
@@ -0,0 +1,366 @@
+//! Asynchronous image jobs
+//!
+//! A [`JobTable`] tracks requests submitted without waiting for the result.
+//! Each job moves from queued to running to a final state; finished jobs are
+//! kept for the retention period so clients can poll for them, then dropped
+//! by a periodic sweep.
+//! Jobs have their own deadline, separate from the synchronous request
+//! timeout, since nobody holds a connection open while they run.
+
+use chrono::Utc;
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use tokio::time::Instant;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::backend::traits::{GenerateResponse, GenerationProgress};
+use crate::error::Result;
+
+/// State of an image job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for a processing slot
+    Queued,
+    /// Being generated by a backend
+    Running,
+    /// Finished with images
+    Succeeded,
+    /// Finished with an error
+    Failed,
+    /// Stopped at the client's request
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job has reached a final state
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled)
+    }
+
+    /// Name of the status as serialized
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Snapshot of an image job
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: String,
+    pub status: JobStatus,
+    /// Unix timestamps of the state changes
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    /// Latest progress reported by the backend, while running
+    pub progress: Option<GenerationProgress>,
+    /// Generated images, once succeeded
+    pub response: Option<GenerateResponse>,
+    /// Failure message, once failed
+    pub error: Option<String>,
+}
+
+struct JobEntry {
+    job: Job,
+    /// When a finished job is dropped
+    expires: Option<Instant>,
+    /// Task waiting for the result, aborted on cancellation
+    task: Option<AbortHandle>,
+    /// Ticket of the request in the queue's waiting list
+    ticket: Option<u64>,
+}
+
+impl JobEntry {
+    /// Whether the job is unfinished or still within its retention
+    // `Option::is_none_or` would need Rust 1.82
+    #[allow(clippy::unnecessary_map_or)]
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires.map_or(true, |expires| expires > now)
+    }
+}
+
+/// Table of image jobs by id
+pub struct JobTable {
+    jobs: DashMap<String, JobEntry>,
+    retention: RwLock<Duration>,
+    timeout: RwLock<Duration>,
+}
+
+impl JobTable {
+    /// Create a table keeping finished jobs for `retention`, with jobs
+    /// failing once they take longer than `timeout`
+    pub fn new(retention: Duration, timeout: Duration) -> Self {
+        Self {
+            jobs: DashMap::new(),
+            retention: RwLock::new(retention),
+            timeout: RwLock::new(timeout),
+        }
+    }
+
+    /// Change how long finished jobs are kept; applies to jobs finishing later
+    pub fn set_retention(&self, retention: Duration) {
+        *self.retention.write() = retention;
+    }
+
+    /// Deadline for jobs, from submission to result
+    pub fn timeout(&self) -> Duration {
+        *self.timeout.read()
+    }
+
+    /// Change the job deadline; applies to jobs submitted later
+    pub fn set_timeout(&self, timeout: Duration) {
+        *self.timeout.write() = timeout;
+    }
+
+    /// Add a queued job
+    pub fn create(&self) -> Job {
+        self.prune();
+        let job = Job {
+            id: format!("imgjob-{}", Uuid::new_v4().simple()),
+            status: JobStatus::Queued,
+            created_at: Utc::now().timestamp(),
+            started_at: None,
+            finished_at: None,
+            progress: None,
+            response: None,
+            error: None,
+        };
+        self.jobs.insert(
+            job.id.clone(),
+            JobEntry {
+                job: job.clone(),
+                expires: None,
+                task: None,
+                ticket: None,
+            },
+        );
+        job
+    }
+
+    /// Remember the task producing a job's result, for cancellation, and
+    /// the job's ticket in the queue
+    ///
+    /// A job cancelled before its task was attached has the task aborted
+    /// straight away.
+    pub fn attach(&self, id: &str, task: AbortHandle, ticket: u64) {
+        match self.jobs.get_mut(id) {
+            Some(mut entry) if !entry.job.status.is_finished() => {
+                entry.task = Some(task);
+                entry.ticket = Some(ticket);
+            }
+            _ => task.abort(),
+        }
+    }
+
+    /// Queue ticket of a job
+    pub fn ticket(&self, id: &str) -> Option<u64> {
+        self.jobs.get(id)?.ticket
+    }
+
+    /// Look up a job
+    ///
+    /// Only the job itself is checked for expiry; the rest of the table is
+    /// pruned when jobs are created and by [`spawn_pruning`](Self::spawn_pruning).
+    pub fn get(&self, id: &str) -> Option<Job> {
+        let now = Instant::now();
+        {
+            let entry = self.jobs.get(id)?;
+            if entry.is_live(now) {
+                return Some(entry.job.clone());
+            }
+        }
+        self.jobs.remove(id);
+        None
+    }
+
+    /// Drop a job that never ran
+    pub fn remove(&self, id: &str) {
+        self.jobs.remove(id);
+    }
+
+    /// Mark a queued job as running
+    pub fn start(&self, id: &str) {
+        if let Some(mut entry) = self.jobs.get_mut(id) {
+            if entry.job.status == JobStatus::Queued {
+                entry.job.status = JobStatus::Running;
+                entry.job.started_at = Some(Utc::now().timestamp());
+            }
+        }
+    }
+
+    /// Record backend progress of a running job
+    pub fn set_progress(&self, id: &str, progress: GenerationProgress) {
+        if let Some(mut entry) = self.jobs.get_mut(id) {
+            if entry.job.status == JobStatus::Running {
+                entry.job.progress = Some(progress);
+            }
+        }
+    }
+
+    /// Record a job's result, unless it was cancelled first
+    pub fn finish(&self, id: &str, result: Result<GenerateResponse>) {
+        let retention = *self.retention.read();
+        if let Some(mut entry) = self.jobs.get_mut(id) {
+            if entry.job.status.is_finished() {
+                return;
+            }
+            match result {
+                Ok(response) => {
+                    entry.job.status = JobStatus::Succeeded;
+                    entry.job.response = Some(response);
+                }
+                Err(e) => {
+                    entry.job.status = JobStatus::Failed;
+                    entry.job.error = Some(e.to_string());
+                }
+            }
+            entry.job.finished_at = Some(Utc::now().timestamp());
+            entry.job.progress = None;
+            entry.expires = Some(Instant::now() + retention);
+            entry.task = None;
+        }
+    }
+
+    /// Cancel a queued or running job
+    ///
+    /// Finished jobs are returned unchanged; `None` for unknown jobs.
+    pub fn cancel(&self, id: &str) -> Option<Job> {
+        let retention = *self.retention.read();
+        let mut entry = self.jobs.get_mut(id)?;
+        if !entry.job.status.is_finished() {
+            if let Some(task) = entry.task.take() {
+                task.abort();
+            }
+            entry.job.status = JobStatus::Cancelled;
+            entry.job.progress = None;
+            entry.job.finished_at = Some(Utc::now().timestamp());
+            entry.expires = Some(Instant::now() + retention);
+        }
+        Some(entry.job.clone())
+    }
+
+    /// Drop finished jobs past their retention every `interval`, so results
+    /// do not pile up while no new jobs come in
+    ///
+    /// The task stops when the table is dropped.
+    pub fn spawn_pruning(self: &Arc<Self>, interval: Duration) {
+        let table: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(interval);
+            loop {
+                tick.tick().await;
+                let Some(table) = table.upgrade() else {
+                    break;
+                };
+                table.prune();
+            }
+        });
+    }
+
+    /// Drop finished jobs past their retention
+    fn prune(&self) {
+        let now = Instant::now();
+        self.jobs.retain(|_, entry| entry.is_live(now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let table = JobTable::new(Duration::from_secs(60), Duration::from_secs(60));
+
+        let job = table.create();
+        assert_eq!(job.status, JobStatus::Queued);
+        let progress = GenerationProgress {
+            step: 5,
+            total_steps: 20,
+            eta_secs: Some(1.5),
+            preview: None,
+        };
+        // Progress only applies while running
+        table.set_progress(&job.id, progress.clone());
+        assert!(table.get(&job.id).unwrap().progress.is_none());
+        table.start(&job.id);
+        table.set_progress(&job.id, progress.clone());
+        let running = table.get(&job.id).unwrap();
+        assert_eq!(running.status, JobStatus::Running);
+        assert_eq!(running.progress, Some(progress));
+
+        table.finish(&job.id, Err(AppError::BackendError("out of memory".to_string())));
+        let failed = table.get(&job.id).unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("Backend error: out of memory"));
+        assert!(failed.progress.is_none());
+
+        // Cancelling a finished job changes nothing, and a late result is ignored
+        let cancelled = table.create();
+        assert_eq!(table.cancel(&cancelled.id).unwrap().status, JobStatus::Cancelled);
+        table.finish(
+            &cancelled.id,
+            Ok(GenerateResponse {
+                images: vec![],
+                model: None,
+                backend: None,
+            }),
+        );
+        assert_eq!(table.get(&cancelled.id).unwrap().status, JobStatus::Cancelled);
+        assert_eq!(table.cancel(&job.id).unwrap().status, JobStatus::Failed);
+        assert!(table.cancel("imgjob-unknown").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_finished_jobs_expire() {
+        let table = JobTable::new(Duration::from_secs(60), Duration::from_secs(60));
+        let finished = table.create();
+        let queued = table.create();
+        table.set_retention(Duration::ZERO);
+        table.finish(&finished.id, Err(AppError::Timeout("slow".to_string())));
+
+        assert!(table.get(&finished.id).is_none());
+        // Unfinished jobs never expire
+        assert!(table.get(&queued.id).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_pruning_drops_expired_jobs_without_polls() {
+        let table = Arc::new(JobTable::new(Duration::ZERO, Duration::from_secs(60)));
+        let finished = table.create();
+        let queued = table.create();
+        table.finish(&finished.id, Err(AppError::Timeout("slow".to_string())));
+        assert_eq!(table.jobs.len(), 2);
+
+        table.spawn_pruning(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!table.jobs.contains_key(&finished.id));
+        assert!(table.jobs.contains_key(&queued.id));
+    }
+
+    #[tokio::test]
+    async fn test_attach_after_cancel_aborts() {
+        let table = JobTable::new(Duration::from_secs(60), Duration::from_secs(60));
+        let job = table.create();
+        // A cancellation can arrive before the waiting task is attached
+        table.cancel(&job.id);
+
+        let task = tokio::spawn(std::future::pending::<()>());
+        table.attach(&job.id, task.abort_handle(), 0);
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert!(table.ticket(&job.id).is_none());
+    }
+}
@@ -1,5 +1,6 @@
 //! Queue module - Request queue and batch processing
 
 pub mod batcher;
+pub mod jobs;
 pub mod request_queue;
 
@@ -1,22 +1,95 @@
 //! Asynchronous request queue for managing image generation requests
 
+use parking_lot::Mutex;
+use std::collections::{BTreeSet, VecDeque};
+use std::ops::Deref;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, oneshot, Semaphore};
-use tracing::debug;
+use tokio::time::Instant;
+use tracing::{debug, warn};
 
-use crate::backend::traits::{GenerateRequest, GenerateResponse};
+use crate::backend::traits::{GenerateRequest, GenerateResponse, ImageBackend, ProgressReporter};
 use crate::error::{AppError, Result};
 use crate::gateway::load_balancer::LoadBalancer;
+use crate::gateway::retry::with_deadline;
+use crate::gateway::router::should_fail_over;
+use crate::queue::jobs::{Job, JobTable};
+
+/// How often expired job results are swept from the job table
+const JOB_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Request with its response channel
 struct QueuedRequest {
     request: GenerateRequest,
     backend_name: Option<String>,
+    /// When the submitter stops waiting for a response
+    deadline: Instant,
+    /// Job tracking the request, when submitted asynchronously
+    job: Option<String>,
+    /// Place in line until a processing slot is free
+    ticket: WaitTicket,
     response_tx: oneshot::Sender<Result<GenerateResponse>>,
 }
 
+/// Counts a request until dropped, so abandoned requests are not leaked
+struct CountGuard<C: Deref<Target = AtomicU64>>(C);
+
+impl<C: Deref<Target = AtomicU64>> CountGuard<C> {
+    fn new(counter: C) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl<C: Deref<Target = AtomicU64>> Drop for CountGuard<C> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+type PendingGuard = CountGuard<Arc<AtomicU64>>;
+
+/// Requests waiting for a processing slot, in arrival order
+#[derive(Default)]
+struct WaitingList {
+    next: AtomicU64,
+    waiting: Mutex<BTreeSet<u64>>,
+}
+
+impl WaitingList {
+    /// Join the end of the line
+    fn join(self: &Arc<Self>) -> WaitTicket {
+        let number = self.next.fetch_add(1, Ordering::Relaxed);
+        self.waiting.lock().insert(number);
+        WaitTicket {
+            list: self.clone(),
+            number,
+        }
+    }
+
+    /// Number of requests ahead of a ticket, or `None` once it left the line
+    fn position(&self, number: u64) -> Option<usize> {
+        let waiting = self.waiting.lock();
+        waiting
+            .contains(&number)
+            .then(|| waiting.range(..number).count())
+    }
+}
+
+/// Place in the [`WaitingList`], given up when dropped
+struct WaitTicket {
+    list: Arc<WaitingList>,
+    number: u64,
+}
+
+impl Drop for WaitTicket {
+    fn drop(&mut self) {
+        self.list.waiting.lock().remove(&self.number);
+    }
+}
+
 /// Configuration for the request queue
 #[derive(Debug, Clone)]
 pub struct QueueConfig {
@@ -26,6 +99,10 @@ pub struct QueueConfig {
     pub max_concurrent: usize,
     /// Request timeout in milliseconds
     pub timeout_ms: u64,
+    /// How long finished jobs are kept for polling
+    pub job_retention: Duration,
+    /// Deadline for asynchronous jobs, in place of `timeout_ms`
+    pub job_timeout: Duration,
 }
 
 impl Default for QueueConfig {
@@ -34,6 +111,8 @@ impl Default for QueueConfig {
             max_queue_size: 1000,
             max_concurrent: 10,
             timeout_ms: 120000, // 2 minutes
+            job_retention: Duration::from_secs(3600),
+            job_timeout: Duration::from_secs(3600),
         }
     }
 }
@@ -44,8 +123,11 @@ pub struct RequestQueue {
     load_balancer: Arc<LoadBalancer>,
     request_tx: mpsc::Sender<QueuedRequest>,
     config: QueueConfig,
-    pending_count: AtomicU64,
-    processed_count: AtomicU64,
+    jobs: Arc<JobTable>,
+    waiting: Arc<WaitingList>,
+    pending_count: Arc<AtomicU64>,
+    in_flight_count: Arc<AtomicU64>,
+    processed_count: Arc<AtomicU64>,
 }
 
 impl RequestQueue {
@@ -59,28 +141,109 @@ impl RequestQueue {
         let (request_tx, request_rx) = mpsc::channel(config.max_queue_size);
         let semaphore = Arc::new(Semaphore::new(config.max_concurrent));
         let lb = load_balancer.clone();
-        let timeout_ms = config.timeout_ms;
+        let in_flight_count = Arc::new(AtomicU64::new(0));
+        let processed_count = Arc::new(AtomicU64::new(0));
+        let counters = (in_flight_count.clone(), processed_count.clone());
+        let jobs = Arc::new(JobTable::new(config.job_retention, config.job_timeout));
+        jobs.spawn_pruning(JOB_PRUNE_INTERVAL);
+        let worker_jobs = jobs.clone();
 
         // Start the worker task
         tokio::spawn(async move {
-            Self::process_requests(request_rx, lb, semaphore, timeout_ms).await;
+            Self::process_requests(request_rx, lb, semaphore, worker_jobs, counters).await;
         });
 
         Self {
             load_balancer,
             request_tx,
             config,
-            pending_count: AtomicU64::new(0),
-            processed_count: AtomicU64::new(0),
+            jobs,
+            waiting: Arc::default(),
+            pending_count: Arc::new(AtomicU64::new(0)),
+            in_flight_count,
+            processed_count,
         }
     }
 
-    /// Submit a request to the queue
+    /// Submit a request to the queue and wait for the result
     pub async fn submit(
         &self,
         request: GenerateRequest,
         backend_name: Option<&str>,
     ) -> Result<GenerateResponse> {
+        let (response_rx, _pending, _) = self.enqueue(request, backend_name, None, self.timeout()).await?;
+        self.wait(response_rx).await
+    }
+
+    /// Submit a request as a job, returning without waiting for the result
+    ///
+    /// The result is recorded in the job table and kept for the configured
+    /// retention after the job finishes. Jobs run against the job deadline
+    /// rather than the request timeout.
+    pub async fn submit_job(
+        &self,
+        request: GenerateRequest,
+        backend_name: Option<&str>,
+    ) -> Result<Job> {
+        let job = self.jobs.create();
+        let timeout = self.jobs.timeout();
+        let (response_rx, pending, ticket) = match self.enqueue(request, backend_name, Some(job.id.clone()), timeout).await {
+            Ok(queued) => queued,
+            Err(e) => {
+                self.jobs.remove(&job.id);
+                return Err(e);
+            }
+        };
+
+        let jobs = self.jobs.clone();
+        let id = job.id.clone();
+        let task = tokio::spawn(async move {
+            let _pending = pending;
+            let result = Self::wait_for(response_rx, timeout).await;
+            jobs.finish(&id, result);
+        });
+        self.jobs.attach(&job.id, task.abort_handle(), ticket);
+
+        debug!(job = %job.id, "Job queued");
+        Ok(job)
+    }
+
+    /// Look up a job
+    pub fn job(&self, id: &str) -> Option<Job> {
+        self.jobs.get(id)
+    }
+
+    /// Number of requests ahead of a queued job, or `None` once it runs
+    pub fn queue_position(&self, id: &str) -> Option<usize> {
+        self.waiting.position(self.jobs.ticket(id)?)
+    }
+
+    /// Cancel a queued or running job, stopping its backend request
+    pub fn cancel_job(&self, id: &str) -> Option<Job> {
+        self.jobs.cancel(id)
+    }
+
+    /// Change how long finished jobs are kept
+    pub fn set_job_retention(&self, retention: Duration) {
+        self.jobs.set_retention(retention);
+    }
+
+    /// Change the deadline of jobs submitted from now on
+    pub fn set_job_timeout(&self, timeout: Duration) {
+        self.jobs.set_timeout(timeout);
+    }
+
+    /// Put a request on the queue; it counts as pending until the guard drops
+    ///
+    /// Generation stops at `timeout` from now. Also returns the request's
+    /// ticket in the waiting list.
+    async fn enqueue(
+        &self,
+        request: GenerateRequest,
+        backend_name: Option<&str>,
+        job: Option<String>,
+        timeout: Duration,
+    ) -> Result<(oneshot::Receiver<Result<GenerateResponse>>, PendingGuard, u64)> {
         // Check if queue is full
         let pending = self.pending_count.load(Ordering::Relaxed);
         if pending >= self.config.max_queue_size as u64 {
@@ -89,15 +252,20 @@ impl RequestQueue {
 
         // Create response channel
         let (response_tx, response_rx) = oneshot::channel();
+        let ticket = self.waiting.join();
+        let number = ticket.number;
 
         let queued_request = QueuedRequest {
             request,
             backend_name: backend_name.map(String::from),
+            deadline: Instant::now() + timeout,
+            job,
+            ticket,
             response_tx,
         };
 
-        // Increment pending count
-        self.pending_count.fetch_add(1, Ordering::Relaxed);
+        // Count as pending until the response arrives or the submitter gives up
+        let guard = CountGuard::new(self.pending_count.clone());
 
         // Send to queue
         self.request_tx
@@ -106,86 +274,206 @@ impl RequestQueue {
             .map_err(|_| AppError::Internal("Failed to queue request".to_string()))?;
 
         debug!(pending = pending + 1, "Request queued");
+        Ok((response_rx, guard, number))
+    }
+
+    async fn wait(&self, response_rx: oneshot::Receiver<Result<GenerateResponse>>) -> Result<GenerateResponse> {
+        Self::wait_for(response_rx, self.timeout()).await
+    }
 
-        // Wait for response with timeout
-        let timeout = Duration::from_millis(self.config.timeout_ms);
+    /// Wait for a queued request's response with timeout
+    async fn wait_for(
+        response_rx: oneshot::Receiver<Result<GenerateResponse>>,
+        timeout: Duration,
+    ) -> Result<GenerateResponse> {
         match tokio::time::timeout(timeout, response_rx).await {
-            Ok(Ok(result)) => {
-                self.pending_count.fetch_sub(1, Ordering::Relaxed);
-                result
-            }
-            Ok(Err(_)) => {
-                self.pending_count.fetch_sub(1, Ordering::Relaxed);
-                Err(AppError::Internal("Request processing was cancelled".to_string()))
-            }
-            Err(_) => {
-                self.pending_count.fetch_sub(1, Ordering::Relaxed);
-                Err(AppError::Timeout("Request timed out".to_string()))
-            }
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(AppError::Internal("Request processing was cancelled".to_string())),
+            Err(_) => Err(AppError::Timeout("Request timed out".to_string())),
         }
     }
 
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.config.timeout_ms)
+    }
+
     /// Process requests from the queue
     async fn process_requests(
         mut request_rx: mpsc::Receiver<QueuedRequest>,
         load_balancer: Arc<LoadBalancer>,
         semaphore: Arc<Semaphore>,
-        timeout_ms: u64,
+        jobs: Arc<JobTable>,
+        (in_flight_count, processed_count): (Arc<AtomicU64>, Arc<AtomicU64>),
     ) {
         while let Some(queued) = request_rx.recv().await {
             let lb = load_balancer.clone();
             let sem = semaphore.clone();
-            let timeout = Duration::from_millis(timeout_ms);
+            let jobs = jobs.clone();
+            let in_flight_count = in_flight_count.clone();
+            let processed_count = processed_count.clone();
 
             tokio::spawn(async move {
+                let QueuedRequest {
+                    request,
+                    backend_name,
+                    deadline,
+                    job,
+                    ticket,
+                    mut response_tx,
+                } = queued;
+
                 // Acquire semaphore permit
                 let _permit = match sem.acquire().await {
                     Ok(permit) => permit,
                     Err(_) => {
-                        let _ = queued.response_tx.send(Err(AppError::Internal(
+                        let _ = response_tx.send(Err(AppError::Internal(
                             "Failed to acquire processing permit".to_string(),
                         )));
                         return;
                     }
                 };
+                drop(ticket);
 
-                // Select backend
-                let backend = match lb
-                    .select_backend(queued.backend_name.as_deref())
-                    .await
-                {
-                    Ok(b) => b,
-                    Err(e) => {
-                        let _ = queued.response_tx.send(Err(e));
-                        return;
+                // The submitter gave up or the job was cancelled while queued
+                if response_tx.is_closed() {
+                    debug!("Dropping abandoned request");
+                    return;
+                }
+                // Jobs record backend progress for clients following them
+                let progress = match &job {
+                    Some(id) => {
+                        jobs.start(id);
+                        let (jobs, id) = (jobs.clone(), id.clone());
+                        ProgressReporter::new(move |progress| jobs.set_progress(&id, progress))
                     }
+                    None => ProgressReporter::default(),
                 };
 
-                debug!(backend = %backend.name(), "Processing request");
-
-                // Generate images with timeout
-                let result = tokio::time::timeout(timeout, backend.generate(queued.request)).await;
-
-                let response = match result {
-                    Ok(Ok(resp)) => Ok(resp),
-                    Ok(Err(e)) => Err(e),
-                    Err(_) => Err(AppError::Timeout(format!(
-                        "Request to {} timed out",
-                        backend.name()
-                    ))),
+                // Retries and fallbacks all share the submitter's deadline;
+                // generation stops once nobody waits for the response
+                let response = tokio::select! {
+                    response = with_deadline(
+                        deadline,
+                        Self::route_and_generate(
+                            &lb,
+                            request,
+                            backend_name.as_deref(),
+                            deadline,
+                            &progress,
+                            (&in_flight_count, &processed_count),
+                        ),
+                    ) => response,
+                    _ = response_tx.closed() => {
+                        debug!(job = ?job, "Request abandoned, stopping generation");
+                        return;
+                    }
                 };
 
                 // Send response
-                let _ = queued.response_tx.send(response);
+                let _ = response_tx.send(response);
             });
         }
     }
 
+    /// Generate on the routed backend, moving down its fallback chain while
+    /// backends fail or are unavailable
+    async fn route_and_generate(
+        lb: &LoadBalancer,
+        request: GenerateRequest,
+        backend_name: Option<&str>,
+        deadline: Instant,
+        progress: &ProgressReporter,
+        counters: (&AtomicU64, &AtomicU64),
+    ) -> Result<GenerateResponse> {
+        let capabilities = request.required_capabilities();
+        // Model routes and fallbacks skip backends without the capabilities;
+        // an explicitly requested backend is kept so the client sees why it failed
+        let capable = |name: &String| {
+            backend_name == Some(name.as_str()) || lb.supports(name, &capabilities)
+        };
+        let mut candidates: VecDeque<String> = lb
+            .candidates(backend_name, request.model.as_deref())
+            .into_iter()
+            .filter(capable)
+            .collect();
+        // With no routed backend, let the load balancer pick any
+        let mut target = candidates.pop_front();
+
+        loop {
+            let result = match lb.select_backend_for(target.as_deref(), &capabilities).await {
+                Ok(backend) => {
+                    if target.is_none() {
+                        candidates.extend(lb.fallbacks(backend.name()).into_iter().filter(capable));
+                    }
+                    Self::generate_on(lb, backend, request.clone(), deadline, progress, counters).await
+                }
+                Err(e) => Err(e),
+            };
+
+            match (result, candidates.pop_front()) {
+                (Err(e), Some(next)) if should_fail_over(&e) => {
+                    warn!(error = %e, fallback = %next, "Backend unavailable, trying fallback");
+                    target = Some(next);
+                }
+                (result, _) => return result,
+            }
+        }
+    }
+
+    /// Generate images on one backend, recording load and outcome
+    async fn generate_on(
+        lb: &LoadBalancer,
+        backend: Arc<dyn ImageBackend>,
+        request: GenerateRequest,
+        deadline: Instant,
+        progress: &ProgressReporter,
+        (in_flight_count, processed_count): (&AtomicU64, &AtomicU64),
+    ) -> Result<GenerateResponse> {
+        debug!(backend = %backend.name(), "Processing request");
+
+        // Generate images within the request deadline
+        let in_flight = CountGuard::new(in_flight_count);
+        let load = lb.start_request(backend.name());
+        let result = tokio::time::timeout_at(deadline, backend.generate_with_progress(request, progress.clone())).await;
+        drop(in_flight);
+        processed_count.fetch_add(1, Ordering::Relaxed);
+
+        let response = match result {
+            Ok(Ok(mut resp)) => {
+                load.finish();
+                resp.backend = Some(backend.name().to_string());
+                Ok(resp)
+            }
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(AppError::Timeout(format!(
+                "Request to {} timed out",
+                backend.name()
+            ))),
+        };
+
+        // Feed the outcome back so failing backends leave rotation between checks;
+        // client errors say nothing about backend health
+        match &response {
+            Ok(_) => lb.record_outcome(backend.name(), true),
+            Err(AppError::BackendError(_) | AppError::HttpClient(_) | AppError::Timeout(_)) => {
+                lb.record_outcome(backend.name(), false)
+            }
+            Err(_) => {}
+        }
+
+        response
+    }
+
     /// Get the number of pending requests
     pub fn pending_count(&self) -> u64 {
         self.pending_count.load(Ordering::Relaxed)
     }
 
+    /// Get the number of requests currently being processed by a backend
+    pub fn in_flight_count(&self) -> u64 {
+        self.in_flight_count.load(Ordering::Relaxed)
+    }
+
     /// Get the number of processed requests
     pub fn processed_count(&self) -> u64 {
         self.processed_count.load(Ordering::Relaxed)
@@ -195,6 +483,7 @@ impl RequestQueue {
     pub fn stats(&self) -> QueueStats {
         QueueStats {
             pending: self.pending_count(),
+            in_flight: self.in_flight_count(),
             processed: self.processed_count(),
             max_queue_size: self.config.max_queue_size,
             max_concurrent: self.config.max_concurrent,
@@ -205,7 +494,9 @@ impl RequestQueue {
 /// Queue statistics
 #[derive(Debug, Clone)]
 pub struct QueueStats {
+    /// Submitted requests awaiting a response, including those in flight
     pub pending: u64,
+    pub in_flight: u64,
     pub processed: u64,
     pub max_queue_size: usize,
     pub max_concurrent: usize,
@@ -12,7 +12,7 @@ pub fn encode(data: &[u8]) -> String {
 pub fn decode(encoded: &str) -> Result<Vec<u8>> {
     // Handle data URL format (e.g., "data:image/png;base64,...")
     let data = if encoded.contains(",") {
-        encoded.split(',').last().unwrap_or(encoded)
+        encoded.split(',').next_back().unwrap_or(encoded)
     } else {
         encoded
     };
@@ -25,7 +25,7 @@ pub fn decode(encoded: &str) -> Result<Vec<u8>> {
 /// Check if a string is valid base64
 pub fn is_valid(data: &str) -> bool {
     let data = if data.contains(",") {
-        data.split(',').last().unwrap_or(data)
+        data.split(',').next_back().unwrap_or(data)
     } else {
         data
     };
@@ -26,7 +26,7 @@ impl FileHandler {
         if !self.storage_path.exists() {
             fs::create_dir_all(&self.storage_path)
                 .await
-                .map_err(|e| AppError::Io(e))?;
+                .map_err(AppError::Io)?;
             debug!(path = ?self.storage_path, "Created storage directory");
         }
         Ok(())
@@ -49,7 +49,7 @@ impl FileHandler {
         // Write file
         fs::write(&file_path, &image_data)
             .await
-            .map_err(|e| AppError::Io(e))?;
+            .map_err(AppError::Io)?;
 
         debug!(path = ?file_path, size = image_data.len(), "Saved image file");
 
@@ -65,9 +65,7 @@ impl FileHandler {
         let file_path = self.storage_path.join(&filename);
 
         // Write file
-        fs::write(&file_path, data)
-            .await
-            .map_err(|e| AppError::Io(e))?;
+        fs::write(&file_path, data).await.map_err(AppError::Io)?;
 
         debug!(path = ?file_path, size = data.len(), "Saved image file");
 
@@ -77,30 +75,26 @@ impl FileHandler {
     /// Read an image file
     pub async fn read(&self, filename: &str) -> Result<Vec<u8>> {
         let file_path = self.storage_path.join(filename);
-        
-        fs::read(&file_path)
-            .await
-            .map_err(|e| AppError::Io(e))
+
+        fs::read(&file_path).await.map_err(AppError::Io)
     }
 
     /// Delete an image file
     pub async fn delete(&self, filename: &str) -> Result<()> {
         let file_path = self.storage_path.join(filename);
-        
-        fs::remove_file(&file_path)
-            .await
-            .map_err(|e| AppError::Io(e))
+
+        fs::remove_file(&file_path).await.map_err(AppError::Io)
     }
 
     /// List all files in storage
     pub async fn list(&self) -> Result<Vec<String>> {
         let mut files = Vec::new();
-        
+
         let mut entries = fs::read_dir(&self.storage_path)
             .await
-            .map_err(|e| AppError::Io(e))?;
+            .map_err(AppError::Io)?;
 
-        while let Some(entry) = entries.next_entry().await.map_err(|e| AppError::Io(e))? {
+        while let Some(entry) = entries.next_entry().await.map_err(AppError::Io)? {
             if let Some(name) = entry.file_name().to_str() {
                 files.push(name.to_string());
             }
@@ -122,17 +116,15 @@ impl FileHandler {
 
         let mut entries = fs::read_dir(&self.storage_path)
             .await
-            .map_err(|e| AppError::Io(e))?;
+            .map_err(AppError::Io)?;
 
-        while let Some(entry) = entries.next_entry().await.map_err(|e| AppError::Io(e))? {
+        while let Some(entry) = entries.next_entry().await.map_err(AppError::Io)? {
             if let Ok(metadata) = entry.metadata().await {
                 if let Ok(modified) = metadata.modified() {
                     if let Ok(age) = now.duration_since(modified) {
-                        if age > max_age {
-                            if fs::remove_file(entry.path()).await.is_ok() {
-                                deleted += 1;
-                                debug!(path = ?entry.path(), "Deleted old file");
-                            }
+                        if age > max_age && fs::remove_file(entry.path()).await.is_ok() {
+                            deleted += 1;
+                            debug!(path = ?entry.path(), "Deleted old file");
                         }
                     }
                 }
@@ -144,7 +136,7 @@ impl FileHandler {
 }
 
 /// Detect image format from binary data using magic bytes
-fn detect_image_format(data: &[u8]) -> Option<&'static str> {
+pub fn detect_image_format(data: &[u8]) -> Option<&'static str> {
     if data.len() < 8 {
         return None;
     }
@@ -193,4 +185,3 @@ mod tests {
         assert_eq!(detect_image_format(&jpeg_header), Some("jpg"));
     }
 }
-
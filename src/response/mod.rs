@@ -19,6 +19,7 @@ pub enum ResponseFormat {
 }
 
 impl ResponseFormat {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "b64_json" | "base64" => Self::Base64Json,
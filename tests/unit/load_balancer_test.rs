@@ -1,39 +1,60 @@
 //! Unit tests for load balancer
 
-use generative_img_serving::backend::registry::BackendRegistry;
-use generative_img_serving::config::BackendConfig;
-use generative_img_serving::gateway::load_balancer::{LoadBalancer, LoadBalancingStrategy};
+use gen_serving_gateway::backend::registry::BackendRegistry;
+use gen_serving_gateway::config::BackendConfig;
+use gen_serving_gateway::gateway::health_check::HealthCheckManager;
+use gen_serving_gateway::gateway::load_balancer::{LoadBalancer, LoadBalancingStrategy};
+use gen_serving_gateway::AppError;
 use std::sync::Arc;
 
 fn create_test_config(name: &str, weight: u32) -> BackendConfig {
     BackendConfig {
         name: name.to_string(),
-        protocol: "http".to_string(),
         endpoints: vec![format!("http://localhost:{}", 8001 + weight as u16)],
         health_check_path: "/health".to_string(),
         health_check_interval_secs: 30,
         timeout_ms: 60000,
         weight,
         enabled: true,
+        ..Default::default()
     }
 }
 
+/// Registry with `count` backends named `backend-1..=count`
+async fn create_registry(count: usize) -> Arc<BackendRegistry> {
+    let registry = Arc::new(BackendRegistry::new());
+    for i in 1..=count {
+        let config = create_test_config(&format!("backend-{}", i), 1);
+        registry.add_backend(config).await.unwrap();
+    }
+    registry
+}
+
+/// Mark a backend unhealthy by reporting enough consecutive failures
+fn mark_unhealthy(health: &HealthCheckManager, name: &str) {
+    for _ in 0..3 {
+        health.record_outcome(name, false);
+    }
+    assert!(!health.is_healthy(name));
+}
+
 #[tokio::test]
 async fn test_load_balancer_creation() {
     let registry = Arc::new(BackendRegistry::new());
     let lb = LoadBalancer::new(registry);
-    
+
     assert_eq!(lb.strategy(), LoadBalancingStrategy::RoundRobin);
+    assert!(!lb.fail_open());
 }
 
 #[tokio::test]
 async fn test_load_balancer_set_strategy() {
     let registry = Arc::new(BackendRegistry::new());
     let lb = LoadBalancer::new(registry);
-    
+
     lb.set_strategy(LoadBalancingStrategy::WeightedRoundRobin);
     assert_eq!(lb.strategy(), LoadBalancingStrategy::WeightedRoundRobin);
-    
+
     lb.set_strategy(LoadBalancingStrategy::Random);
     assert_eq!(lb.strategy(), LoadBalancingStrategy::Random);
 }
@@ -42,7 +63,7 @@ async fn test_load_balancer_set_strategy() {
 async fn test_load_balancer_no_backends() {
     let registry = Arc::new(BackendRegistry::new());
     let lb = LoadBalancer::new(registry);
-    
+
     let result = lb.select_backend(None).await;
     assert!(result.is_err());
 }
@@ -50,12 +71,12 @@ async fn test_load_balancer_no_backends() {
 #[tokio::test]
 async fn test_load_balancer_specific_backend() {
     let registry = Arc::new(BackendRegistry::new());
-    
+
     let config = create_test_config("test-backend", 1);
     registry.add_backend(config).await.unwrap();
-    
+
     let lb = LoadBalancer::new(registry);
-    
+
     let result = lb.select_backend(Some("test-backend")).await;
     assert!(result.is_ok());
     assert_eq!(result.unwrap().name(), "test-backend");
@@ -65,35 +86,210 @@ async fn test_load_balancer_specific_backend() {
 async fn test_load_balancer_nonexistent_backend() {
     let registry = Arc::new(BackendRegistry::new());
     let lb = LoadBalancer::new(registry);
-    
+
     let result = lb.select_backend(Some("nonexistent")).await;
     assert!(result.is_err());
 }
 
 #[tokio::test]
 async fn test_round_robin_distribution() {
-    let registry = Arc::new(BackendRegistry::new());
-    
-    // Add multiple backends
-    for i in 1..=3 {
-        let config = create_test_config(&format!("backend-{}", i), 1);
-        registry.add_backend(config).await.unwrap();
-    }
-    
+    let registry = create_registry(3).await;
+
     let lb = LoadBalancer::new(registry);
     lb.set_strategy(LoadBalancingStrategy::RoundRobin);
-    
+
     // Track selections
     let mut selections = std::collections::HashMap::new();
-    
+
     for _ in 0..30 {
         let backend = lb.select_backend(None).await.unwrap();
         *selections.entry(backend.name().to_string()).or_insert(0) += 1;
     }
-    
+
     // Each backend should be selected roughly equally
-    for (_, count) in &selections {
+    for count in selections.values() {
         assert!(*count >= 8 && *count <= 12, "Expected roughly equal distribution");
     }
 }
 
+#[tokio::test]
+async fn test_unhealthy_backend_is_skipped() {
+    let registry = create_registry(3).await;
+    let health = Arc::new(HealthCheckManager::new(registry.clone()));
+    let lb = LoadBalancer::new(registry).with_health_manager(health.clone());
+
+    mark_unhealthy(&health, "backend-2");
+
+    for _ in 0..30 {
+        let backend = lb.select_backend(None).await.unwrap();
+        assert_ne!(backend.name(), "backend-2");
+    }
+}
+
+#[tokio::test]
+async fn test_backend_rejoins_after_recovery() {
+    let registry = create_registry(2).await;
+    let health = Arc::new(HealthCheckManager::new(registry.clone()));
+    let lb = LoadBalancer::new(registry).with_health_manager(health.clone());
+
+    mark_unhealthy(&health, "backend-1");
+    health.record_outcome("backend-1", true);
+    health.record_outcome("backend-1", true);
+    assert!(health.is_healthy("backend-1"));
+
+    let mut selected = std::collections::HashSet::new();
+    for _ in 0..10 {
+        selected.insert(lb.select_backend(None).await.unwrap().name().to_string());
+    }
+    assert!(selected.contains("backend-1"));
+}
+
+#[tokio::test]
+async fn test_all_unhealthy_fails_closed_by_default() {
+    let registry = create_registry(2).await;
+    let health = Arc::new(HealthCheckManager::new(registry.clone()));
+    let lb = LoadBalancer::new(registry).with_health_manager(health.clone());
+
+    mark_unhealthy(&health, "backend-1");
+    mark_unhealthy(&health, "backend-2");
+
+    let result = lb.select_backend(None).await;
+    assert!(matches!(result, Err(AppError::NoHealthyBackends(_))));
+}
+
+#[tokio::test]
+async fn test_all_unhealthy_fails_open() {
+    let registry = create_registry(2).await;
+    let health = Arc::new(HealthCheckManager::new(registry.clone()));
+    let lb = LoadBalancer::new(registry).with_health_manager(health.clone());
+    lb.set_fail_open(true);
+
+    mark_unhealthy(&health, "backend-1");
+    mark_unhealthy(&health, "backend-2");
+
+    let mut selected = std::collections::HashSet::new();
+    for _ in 0..10 {
+        selected.insert(lb.select_backend(None).await.unwrap().name().to_string());
+    }
+    assert_eq!(selected.len(), 2);
+}
+
+#[tokio::test]
+async fn test_fail_open_only_applies_when_all_unhealthy() {
+    let registry = create_registry(2).await;
+    let health = Arc::new(HealthCheckManager::new(registry.clone()));
+    let lb = LoadBalancer::new(registry).with_health_manager(health.clone());
+    lb.set_fail_open(true);
+
+    mark_unhealthy(&health, "backend-1");
+
+    for _ in 0..10 {
+        assert_eq!(lb.select_backend(None).await.unwrap().name(), "backend-2");
+    }
+}
+
+#[tokio::test]
+async fn test_specific_unhealthy_backend() {
+    let registry = create_registry(2).await;
+    let health = Arc::new(HealthCheckManager::new(registry.clone()));
+    let lb = LoadBalancer::new(registry).with_health_manager(health.clone());
+
+    mark_unhealthy(&health, "backend-1");
+
+    let result = lb.select_backend(Some("backend-1")).await;
+    assert!(matches!(result, Err(AppError::NoHealthyBackends(_))));
+
+    lb.set_fail_open(true);
+    let result = lb.select_backend(Some("backend-1")).await;
+    assert_eq!(result.unwrap().name(), "backend-1");
+}
+
+#[tokio::test]
+async fn test_health_check_failure_removes_backend() {
+    // Nothing listens on port 9, so the health check fails
+    let registry = Arc::new(BackendRegistry::new());
+    registry.add_backend(BackendConfig {
+        endpoints: vec!["http://127.0.0.1:9".to_string()],
+        ..create_test_config("down", 1)
+    }).await.unwrap();
+    registry.add_backend(create_test_config("up", 1)).await.unwrap();
+
+    let health = Arc::new(HealthCheckManager::new(registry.clone()));
+    let lb = LoadBalancer::new(registry).with_health_manager(health.clone());
+
+    assert_eq!(health.check_now("down").await, Some(false));
+
+    for _ in 0..10 {
+        assert_eq!(lb.select_backend(None).await.unwrap().name(), "up");
+    }
+}
+
+#[tokio::test]
+async fn test_least_connections() {
+    let registry = create_registry(3).await;
+    let lb = LoadBalancer::new(registry);
+    lb.set_strategy(LoadBalancingStrategy::LeastConnections);
+
+    let busy = [
+        lb.start_request("backend-1"),
+        lb.start_request("backend-1"),
+        lb.start_request("backend-2"),
+    ];
+
+    for _ in 0..5 {
+        assert_eq!(lb.select_backend(None).await.unwrap().name(), "backend-3");
+    }
+
+    drop(busy);
+    assert_eq!(lb.in_flight("backend-1"), 0);
+}
+
+#[tokio::test]
+async fn test_least_latency_prefers_faster_backend() {
+    let registry = create_registry(2).await;
+    let lb = LoadBalancer::new(registry);
+    lb.set_strategy(LoadBalancingStrategy::LeastLatency);
+
+    let slow = lb.start_request("backend-1");
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    slow.finish();
+    lb.start_request("backend-2").finish();
+
+    assert!(lb.latency_ms("backend-1").unwrap() > lb.latency_ms("backend-2").unwrap());
+    for _ in 0..5 {
+        assert_eq!(lb.select_backend(None).await.unwrap().name(), "backend-2");
+    }
+}
+
+#[tokio::test]
+async fn test_power_of_two_choices_avoids_busy_backend() {
+    let registry = create_registry(2).await;
+    let lb = LoadBalancer::new(registry);
+    lb.set_strategy(LoadBalancingStrategy::PowerOfTwoChoices);
+
+    let _busy = lb.start_request("backend-1");
+
+    for _ in 0..10 {
+        assert_eq!(lb.select_backend(None).await.unwrap().name(), "backend-2");
+    }
+}
+
+#[tokio::test]
+async fn test_backend_endpoint_strategy_from_config() {
+    let registry = Arc::new(BackendRegistry::new());
+    let mut config = create_test_config("p2c", 1);
+    config.load_balancer.strategy = "power_of_two_choices".to_string();
+    registry.add_backend(config).await.unwrap();
+    registry.add_backend(create_test_config("default", 2)).await.unwrap();
+
+    let backend = registry.get("p2c").unwrap();
+    assert_eq!(backend.endpoint_strategy(), LoadBalancingStrategy::PowerOfTwoChoices);
+    assert_eq!(registry.get("default").unwrap().endpoint_strategy(), LoadBalancingStrategy::RoundRobin);
+
+    backend.set_endpoint_strategy(LoadBalancingStrategy::LeastLatency);
+    assert_eq!(backend.endpoint_strategy(), LoadBalancingStrategy::LeastLatency);
+
+    let mut invalid = create_test_config("invalid", 3);
+    invalid.load_balancer.strategy = "fastest".to_string();
+    assert!(matches!(registry.add_backend(invalid).await, Err(AppError::Config(_))));
+}
@@ -1,7 +1,52 @@
 //! Unit tests for request queue and batcher
 
-use generative_img_serving::queue::batcher::{Batcher, BatchConfig};
-use generative_img_serving::queue::request_queue::QueueConfig;
+use gen_serving_gateway::backend::registry::BackendRegistry;
+use gen_serving_gateway::backend::traits::GenerateRequest;
+use gen_serving_gateway::config::{BackendConfig, RoutingConfig};
+use gen_serving_gateway::gateway::load_balancer::LoadBalancer;
+use gen_serving_gateway::gateway::router::RoutingRules;
+use gen_serving_gateway::queue::batcher::{Batcher, BatchConfig};
+use gen_serving_gateway::queue::jobs::JobStatus;
+use gen_serving_gateway::queue::request_queue::{QueueConfig, RequestQueue};
+use std::sync::Arc;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn create_test_config(name: &str, endpoint: String) -> BackendConfig {
+    BackendConfig {
+        name: name.to_string(),
+        endpoints: vec![endpoint],
+        health_check_path: "/health".to_string(),
+        timeout_ms: 60000,
+        weight: 1,
+        enabled: true,
+        ..Default::default()
+    }
+}
+
+fn create_test_request() -> GenerateRequest {
+    GenerateRequest {
+        prompt: "a cat".to_string(),
+        n: 1,
+        response_format: "b64_json".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Mock image backend answering generations after `delay_ms`
+async fn start_image_server(delay_ms: u64) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/images/generations"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"data": [{"b64_json": "aGVsbG8="}]}))
+                .set_delay(std::time::Duration::from_millis(delay_ms)),
+        )
+        .mount(&server)
+        .await;
+    server
+}
 
 #[test]
 fn test_queue_config_defaults() {
@@ -55,3 +100,99 @@ async fn test_batcher_disabled() {
     assert!(!batcher.should_process().await);
 }
 
+#[tokio::test]
+async fn test_jobs_complete_and_cancel() {
+    let server = start_image_server(200).await;
+
+    let registry = Arc::new(BackendRegistry::new());
+    registry.add_backend(create_test_config("sd", server.uri())).await.unwrap();
+    let queue = RequestQueue::new(Arc::new(LoadBalancer::new(registry)));
+    let request = create_test_request();
+
+    let job = queue.submit_job(request.clone(), None).await.unwrap();
+    assert!(!job.status.is_finished());
+    let mut finished = None;
+    for _ in 0..50 {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let current = queue.job(&job.id).unwrap();
+        if current.status.is_finished() {
+            finished = Some(current);
+            break;
+        }
+    }
+    let finished = finished.expect("job did not finish");
+    assert_eq!(finished.status, JobStatus::Succeeded);
+    assert_eq!(finished.response.unwrap().backend.as_deref(), Some("sd"));
+
+    // A cancelled job stays cancelled and no longer counts as pending
+    let job = queue.submit_job(request, None).await.unwrap();
+    assert_eq!(queue.cancel_job(&job.id).unwrap().status, JobStatus::Cancelled);
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    assert_eq!(queue.job(&job.id).unwrap().status, JobStatus::Cancelled);
+    assert_eq!(queue.pending_count(), 0);
+    assert!(queue.job("imgjob-missing").is_none());
+}
+
+#[tokio::test]
+async fn test_queued_jobs_report_position() {
+    let server = start_image_server(300).await;
+
+    let registry = Arc::new(BackendRegistry::new());
+    registry.add_backend(create_test_config("sd", server.uri())).await.unwrap();
+    let queue = RequestQueue::with_config(
+        Arc::new(LoadBalancer::new(registry)),
+        QueueConfig {
+            max_concurrent: 1,
+            ..Default::default()
+        },
+    );
+    let request = create_test_request();
+
+    let mut jobs = Vec::new();
+    for _ in 0..3 {
+        jobs.push(queue.submit_job(request.clone(), None).await.unwrap());
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    // The first job holds the only slot; the others wait behind it in order
+    assert_eq!(queue.job(&jobs[0].id).unwrap().status, JobStatus::Running);
+    assert_eq!(queue.queue_position(&jobs[0].id), None);
+    assert_eq!(queue.queue_position(&jobs[1].id), Some(0));
+    assert_eq!(queue.queue_position(&jobs[2].id), Some(1));
+
+    // Positions move up as jobs leave the line
+    queue.cancel_job(&jobs[0].id);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(queue.queue_position(&jobs[1].id), None);
+    assert_eq!(queue.queue_position(&jobs[2].id), Some(0));
+}
+
+#[tokio::test]
+async fn test_queue_falls_back_when_primary_fails() {
+    let server = start_image_server(0).await;
+
+    // Nothing listens on port 9, so the primary fails to connect
+    let registry = Arc::new(BackendRegistry::new());
+    registry
+        .add_backend(create_test_config("primary", "http://127.0.0.1:9".to_string()))
+        .await
+        .unwrap();
+    registry.add_backend(create_test_config("fallback", server.uri())).await.unwrap();
+
+    let lb = Arc::new(LoadBalancer::new(registry));
+    lb.set_routing(RoutingRules::from_config(&RoutingConfig {
+        model_mappings: [("sdxl".to_string(), "primary".to_string())].into(),
+        fallbacks: [("primary".to_string(), vec!["fallback".to_string()])].into(),
+        ..Default::default()
+    }));
+    let queue = RequestQueue::new(lb);
+
+    let request = GenerateRequest {
+        model: Some("sdxl".to_string()),
+        ..create_test_request()
+    };
+    let response = queue.submit(request, None).await.unwrap();
+
+    assert_eq!(response.backend.as_deref(), Some("fallback"));
+    assert_eq!(response.images.len(), 1);
+}